@@ -17,10 +17,15 @@
 //! Common types between legacy and current desub versions.
 
 #![forbid(unsafe_code)]
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use sp_core::crypto::AccountId32;
 #[deny(unused)]
 use sp_runtime::MultiAddress as SubstrateMultiAddress;
+use std::fmt;
+
+mod value_path;
+
+pub use value_path::{PathSegment, ValuePath};
 
 /// Spec Version type defined in the runtime of a chain.
 pub type SpecVersion = u32;
@@ -41,3 +46,40 @@ pub enum RemoteAddress {
 	/// It's a 20 byte representation.
 	Address20([u8; 20]),
 }
+
+/// An extrinsic which could not be decoded. Each extrinsic in a block is prefixed with its length, and so
+/// we can carry on decoding the rest of them when one fails, handing this back in its place.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(bound(serialize = "E: fmt::Display"))]
+pub struct ExtrinsicDecodeFailure<E> {
+	/// The position of the extrinsic in the block.
+	pub index: usize,
+	/// The SCALE encoded bytes of the extrinsic, not including its length prefix.
+	pub bytes: Vec<u8>,
+	/// The reason that the extrinsic could not be decoded.
+	#[serde(serialize_with = "serialize_display")]
+	pub error: E,
+}
+
+impl<E> ExtrinsicDecodeFailure<E> {
+	/// Convert the error into some other type.
+	pub fn map_err<F>(self, f: impl FnOnce(E) -> F) -> ExtrinsicDecodeFailure<F> {
+		ExtrinsicDecodeFailure { index: self.index, bytes: self.bytes, error: f(self.error) }
+	}
+}
+
+impl<E: fmt::Display> fmt::Display for ExtrinsicDecodeFailure<E> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Failed to decode extrinsic {}: {}", self.index, self.error)
+	}
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for ExtrinsicDecodeFailure<E> {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		Some(&self.error)
+	}
+}
+
+fn serialize_display<E: fmt::Display, S: Serializer>(error: &E, serializer: S) -> Result<S::Ok, S::Error> {
+	serializer.collect_str(error)
+}
@@ -0,0 +1,120 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use serde::Serialize;
+use std::fmt;
+
+/// The path to a value nested somewhere inside another, made up of field names, variant names and `[n]`
+/// indexes (for the items in a sequence, or the unnamed fields of a tuple or composite). This is rendered
+/// like `calls[1].Balances.transfer.value`.
+///
+/// Errors are found in the innermost value first, so paths are built up with [`ValuePath::prepend`] as
+/// the error makes its way back out to the outermost value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct ValuePath(Vec<String>);
+
+impl ValuePath {
+	/// Create a new, empty path.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a step to the front of the path.
+	pub fn prepend(&mut self, segment: impl Into<PathSegment>) {
+		let segment = match segment.into() {
+			PathSegment::Name(name) => name,
+			PathSegment::Index(idx) => format!("[{}]", idx),
+		};
+		self.0.insert(0, segment);
+	}
+
+	/// Each step in the path, where indexes are given in the form `[n]`.
+	pub fn segments(&self) -> &[String] {
+		&self.0
+	}
+
+	/// Is the path empty (ie does it point to the outermost value)?
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}
+
+impl fmt::Display for ValuePath {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (idx, segment) in self.0.iter().enumerate() {
+			if idx > 0 && !segment.starts_with('[') {
+				write!(f, ".")?;
+			}
+			write!(f, "{}", segment)?;
+		}
+		Ok(())
+	}
+}
+
+/// A single step in a [`ValuePath`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+	/// The name of a field or variant.
+	Name(String),
+	/// The position of an item in a sequence, or of an unnamed field.
+	Index(usize),
+}
+
+impl From<usize> for PathSegment {
+	fn from(idx: usize) -> Self {
+		PathSegment::Index(idx)
+	}
+}
+
+impl From<String> for PathSegment {
+	fn from(name: String) -> Self {
+		PathSegment::Name(name)
+	}
+}
+
+impl From<&String> for PathSegment {
+	fn from(name: &String) -> Self {
+		PathSegment::Name(name.clone())
+	}
+}
+
+impl From<&str> for PathSegment {
+	fn from(name: &str) -> Self {
+		PathSegment::Name(name.to_owned())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn indexes_are_bracketed() {
+		let mut path = ValuePath::new();
+		path.prepend("value");
+		path.prepend("transfer");
+		path.prepend(1);
+		path.prepend("calls");
+		assert_eq!(path.to_string(), "calls[1].transfer.value");
+		assert_eq!(path.segments(), &["calls", "[1]", "transfer", "value"]);
+
+		let mut path = ValuePath::new();
+		path.prepend(0);
+		path.prepend(2);
+		assert_eq!(path.to_string(), "[2][0]");
+	}
+}
@@ -0,0 +1,260 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{decode_value_by_id, Address, EventRecord, Extrinsic, ExtrinsicKind, StorageEntry, StorageEntryType};
+use super::{StorageHasher, StorageMapKey};
+use crate::metadata::Metadata;
+use crate::value::{self, Composite, Primitive, Value, ValueDef, Variant};
+use crate::TypeId;
+use codec::Encode;
+use sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+use std::borrow::Cow;
+
+/// The SS58 prefix used when the metadata doesn't tell us which one the chain uses.
+pub const DEFAULT_SS58_PREFIX: u16 = 42;
+
+/// Render the account IDs found in decoded values as strings, rather than as arrays of bytes. Account IDs are
+/// found by their type paths: `AccountId32`s (and the `Address32` variant of a `MultiAddress`) are rendered
+/// as SS58 addresses, and `AccountId20`s (and the `Address20` variant of a `MultiAddress`) as EIP-55
+/// checksummed hex strings.
+///
+/// The rendered account IDs are [`Primitive::Str`] values, and so values which have been formatted
+/// like this are intended for output, and can no longer be encoded back to the types they came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountFormat {
+	ss58_prefix: u16,
+}
+
+impl AccountFormat {
+	/// Render account IDs using the SS58 network prefix given.
+	pub fn new(ss58_prefix: u16) -> Self {
+		AccountFormat { ss58_prefix }
+	}
+
+	/// Render account IDs using the SS58 network prefix given by the `System.SS58Prefix` constant
+	/// in the metadata, or [`DEFAULT_SS58_PREFIX`] if there isn't one.
+	pub fn from_metadata(metadata: &Metadata) -> Self {
+		let ss58_prefix = metadata
+			.constant("System", "SS58Prefix")
+			.and_then(|c| c.value().ok())
+			.and_then(|v| value::from_value::<_, u16>(v).ok())
+			.unwrap_or(DEFAULT_SS58_PREFIX);
+		AccountFormat { ss58_prefix }
+	}
+
+	/// The SS58 network prefix that 32 byte account IDs are rendered with.
+	pub fn ss58_prefix(&self) -> u16 {
+		self.ss58_prefix
+	}
+
+	/// Render any account IDs found in the value given.
+	pub fn format_value(&self, value: Value<TypeId>, metadata: &Metadata) -> Value<TypeId> {
+		let ty = metadata.resolve(value.context);
+		let name = ty.and_then(|ty| ty.path().segments().last()).map(|s| s.as_str());
+
+		let rendered = match name {
+			Some("AccountId32") => account_bytes(&value.value).map(|bytes| self.ss58(bytes)),
+			Some("AccountId20") => account_bytes(&value.value).map(eip55),
+			_ => None,
+		};
+		if let Some(rendered) = rendered {
+			return Value { value: ValueDef::Primitive(Primitive::Str(rendered)), context: value.context };
+		}
+
+		let inner = match value.value {
+			ValueDef::Composite(composite) => ValueDef::Composite(self.format_composite(composite, metadata)),
+			ValueDef::Variant(variant) if name == Some("MultiAddress") => {
+				ValueDef::Variant(self.format_multi_address(variant, metadata))
+			}
+			ValueDef::Variant(Variant { name, values }) => {
+				ValueDef::Variant(Variant { name, values: self.format_composite(values, metadata) })
+			}
+			other => other,
+		};
+		Value { value: inner, context: value.context }
+	}
+
+	/// Render any account IDs found in the call arguments, address and extensions of an extrinsic.
+	/// A `MultiAddress` that the extrinsic is sent from is turned into a [`Value`] (see [`Address::Value`]),
+	/// so that it is rendered in the same way as any other `MultiAddress`.
+	pub fn format_extrinsic<'a>(&self, mut ext: Extrinsic<'a>, metadata: &Metadata) -> Extrinsic<'a> {
+		ext.call_data.arguments = self.format_values(ext.call_data.arguments, metadata);
+		if let ExtrinsicKind::General { extensions, .. } = &mut ext.kind {
+			*extensions = self.format_extensions(std::mem::take(extensions), metadata);
+		}
+		ext.signature = ext.signature.map(|mut sig| {
+			sig.address = self.format_address(sig.address, metadata);
+			sig.extensions = self.format_extensions(sig.extensions, metadata);
+			sig
+		});
+		ext
+	}
+
+	/// Render any account IDs found in the fields of an event.
+	pub fn format_event<'a>(&self, mut event: EventRecord<'a>, metadata: &Metadata) -> EventRecord<'a> {
+		event.event.arguments = self.format_values(event.event.arguments, metadata);
+		event
+	}
+
+	/// Render any account IDs found in the map keys of a storage entry.
+	pub fn format_storage_entry<'m, 'b>(
+		&self,
+		mut entry: StorageEntry<'m, 'b>,
+		metadata: &Metadata,
+	) -> StorageEntry<'m, 'b> {
+		if let StorageEntryType::Map(keys) = entry.details {
+			let keys = keys
+				.into_iter()
+				.map(|key| StorageMapKey { hasher: self.format_hasher(key.hasher, metadata), ..key })
+				.collect();
+			entry.details = StorageEntryType::Map(keys);
+		}
+		entry
+	}
+
+	fn format_values(&self, values: Vec<Value<TypeId>>, metadata: &Metadata) -> Vec<Value<TypeId>> {
+		values.into_iter().map(|v| self.format_value(v, metadata)).collect()
+	}
+
+	fn format_extensions<'a>(
+		&self,
+		extensions: Vec<(Cow<'a, str>, Value<TypeId>)>,
+		metadata: &Metadata,
+	) -> Vec<(Cow<'a, str>, Value<TypeId>)> {
+		extensions.into_iter().map(|(name, v)| (name, self.format_value(v, metadata))).collect()
+	}
+
+	fn format_composite(&self, composite: Composite<TypeId>, metadata: &Metadata) -> Composite<TypeId> {
+		match composite {
+			Composite::Named(vals) => {
+				Composite::Named(vals.into_iter().map(|(name, v)| (name, self.format_value(v, metadata))).collect())
+			}
+			Composite::Unnamed(vals) => Composite::Unnamed(self.format_values(vals, metadata)),
+		}
+	}
+
+	// The `Address32` and `Address20` variants of a `MultiAddress` hold plain byte arrays rather than
+	// account ID types, so we render those here, and anything else as normal.
+	fn format_multi_address(&self, variant: Variant<TypeId>, metadata: &Metadata) -> Variant<TypeId> {
+		let rendered = match (variant.name.as_str(), &variant.values) {
+			("Address32", Composite::Unnamed(vals)) if vals.len() == 1 => {
+				account_bytes(&vals[0].value).map(|bytes| (self.ss58(bytes), vals[0].context))
+			}
+			("Address20", Composite::Unnamed(vals)) if vals.len() == 1 => {
+				account_bytes(&vals[0].value).map(|bytes| (eip55(bytes), vals[0].context))
+			}
+			_ => None,
+		};
+		match rendered {
+			Some((rendered, context)) => Variant {
+				name: variant.name,
+				values: Composite::Unnamed(vec![Value {
+					value: ValueDef::Primitive(Primitive::Str(rendered)),
+					context,
+				}]),
+			},
+			None => Variant { name: variant.name, values: self.format_composite(variant.values, metadata) },
+		}
+	}
+
+	fn format_address(&self, address: Address, metadata: &Metadata) -> Address {
+		match address {
+			Address::Value(value) => Address::Value(self.format_value(value, metadata)),
+			Address::MultiAddress(address) => {
+				let value = metadata
+					.extrinsic()
+					.address_type()
+					.and_then(|ty| decode_value_by_id(metadata, ty, &mut &*address.encode()).ok());
+				match value {
+					Some(value) => Address::Value(self.format_value(value, metadata)),
+					None => Address::MultiAddress(address),
+				}
+			}
+		}
+	}
+
+	fn format_hasher(&self, hasher: StorageHasher, metadata: &Metadata) -> StorageHasher {
+		match hasher {
+			StorageHasher::Blake2_128Concat(v) => StorageHasher::Blake2_128Concat(self.format_value(v, metadata)),
+			StorageHasher::Twox64Concat(v) => StorageHasher::Twox64Concat(self.format_value(v, metadata)),
+			StorageHasher::Identity(v) => StorageHasher::Identity(self.format_value(v, metadata)),
+			other => other,
+		}
+	}
+
+	fn ss58(&self, bytes: [u8; 32]) -> String {
+		AccountId32::from(bytes).to_ss58check_with_version(Ss58AddressFormat::custom(self.ss58_prefix))
+	}
+}
+
+/// Account IDs are usually newtype wrappers around byte arrays, so we look through any composites with
+/// a single field until we find an array of exactly `N` bytes.
+fn account_bytes<const N: usize>(value: &ValueDef<TypeId>) -> Option<[u8; N]> {
+	let vals = match value {
+		ValueDef::Composite(Composite::Unnamed(vals)) => vals,
+		ValueDef::Composite(Composite::Named(vals)) if vals.len() == 1 => return account_bytes(&vals[0].1.value),
+		_ => return None,
+	};
+	if vals.len() == 1 && N != 1 {
+		return account_bytes(&vals[0].value);
+	}
+	if vals.len() != N {
+		return None;
+	}
+
+	let mut bytes = [0; N];
+	for (byte, val) in bytes.iter_mut().zip(vals) {
+		match val.value {
+			ValueDef::Primitive(Primitive::U8(b)) => *byte = b,
+			_ => return None,
+		}
+	}
+	Some(bytes)
+}
+
+/// Hex encode a 20 byte account ID, with the checksum described in EIP-55: each letter is uppercased
+/// if the corresponding nibble of the keccak256 hash of the lowercase hex string is 8 or more.
+fn eip55(bytes: [u8; 20]) -> String {
+	let hex = hex::encode(bytes);
+	let hash = sp_core::keccak_256(hex.as_bytes());
+
+	let mut out = String::with_capacity(2 + hex.len());
+	out.push_str("0x");
+	for (idx, c) in hex.chars().enumerate() {
+		let nibble = if idx % 2 == 0 { hash[idx / 2] >> 4 } else { hash[idx / 2] & 0x0f };
+		out.push(if nibble >= 8 { c.to_ascii_uppercase() } else { c });
+	}
+	out
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn eip55_checksums() {
+		// Test vectors from EIP-55:
+		for expected in [
+			"0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+			"0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+			"0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+			"0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+		] {
+			let bytes: [u8; 20] = hex::decode(&expected[2..]).unwrap().try_into().unwrap();
+			assert_eq!(eip55(bytes), expected);
+		}
+	}
+}
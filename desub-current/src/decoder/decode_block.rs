@@ -0,0 +1,134 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{DecodeError, Extrinsic};
+use crate::metadata::Metadata;
+use codec::{Compact, Decode};
+use serde::Serialize;
+
+/// The ID of a consensus engine, for example `*b"BABE"`.
+pub type ConsensusEngineId = [u8; 4];
+
+/// Decode a SCALE encoded block header. This takes the same shape as `sp_runtime::generic::Header`:
+/// the parent hash, the compact encoded block number, the state and extrinsics roots, and then the
+/// digest.
+pub(super) fn decode_header(data: &mut &[u8]) -> Result<Header, DecodeError> {
+	let parent_hash = <[u8; 32]>::decode(data)?;
+	let number = <Compact<u64>>::decode(data)?.0;
+	let state_root = <[u8; 32]>::decode(data)?;
+	let extrinsics_root = <[u8; 32]>::decode(data)?;
+
+	let len = <Compact<u32>>::decode(data)?.0;
+	let digest = (0..len).map(|_| decode_digest_item(data)).collect::<Result<_, _>>()?;
+
+	Ok(Header { parent_hash, number, state_root, extrinsics_root, digest })
+}
+
+/// Decode a single digest item. This has the same shape as `sp_runtime::generic::DigestItem`.
+fn decode_digest_item(data: &mut &[u8]) -> Result<DigestItem, DecodeError> {
+	let item = match u8::decode(data)? {
+		0 => DigestItem::Other(Decode::decode(data)?),
+		4 => DigestItem::Consensus(Decode::decode(data)?, Decode::decode(data)?),
+		5 => DigestItem::Seal(Decode::decode(data)?, Decode::decode(data)?),
+		6 => DigestItem::PreRuntime(Decode::decode(data)?, Decode::decode(data)?),
+		8 => DigestItem::RuntimeEnvironmentUpdated,
+		other => return Err(DecodeError::CannotDecodeDigestItem(other)),
+	};
+	Ok(item)
+}
+
+/// Decode a SCALE encoded block. This is a header followed by a vector of extrinsics,
+/// each prefixed with its compact encoded length.
+pub(super) fn decode_block<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<Block<'a>, DecodeError> {
+	let header = decode_header(data)?;
+	let extrinsics = super::decode_extrinsics(metadata, data).map_err(|(_, e)| e)?;
+
+	Ok(Block { header, extrinsics })
+}
+
+/// Decode a SCALE encoded signed block. This is a block, followed by any justifications
+/// for it (an optional vector of consensus engine IDs and the encoded justification).
+pub(super) fn decode_signed_block<'a>(
+	metadata: &'a Metadata,
+	data: &mut &[u8],
+) -> Result<SignedBlock<'a>, DecodeError> {
+	let block = decode_block(metadata, data)?;
+	let justifications = Decode::decode(data)?;
+
+	Ok(SignedBlock { block, justifications })
+}
+
+/// A decoded block header.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+	/// The hash of the parent block.
+	pub parent_hash: [u8; 32],
+	/// The block number.
+	pub number: u64,
+	/// The root of the state trie after this block is executed.
+	pub state_root: [u8; 32],
+	/// The root of the trie of extrinsics in this block.
+	pub extrinsics_root: [u8; 32],
+	/// The digest items (logs) in the block header.
+	pub digest: Vec<DigestItem>,
+}
+
+/// A single item in a block header digest.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum DigestItem {
+	/// A pre-runtime digest, produced by the block author before the runtime executes.
+	PreRuntime(ConsensusEngineId, Vec<u8>),
+	/// A message from the runtime to the consensus engine.
+	Consensus(ConsensusEngineId, Vec<u8>),
+	/// A seal, usually containing the block author's signature over the header.
+	Seal(ConsensusEngineId, Vec<u8>),
+	/// Some other opaque data.
+	Other(Vec<u8>),
+	/// The runtime code or heap pages were updated in this block.
+	RuntimeEnvironmentUpdated,
+}
+
+/// A decoded block.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Block<'a> {
+	/// The block header.
+	pub header: Header,
+	/// The extrinsics in the block.
+	#[serde(borrow)]
+	pub extrinsics: Vec<Extrinsic<'a>>,
+}
+
+impl<'a> Block<'a> {
+	pub fn into_owned(self) -> Block<'static> {
+		Block { header: self.header, extrinsics: self.extrinsics.into_iter().map(Extrinsic::into_owned).collect() }
+	}
+}
+
+/// A decoded block, along with any justifications for it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SignedBlock<'a> {
+	/// The block itself.
+	#[serde(borrow)]
+	pub block: Block<'a>,
+	/// Justifications for the block, if any, alongside the ID of the consensus engine that each is for.
+	pub justifications: Option<Vec<(ConsensusEngineId, Vec<u8>)>>,
+}
+
+impl<'a> SignedBlock<'a> {
+	pub fn into_owned(self) -> SignedBlock<'static> {
+		SignedBlock { block: self.block.into_owned(), justifications: self.justifications }
+	}
+}
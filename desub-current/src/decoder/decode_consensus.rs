@@ -0,0 +1,277 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The consensus engines that substrate ships with (BABE, Aura, GRANDPA and BEEFY) put well known
+//! types into block header digests. These types don't appear in the metadata, so we decode them by hand
+//! here, following the shapes given in the `sp_consensus_*` crates.
+
+use super::{ConsensusEngineId, DecodeError, DigestItem, Header};
+use codec::Decode;
+use serde::Serialize;
+
+pub const BABE_ENGINE_ID: ConsensusEngineId = *b"BABE";
+pub const AURA_ENGINE_ID: ConsensusEngineId = *b"aura";
+pub const GRANDPA_ENGINE_ID: ConsensusEngineId = *b"FRNK";
+pub const BEEFY_ENGINE_ID: ConsensusEngineId = *b"BEEF";
+
+/// An sr25519 or ed25519 public key, used by BABE, Aura and GRANDPA authorities.
+pub type AuthorityId = [u8; 32];
+
+/// Decode the payload of a digest item, if it is a pre-runtime or consensus digest from one of
+/// the consensus engines that we know about. Returns `Ok(None)` for any other digest item.
+pub(super) fn decode_consensus_digest(item: &DigestItem) -> Result<Option<ConsensusDigest>, DecodeError> {
+	let digest = match item {
+		DigestItem::PreRuntime(BABE_ENGINE_ID, data) => ConsensusDigest::BabePreDigest(decode_babe_pre_digest(data)?),
+		DigestItem::PreRuntime(AURA_ENGINE_ID, data) => {
+			ConsensusDigest::AuraPreDigest { slot: Decode::decode(&mut &**data)? }
+		}
+		DigestItem::Consensus(BABE_ENGINE_ID, data) => ConsensusDigest::Babe(decode_babe_consensus_log(data)?),
+		DigestItem::Consensus(AURA_ENGINE_ID, data) => ConsensusDigest::Aura(decode_aura_consensus_log(data)?),
+		DigestItem::Consensus(GRANDPA_ENGINE_ID, data) => ConsensusDigest::Grandpa(decode_grandpa_consensus_log(data)?),
+		DigestItem::Consensus(BEEFY_ENGINE_ID, data) => ConsensusDigest::Beefy(decode_beefy_consensus_log(data)?),
+		_ => return Ok(None),
+	};
+	Ok(Some(digest))
+}
+
+/// Find the author of a block, given the authority set that was active when it was produced. For BABE,
+/// this is the set stored at `Babe.Authorities`, and for Aura, the set stored at `Aura.Authorities`.
+/// Returns `Ok(None)` if there is no BABE or Aura pre-runtime digest in the header, or the authority
+/// is not in the set provided.
+pub(super) fn block_author<'a, A>(header: &Header, authorities: &'a [A]) -> Result<Option<&'a A>, DecodeError> {
+	for item in &header.digest {
+		let idx = match decode_consensus_digest(item)? {
+			Some(ConsensusDigest::BabePreDigest(pre_digest)) => pre_digest.authority_index() as usize,
+			Some(ConsensusDigest::AuraPreDigest { slot }) if !authorities.is_empty() => {
+				(slot % authorities.len() as u64) as usize
+			}
+			_ => continue,
+		};
+		return Ok(authorities.get(idx));
+	}
+	Ok(None)
+}
+
+fn decode_babe_pre_digest(data: &[u8]) -> Result<BabePreDigest, DecodeError> {
+	let data = &mut &*data;
+	let pre_digest = match u8::decode(data)? {
+		1 => BabePreDigest::Primary {
+			authority_index: Decode::decode(data)?,
+			slot: Decode::decode(data)?,
+			vrf: decode_vrf_signature(data)?,
+		},
+		2 => BabePreDigest::SecondaryPlain { authority_index: Decode::decode(data)?, slot: Decode::decode(data)? },
+		3 => BabePreDigest::SecondaryVrf {
+			authority_index: Decode::decode(data)?,
+			slot: Decode::decode(data)?,
+			vrf: decode_vrf_signature(data)?,
+		},
+		index => return Err(DecodeError::CannotDecodeConsensusDigest { engine: "BABE", index }),
+	};
+	Ok(pre_digest)
+}
+
+fn decode_vrf_signature(data: &mut &[u8]) -> Result<VrfSignature, DecodeError> {
+	let output = Decode::decode(data)?;
+	let proof = <[u8; 64]>::decode(data)?.to_vec();
+	Ok(VrfSignature { output, proof })
+}
+
+fn decode_babe_consensus_log(data: &[u8]) -> Result<BabeConsensusLog, DecodeError> {
+	let data = &mut &*data;
+	let log = match u8::decode(data)? {
+		1 => BabeConsensusLog::NextEpochData { authorities: Decode::decode(data)?, randomness: Decode::decode(data)? },
+		2 => BabeConsensusLog::OnDisabled(Decode::decode(data)?),
+		3 => match u8::decode(data)? {
+			1 => BabeConsensusLog::NextConfigData {
+				c: Decode::decode(data)?,
+				allowed_slots: match u8::decode(data)? {
+					0 => AllowedSlots::PrimarySlots,
+					1 => AllowedSlots::PrimaryAndSecondaryPlainSlots,
+					2 => AllowedSlots::PrimaryAndSecondaryVrfSlots,
+					index => return Err(DecodeError::CannotDecodeConsensusDigest { engine: "BABE", index }),
+				},
+			},
+			index => return Err(DecodeError::CannotDecodeConsensusDigest { engine: "BABE", index }),
+		},
+		index => return Err(DecodeError::CannotDecodeConsensusDigest { engine: "BABE", index }),
+	};
+	Ok(log)
+}
+
+fn decode_aura_consensus_log(data: &[u8]) -> Result<AuraConsensusLog, DecodeError> {
+	let data = &mut &*data;
+	let log = match u8::decode(data)? {
+		1 => AuraConsensusLog::AuthoritiesChange(Decode::decode(data)?),
+		2 => AuraConsensusLog::OnDisabled(Decode::decode(data)?),
+		index => return Err(DecodeError::CannotDecodeConsensusDigest { engine: "Aura", index }),
+	};
+	Ok(log)
+}
+
+fn decode_grandpa_consensus_log(data: &[u8]) -> Result<GrandpaConsensusLog, DecodeError> {
+	let data = &mut &*data;
+	let log = match u8::decode(data)? {
+		1 => GrandpaConsensusLog::ScheduledChange(decode_grandpa_scheduled_change(data)?),
+		2 => GrandpaConsensusLog::ForcedChange(Decode::decode(data)?, decode_grandpa_scheduled_change(data)?),
+		3 => GrandpaConsensusLog::OnDisabled(Decode::decode(data)?),
+		4 => GrandpaConsensusLog::Pause(Decode::decode(data)?),
+		5 => GrandpaConsensusLog::Resume(Decode::decode(data)?),
+		index => return Err(DecodeError::CannotDecodeConsensusDigest { engine: "GRANDPA", index }),
+	};
+	Ok(log)
+}
+
+fn decode_grandpa_scheduled_change(data: &mut &[u8]) -> Result<GrandpaScheduledChange, DecodeError> {
+	Ok(GrandpaScheduledChange { next_authorities: Decode::decode(data)?, delay: Decode::decode(data)? })
+}
+
+fn decode_beefy_consensus_log(data: &[u8]) -> Result<BeefyConsensusLog, DecodeError> {
+	let data = &mut &*data;
+	let log = match u8::decode(data)? {
+		1 => {
+			// BEEFY authorities are compressed ecdsa public keys.
+			let validators = <Vec<[u8; 33]>>::decode(data)?.into_iter().map(|v| v.to_vec()).collect();
+			BeefyConsensusLog::AuthoritiesChange { validators, id: Decode::decode(data)? }
+		}
+		2 => BeefyConsensusLog::OnDisabled(Decode::decode(data)?),
+		3 => BeefyConsensusLog::MmrRoot(Decode::decode(data)?),
+		index => return Err(DecodeError::CannotDecodeConsensusDigest { engine: "BEEFY", index }),
+	};
+	Ok(log)
+}
+
+/// A decoded pre-runtime or consensus digest item from a known consensus engine.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum ConsensusDigest {
+	/// A BABE pre-runtime digest, identifying the slot and the author of the block.
+	BabePreDigest(BabePreDigest),
+	/// An Aura pre-runtime digest, identifying the slot of the block.
+	AuraPreDigest { slot: u64 },
+	/// A message from the runtime to BABE.
+	Babe(BabeConsensusLog),
+	/// A message from the runtime to Aura.
+	Aura(AuraConsensusLog),
+	/// A message from the runtime to GRANDPA.
+	Grandpa(GrandpaConsensusLog),
+	/// A message from the runtime to BEEFY.
+	Beefy(BeefyConsensusLog),
+}
+
+/// A BABE pre-runtime digest. This has the same shape as `sp_consensus_babe::digests::PreDigest`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum BabePreDigest {
+	/// A primary VRF-based slot assignment.
+	Primary { authority_index: u32, slot: u64, vrf: VrfSignature },
+	/// A secondary deterministic slot assignment.
+	SecondaryPlain { authority_index: u32, slot: u64 },
+	/// A secondary deterministic slot assignment with a VRF output.
+	SecondaryVrf { authority_index: u32, slot: u64, vrf: VrfSignature },
+}
+
+impl BabePreDigest {
+	/// The index of the block author in the BABE authority set.
+	pub fn authority_index(&self) -> u32 {
+		match self {
+			BabePreDigest::Primary { authority_index, .. }
+			| BabePreDigest::SecondaryPlain { authority_index, .. }
+			| BabePreDigest::SecondaryVrf { authority_index, .. } => *authority_index,
+		}
+	}
+
+	/// The slot that the block was produced in.
+	pub fn slot(&self) -> u64 {
+		match self {
+			BabePreDigest::Primary { slot, .. }
+			| BabePreDigest::SecondaryPlain { slot, .. }
+			| BabePreDigest::SecondaryVrf { slot, .. } => *slot,
+		}
+	}
+}
+
+/// The VRF output and proof given in a BABE pre-runtime digest.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct VrfSignature {
+	/// The 32 byte VRF output.
+	pub output: [u8; 32],
+	/// The 64 byte VRF proof.
+	pub proof: Vec<u8>,
+}
+
+/// A BABE consensus log. This has the same shape as `sp_consensus_babe::ConsensusLog`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum BabeConsensusLog {
+	/// The authorities and randomness of the next epoch. This is emitted in the first block of each epoch.
+	NextEpochData { authorities: Vec<(AuthorityId, u64)>, randomness: [u8; 32] },
+	/// The authority with the given index is disabled.
+	OnDisabled(u32),
+	/// The configuration of the next epoch. This is only emitted when it changes.
+	NextConfigData { c: (u64, u64), allowed_slots: AllowedSlots },
+}
+
+/// The kinds of slot that BABE authors are allowed to claim.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllowedSlots {
+	PrimarySlots,
+	PrimaryAndSecondaryPlainSlots,
+	PrimaryAndSecondaryVrfSlots,
+}
+
+/// An Aura consensus log. This has the same shape as `sp_consensus_aura::ConsensusLog`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum AuraConsensusLog {
+	/// The authority set has changed.
+	AuthoritiesChange(Vec<AuthorityId>),
+	/// The authority with the given index is disabled.
+	OnDisabled(u32),
+}
+
+/// A GRANDPA consensus log. This has the same shape as `sp_consensus_grandpa::ConsensusLog`, assuming
+/// that block numbers are `u32`s, as they are in Substrate and Polkadot.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum GrandpaConsensusLog {
+	/// A change to the authority set, to be enacted after the given delay once this block is finalized.
+	ScheduledChange(GrandpaScheduledChange),
+	/// A forced change to the authority set, to be enacted after the given delay once this block is imported.
+	/// The block number is the median last finalized block when the change was signaled.
+	ForcedChange(u32, GrandpaScheduledChange),
+	/// The authority with the given index is disabled.
+	OnDisabled(u64),
+	/// Pause the current authority set after the given delay.
+	Pause(u32),
+	/// Resume the current authority set after the given delay.
+	Resume(u32),
+}
+
+/// A change to the GRANDPA authority set.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct GrandpaScheduledChange {
+	/// The new authorities, and their weights.
+	pub next_authorities: Vec<(AuthorityId, u64)>,
+	/// The number of blocks to wait before enacting the change.
+	pub delay: u32,
+}
+
+/// A BEEFY consensus log. This has the same shape as `sp_consensus_beefy::ConsensusLog`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum BeefyConsensusLog {
+	/// The validator set has changed. Each validator is a 33 byte compressed ecdsa public key.
+	AuthoritiesChange { validators: Vec<Vec<u8>>, id: u64 },
+	/// The validator with the given index is disabled.
+	OnDisabled(u32),
+	/// The MMR root hash for this block.
+	MmrRoot([u8; 32]),
+}
@@ -0,0 +1,146 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::DecodeError;
+use crate::metadata::{Metadata, PalletEnum};
+use crate::value::{TypeInfo, Value};
+use crate::TypeId;
+use codec::{Compact, Decode};
+use serde::Serialize;
+use std::borrow::Cow;
+
+/// Decode the SCALE encoded bytes stored at `System.Events`. These take the shape
+/// `Vec<EventRecord>`, where each record contains the phase, the event itself (which
+/// begins with a `u8` pallet index and a `u8` event index, and then the event fields)
+/// and a vector of topics.
+pub(super) fn decode_events<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<Vec<EventRecord<'a>>, DecodeError> {
+	let len = <Compact<u32>>::decode(data)?.0;
+	log::trace!("Decoding {} Total Events.", len);
+
+	(0..len).map(|_| decode_event_record(metadata, data)).collect()
+}
+
+/// Decode a single SCALE encoded event record.
+fn decode_event_record<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<EventRecord<'a>, DecodeError> {
+	let phase = decode_phase(data)?;
+	let event = decode_event_data(metadata, data)?;
+	let topics = <Vec<[u8; 32]>>::decode(data)?;
+
+	Ok(EventRecord { phase, event, topics })
+}
+
+/// Decode the phase of an event. This has the same shape as `frame_system::Phase`.
+fn decode_phase(data: &mut &[u8]) -> Result<Phase, DecodeError> {
+	let phase = match u8::decode(data)? {
+		0 => Phase::ApplyExtrinsic(u32::decode(data)?),
+		1 => Phase::Finalization,
+		2 => Phase::Initialization,
+		other => return Err(DecodeError::CannotDecodePhase(other)),
+	};
+	Ok(phase)
+}
+
+/// Decode SCALE encoded event data. Much like call data, this takes the form of
+/// `(u8, u8, fields)`, where the `u8`s determine the pallet and event variant.
+fn decode_event_data<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<EventData<'a>, DecodeError> {
+	let start_len = data.len();
+	if data.len() < 2 {
+		return Err(DecodeError::EarlyEof("expected at least 2 more bytes for the pallet/event index"));
+	}
+	let pallet_index = u8::decode(data)?;
+	let event_index = u8::decode(data)?;
+	log::trace!("pallet index: {}, event index: {}", pallet_index, event_index);
+
+	let (pallet_name, variant) = match metadata.variant_by_enum_index(PalletEnum::Events, pallet_index, event_index) {
+		Some(event) => event,
+		None => return Err(DecodeError::CannotFindEvent(pallet_index, event_index)),
+	};
+
+	// Errors are located by the pallet, event and field name (or index), and their offset from the
+	// start of the event data.
+	let arguments = variant
+		.fields()
+		.iter()
+		.enumerate()
+		.map(|(idx, field)| {
+			let offset = start_len - data.len();
+			super::decode_value_by_id(metadata, field.ty(), data).map_err(|e| {
+				let e = e.at(super::field_segment(field.name(), idx)).at(variant.name()).at(pallet_name);
+				DecodeError::DecodeValueError(e.offset_by(offset))
+			})
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	Ok(EventData { pallet_name: Cow::Borrowed(pallet_name), ty: Cow::Borrowed(variant), arguments })
+}
+
+/// The phase of block execution that an event was emitted in.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+	/// Applying the extrinsic at the given index.
+	ApplyExtrinsic(u32),
+	/// Finalizing the block.
+	Finalization,
+	/// Initializing the block.
+	Initialization,
+}
+
+/// A decoded event record, as found in the `System.Events` storage entry.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EventRecord<'a> {
+	/// The phase of block execution in which the event was emitted.
+	pub phase: Phase,
+	/// Decoded event data and associated type information about the event.
+	#[serde(borrow)]
+	pub event: EventData<'a>,
+	/// The topics associated with this event.
+	pub topics: Vec<[u8; 32]>,
+}
+
+impl<'a> EventRecord<'a> {
+	pub fn into_owned(self) -> EventRecord<'static> {
+		EventRecord { phase: self.phase, event: self.event.into_owned(), topics: self.topics }
+	}
+}
+
+/// Decoded event data and associated type information.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EventData<'a> {
+	/// The name of the pallet that emitted the event.
+	#[serde(borrow)]
+	pub pallet_name: Cow<'a, str>,
+	/// The type information for this event (including the name
+	/// of the event and information about each field).
+	pub ty: Cow<'a, scale_info::Variant<scale_info::form::PortableForm>>,
+	/// The decoded field data.
+	pub arguments: Vec<Value<TypeId>>,
+}
+
+impl<'a> EventData<'a> {
+	pub fn into_owned(self) -> EventData<'static> {
+		EventData {
+			pallet_name: Cow::Owned(self.pallet_name.into_owned()),
+			ty: Cow::Owned(self.ty.into_owned()),
+			arguments: self.arguments,
+		}
+	}
+
+	/// The decoded fields, with type information attached to each of them (see [`TypeInfo`]).
+	/// The top level fields are given the type names of the event's fields.
+	pub fn arguments_with_type_info(&self, metadata: &Metadata) -> Vec<Value<TypeInfo>> {
+		super::with_field_type_info(self.ty.fields(), &self.arguments, metadata)
+	}
+}
@@ -0,0 +1,132 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{decode_value_by_id, DecodeValueError};
+use crate::metadata::{Metadata, PalletEnum};
+use crate::value::{Composite, Primitive, Value, ValueDef};
+use crate::TypeId;
+use serde::Serialize;
+use std::borrow::Cow;
+
+/// An enum of the possible errors that can be returned from attempting to look up
+/// the details of a module error.
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum ModuleErrorDecodeError {
+	#[error("Expected a DispatchError::Module value containing an 'index' and 'error' field")]
+	NotModuleError,
+	#[error("Expected the module error to be 1 or 4 bytes long, but got {0} bytes")]
+	InvalidErrorLength(usize),
+	#[error("Cannot find error corresponding to pallet index {0} and error index {1}")]
+	CannotFindError(u8, u8),
+	#[error("Cannot decode the fields of the module error: {0}")]
+	CannotDecodeErrorFields(DecodeValueError),
+}
+
+/// Details about the pallet error that a `DispatchError::Module` points to.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ModuleError<'a> {
+	/// The name of the pallet that the error belongs to.
+	#[serde(borrow)]
+	pub pallet_name: Cow<'a, str>,
+	/// The type information for this error (including the name
+	/// of the error, its docs and information about each field).
+	pub ty: Cow<'a, scale_info::Variant<scale_info::form::PortableForm>>,
+	/// The values of any fields that the error has, decoded from the bytes following the error index.
+	/// This is always empty for the single `u8` form of module errors, which has no room for them.
+	pub arguments: Vec<Value<TypeId>>,
+}
+
+impl<'a> ModuleError<'a> {
+	pub fn into_owned(self) -> ModuleError<'static> {
+		ModuleError {
+			pallet_name: Cow::Owned(self.pallet_name.into_owned()),
+			ty: Cow::Owned(self.ty.into_owned()),
+			arguments: self.arguments,
+		}
+	}
+}
+
+/// Look up the error given the pallet index and error bytes. Older runtimes use a single `u8` to
+/// identify the error, and newer runtimes use `[u8; 4]`, where the first byte identifies the error
+/// and the remaining bytes hold the values of its fields (padded with zeroes). Either form is accepted.
+pub(super) fn decode_module_error<'a>(
+	metadata: &'a Metadata,
+	pallet_index: u8,
+	error: &[u8],
+) -> Result<ModuleError<'a>, ModuleErrorDecodeError> {
+	let error_index = match error.len() {
+		1 | 4 => error[0],
+		len => return Err(ModuleErrorDecodeError::InvalidErrorLength(len)),
+	};
+
+	let (pallet_name, variant) = metadata
+		.variant_by_enum_index(PalletEnum::Errors, pallet_index, error_index)
+		.ok_or(ModuleErrorDecodeError::CannotFindError(pallet_index, error_index))?;
+
+	let data = &mut &error[1..];
+	let arguments = variant
+		.fields()
+		.iter()
+		.map(|field| decode_value_by_id(metadata, field.ty(), data))
+		.collect::<Result<_, _>>()
+		.map_err(ModuleErrorDecodeError::CannotDecodeErrorFields)?;
+
+	Ok(ModuleError { pallet_name: Cow::Borrowed(pallet_name), ty: Cow::Borrowed(variant), arguments })
+}
+
+/// Look up the error given a decoded `DispatchError`. The `Module` variant either holds the `index`
+/// and `error` fields directly, or (in newer runtimes) wraps a `ModuleError` struct containing them.
+pub(super) fn decode_dispatch_error<'a, T>(
+	metadata: &'a Metadata,
+	dispatch_error: &Value<T>,
+) -> Result<ModuleError<'a>, ModuleErrorDecodeError> {
+	let fields = match &dispatch_error.value {
+		ValueDef::Variant(variant) if variant.name == "Module" => &variant.values,
+		_ => return Err(ModuleErrorDecodeError::NotModuleError),
+	};
+	let fields = match fields {
+		Composite::Unnamed(vals) if vals.len() == 1 => match &vals[0].value {
+			ValueDef::Composite(inner) => inner,
+			_ => return Err(ModuleErrorDecodeError::NotModuleError),
+		},
+		fields => fields,
+	};
+	let named_field = |name: &str| match fields {
+		Composite::Named(vals) => vals.iter().find(|(n, _)| n == name).map(|(_, v)| v),
+		Composite::Unnamed(_) => None,
+	};
+
+	let pallet_index = named_field("index").and_then(as_u8).ok_or(ModuleErrorDecodeError::NotModuleError)?;
+	let error = named_field("error").and_then(as_bytes).ok_or(ModuleErrorDecodeError::NotModuleError)?;
+
+	decode_module_error(metadata, pallet_index, &error)
+}
+
+fn as_u8<T>(value: &Value<T>) -> Option<u8> {
+	match value.value {
+		ValueDef::Primitive(Primitive::U8(n)) => Some(n),
+		_ => None,
+	}
+}
+
+/// The error is either a single `u8`, or an array of them.
+fn as_bytes<T>(value: &Value<T>) -> Option<Vec<u8>> {
+	match &value.value {
+		ValueDef::Primitive(Primitive::U8(n)) => Some(vec![*n]),
+		ValueDef::Composite(Composite::Unnamed(vals)) => vals.iter().map(as_u8).collect(),
+		_ => None,
+	}
+}
@@ -0,0 +1,138 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::DecodeError;
+use crate::metadata::Metadata;
+use crate::value::Value;
+use crate::TypeId;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// A set of runtime API method definitions, which are needed in order to decode the arguments given to,
+/// and the values returned from, runtime API calls (ie `state_call` payloads). Methods are named in the
+/// same way as they are in `state_call`, for example `TransactionPaymentApi_query_info`.
+///
+/// Definitions can be registered by hand using type IDs from the metadata, or obtained from the runtime
+/// APIs given in V15 metadata using [`RuntimeApiDefinitions::from_metadata`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuntimeApiDefinitions {
+	methods: HashMap<String, RuntimeApiMethod>,
+}
+
+impl RuntimeApiDefinitions {
+	/// Create a new, empty set of definitions.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create a set of definitions containing every runtime API method given in the metadata. Runtime APIs
+	/// are only available from V15 metadata onwards, so this will be empty for older metadata.
+	pub fn from_metadata(metadata: &Metadata) -> Self {
+		let mut definitions = Self::new();
+		for api in metadata.runtime_apis() {
+			for method in &api.methods {
+				let inputs = method.inputs.iter().map(|input| (input.name.clone(), input.ty.into())).collect();
+				let output = method.output.into();
+				definitions.insert(format!("{}_{}", api.name, method.name), RuntimeApiMethod { inputs, output });
+			}
+		}
+		definitions
+	}
+
+	/// Add a method definition, returning the previous definition with the same name if there was one.
+	pub fn insert(&mut self, name: impl Into<String>, method: RuntimeApiMethod) -> Option<RuntimeApiMethod> {
+		self.methods.insert(name.into(), method)
+	}
+
+	/// Return the definition of the method given, if one exists.
+	pub fn get(&self, name: &str) -> Option<&RuntimeApiMethod> {
+		self.methods.get(name)
+	}
+
+	/// Add each of the definitions from another set to this one, overwriting any that have the same name.
+	pub fn extend(&mut self, other: RuntimeApiDefinitions) {
+		self.methods.extend(other.methods)
+	}
+}
+
+/// The definition of a single runtime API method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeApiMethod {
+	/// The name and type of each argument, in order.
+	pub inputs: Vec<(String, TypeId)>,
+	/// The type of the value returned.
+	pub output: TypeId,
+}
+
+/// The decoded arguments given to a runtime API method.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RuntimeApiCall<'a> {
+	/// The name of the method, for example `TransactionPaymentApi_query_info`.
+	#[serde(borrow)]
+	pub method: Cow<'a, str>,
+	/// The name and decoded value of each argument.
+	pub arguments: Vec<(Cow<'a, str>, Value<TypeId>)>,
+}
+
+impl<'a> RuntimeApiCall<'a> {
+	pub fn into_owned(self) -> RuntimeApiCall<'static> {
+		RuntimeApiCall {
+			method: Cow::Owned(self.method.into_owned()),
+			arguments: self.arguments.into_iter().map(|(name, value)| (Cow::Owned(name.into_owned()), value)).collect(),
+		}
+	}
+}
+
+pub(super) fn decode_runtime_api_call<'a>(
+	metadata: &Metadata,
+	definitions: &'a RuntimeApiDefinitions,
+	method: &'a str,
+	data: &mut &[u8],
+) -> Result<RuntimeApiCall<'a>, DecodeError> {
+	let definition = get_method(definitions, method)?;
+
+	let arguments = definition
+		.inputs
+		.iter()
+		.map(|(name, ty)| {
+			let value = super::decode_value_by_id(metadata, ty, data)?;
+			Ok((Cow::Borrowed(&**name), value))
+		})
+		.collect::<Result<Vec<_>, DecodeError>>()?;
+
+	Ok(RuntimeApiCall { method: Cow::Borrowed(method), arguments })
+}
+
+pub(super) fn decode_runtime_api_result(
+	metadata: &Metadata,
+	definitions: &RuntimeApiDefinitions,
+	method: &str,
+	data: &mut &[u8],
+) -> Result<Value<TypeId>, DecodeError> {
+	let definition = get_method(definitions, method)?;
+	let value = super::decode_value_by_id(metadata, definition.output, data)?;
+
+	// The output should be the only thing in the data given, so anything left over means we got it wrong.
+	if !data.is_empty() {
+		return Err(DecodeError::ExcessBytes(data.len()));
+	}
+	Ok(value)
+}
+
+fn get_method<'a>(definitions: &'a RuntimeApiDefinitions, method: &str) -> Result<&'a RuntimeApiMethod, DecodeError> {
+	definitions.get(method).ok_or_else(|| DecodeError::CannotFindRuntimeApiMethod(method.to_owned()))
+}
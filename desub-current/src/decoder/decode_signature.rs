@@ -0,0 +1,137 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{decode_value_by_id, DecodeError};
+use crate::metadata::Metadata;
+use crate::value::Value;
+use crate::{Type, TypeId};
+use codec::Decode;
+use scale_info::{TypeDef, TypeDefPrimitive};
+use serde::Serialize;
+use sp_runtime::{AccountId32, MultiAddress, MultiSignature};
+
+/// The address that a signed extrinsic is sent from. Where the address type is the usual Substrate
+/// `MultiAddress`, we decode it into that, and otherwise we decode it into a [`Value`] according
+/// to the address type given in the metadata.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Address {
+	/// A standard Substrate `MultiAddress`.
+	MultiAddress(#[serde(with = "desub_common::RemoteAddress")] MultiAddress<AccountId32, u32>),
+	/// Some other address type, such as the `AccountId20` used in EVM compatible chains.
+	Value(Value<TypeId>),
+}
+
+impl Address {
+	/// Return the address as a `MultiAddress`, if it is one.
+	pub fn as_multi_address(&self) -> Option<&MultiAddress<AccountId32, u32>> {
+		match self {
+			Address::MultiAddress(address) => Some(address),
+			Address::Value(_) => None,
+		}
+	}
+
+	/// Return the account ID that the address refers to, if it's given directly (for instance
+	/// as a `MultiAddress::Id` or `MultiAddress::Address32`).
+	pub fn account_id(&self) -> Option<AccountId32> {
+		match self {
+			Address::MultiAddress(MultiAddress::Id(account_id)) => Some(account_id.clone()),
+			Address::MultiAddress(MultiAddress::Address32(bytes)) => Some(AccountId32::new(*bytes)),
+			Address::MultiAddress(_) => None,
+			Address::Value(value) => match value.as_variant() {
+				Some(variant) if variant.name != "Id" && variant.name != "Address32" => None,
+				_ => value.as_account_id(),
+			},
+		}
+	}
+}
+
+impl From<MultiAddress<AccountId32, u32>> for Address {
+	fn from(address: MultiAddress<AccountId32, u32>) -> Self {
+		Address::MultiAddress(address)
+	}
+}
+
+/// The signature attached to a signed extrinsic. Where the signature type is the usual Substrate
+/// `MultiSignature`, we decode it into that, and otherwise we decode it into a [`Value`] according
+/// to the signature type given in the metadata.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Signature {
+	/// A standard Substrate `MultiSignature`.
+	MultiSignature(MultiSignature),
+	/// Some other signature type, such as the `EthereumSignature` used in EVM compatible chains.
+	Value(Value<TypeId>),
+}
+
+impl Signature {
+	/// Return the signature as a `MultiSignature`, if it is one.
+	pub fn as_multi_signature(&self) -> Option<&MultiSignature> {
+		match self {
+			Signature::MultiSignature(signature) => Some(signature),
+			Signature::Value(_) => None,
+		}
+	}
+}
+
+impl From<MultiSignature> for Signature {
+	fn from(signature: MultiSignature) -> Self {
+		Signature::MultiSignature(signature)
+	}
+}
+
+pub(super) fn decode_address(metadata: &Metadata, data: &mut &[u8]) -> Result<Address, DecodeError> {
+	match metadata.extrinsic().address_type() {
+		Some(ty) if !is_multi_address(metadata, ty) => Ok(Address::Value(decode_value_by_id(metadata, ty, data)?)),
+		_ => Ok(Address::MultiAddress(Decode::decode(data)?)),
+	}
+}
+
+pub(super) fn decode_signature(metadata: &Metadata, data: &mut &[u8]) -> Result<Signature, DecodeError> {
+	match metadata.extrinsic().signature_type() {
+		Some(ty) if !is_multi_signature(metadata, ty) => Ok(Signature::Value(decode_value_by_id(metadata, ty, data)?)),
+		_ => Ok(Signature::MultiSignature(Decode::decode(data)?)),
+	}
+}
+
+/// Can the address type given be decoded into a `MultiAddress<AccountId32, u32>`? Chains often use
+/// `()` as the account index type, which a `MultiAddress<AccountId32, u32>` can't represent, and so
+/// those addresses are decoded into a [`Value`] instead.
+fn is_multi_address(metadata: &Metadata, ty: TypeId) -> bool {
+	let ty = match metadata.resolve(ty) {
+		Some(ty) if has_path(ty, &["sp_runtime", "multiaddress", "MultiAddress"]) => ty,
+		_ => return false,
+	};
+	let type_param = |name: &str| {
+		ty.type_params().iter().find(|p| p.name() == name).and_then(|p| p.ty()).and_then(|id| metadata.resolve(id))
+	};
+
+	let is_account_id32 =
+		matches!(type_param("AccountId"), Some(t) if has_path(t, &["sp_core", "crypto", "AccountId32"]));
+	let is_u32_index =
+		matches!(type_param("AccountIndex").map(|t| t.type_def()), Some(TypeDef::Primitive(TypeDefPrimitive::U32)));
+
+	is_account_id32 && is_u32_index
+}
+
+/// Can the signature type given be decoded into a `MultiSignature`?
+fn is_multi_signature(metadata: &Metadata, ty: TypeId) -> bool {
+	matches!(metadata.resolve(ty), Some(ty) if has_path(ty, &["sp_runtime", "MultiSignature"]))
+}
+
+fn has_path(ty: &Type, path: &[&str]) -> bool {
+	ty.path().segments().iter().map(|s| &**s).eq(path.iter().copied())
+}
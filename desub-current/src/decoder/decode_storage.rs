@@ -1,7 +1,7 @@
 use super::Value;
 use crate::metadata::{Metadata, StorageLocation};
 use crate::{ScaleInfoTypeId, TypeId};
-use frame_metadata::v14::StorageEntryType as FrameStorageEntryType;
+use frame_metadata::v14::{StorageEntryModifier, StorageEntryType as FrameStorageEntryType};
 use serde::Serialize;
 use sp_core::twox_128;
 use std::borrow::Cow;
@@ -37,6 +37,16 @@ pub enum StorageDecodeError {
 	PrefixNotFound,
 	#[error("Couldn't find a storage entry corresponding to the name hash provided in the data")]
 	NameNotFound,
+	#[error(
+		"The metadata for this storage entry has {keys} key types but {hashers} hashers, so keys can't be decoded"
+	)]
+	KeysAndHashersMismatch { keys: usize, hashers: usize },
+	#[error("Couldn't decode the storage value: {0}")]
+	CouldNotDecodeValue(super::DecodeValueError),
+	#[error("Couldn't decode the default storage value: {0}")]
+	CouldNotDecodeDefaultValue(super::DecodeValueError),
+	#[error("Failed to decode the storage value: {0} bytes of the input were not consumed")]
+	ExcessValueBytes(usize),
 }
 
 impl StorageDecoder {
@@ -70,6 +80,53 @@ impl StorageDecoder {
 		metadata: &'m Metadata,
 		bytes: &mut &'b [u8],
 	) -> Result<StorageEntry<'m, 'b>, StorageDecodeError> {
+		self.decode_key_at_location(metadata, bytes).map(|(entry, _)| entry)
+	}
+
+	/// Decode the SCALE encoded bytes representing a storage entry lookup (see [`StorageDecoder::decode_key`]),
+	/// as well as the SCALE encoded value found at that location, if any.
+	///
+	/// If no value is provided, we fall back to what the storage entry metadata tells us to expect; entries with
+	/// an `Optional` modifier will hand back no value, and entries with a `Default` modifier will hand back the
+	/// decoded default value for the entry.
+	pub fn decode_key_value<'m, 'b>(
+		&self,
+		metadata: &'m Metadata,
+		key: &mut &'b [u8],
+		value: Option<&[u8]>,
+	) -> Result<StorageEntryWithValue<'m, 'b>, StorageDecodeError> {
+		let (entry, location) = self.decode_key_at_location(metadata, key)?;
+		let entry_metadata = metadata.storage_entry(location).metadata;
+
+		let value = match (value, &entry_metadata.modifier) {
+			(Some(bytes), _) => {
+				let bytes = &mut &*bytes;
+				let value = super::decode_value_by_id(metadata, entry.ty, bytes)
+					.map_err(StorageDecodeError::CouldNotDecodeValue)?;
+				if !bytes.is_empty() {
+					return Err(StorageDecodeError::ExcessValueBytes(bytes.len()));
+				}
+				Some(value)
+			}
+			(None, StorageEntryModifier::Optional) => None,
+			(None, StorageEntryModifier::Default) => {
+				let default_bytes = &mut &*entry_metadata.default;
+				let value = super::decode_value_by_id(metadata, entry.ty, default_bytes)
+					.map_err(StorageDecodeError::CouldNotDecodeDefaultValue)?;
+				Some(value)
+			}
+		};
+
+		Ok(StorageEntryWithValue { entry, value })
+	}
+
+	// Decode a storage key, also handing back the location of the entry in the metadata
+	// so that further details about it can be looked up if needed.
+	fn decode_key_at_location<'m, 'b>(
+		&self,
+		metadata: &'m Metadata,
+		bytes: &mut &'b [u8],
+	) -> Result<(StorageEntry<'m, 'b>, StorageLocation), StorageDecodeError> {
 		// Step 1: reverse-lookup the hashed prefix+name part of the key, and get
 		// details about this storage location from our metadata.
 		let location = self.decode_prefix_and_name_to_location(bytes)?;
@@ -84,22 +141,25 @@ impl StorageDecoder {
 			FrameStorageEntryType::Plain(ty) => {
 				// No more work to do here; our storage entry is a plain prefix+name entry,
 				// so return the details of it:
-				Ok(StorageEntry {
-					prefix: prefix_str.into(),
-					name: name_str.into(),
-					ty: ty.into(),
-					details: StorageEntryType::Plain,
-				})
+				Ok((
+					StorageEntry {
+						prefix: prefix_str.into(),
+						name: name_str.into(),
+						ty: ty.into(),
+						details: StorageEntryType::Plain,
+					},
+					location,
+				))
 			}
 			FrameStorageEntryType::Map { hashers, key, value } => {
 				// We'll consume some more data based on the hashers.
 				// First, get the type information that we need ready.
-				let keys = storage_map_key_to_type_id_vec(metadata, key);
+				let keys = storage_map_key_to_type_id_vec(metadata, key, hashers.len());
 				if keys.len() != hashers.len() {
-					panic!(
-						"Metadata inconsistency: keys and hashers for storage lookup {}.{} don't line up",
-						prefix_str, name_str
-					);
+					return Err(StorageDecodeError::KeysAndHashersMismatch {
+						keys: keys.len(),
+						hashers: hashers.len(),
+					});
 				}
 
 				// Work through the hashers and type info we have to generate the output
@@ -152,12 +212,15 @@ impl StorageDecoder {
 					storage_keys.push(StorageMapKey { bytes: Cow::Borrowed(hash_bytes), hasher, ty });
 				}
 
-				Ok(StorageEntry {
-					prefix: prefix_str.into(),
-					name: name_str.into(),
-					ty: value.into(),
-					details: StorageEntryType::Map(storage_keys),
-				})
+				Ok((
+					StorageEntry {
+						prefix: prefix_str.into(),
+						name: name_str.into(),
+						ty: value.into(),
+						details: StorageEntryType::Map(storage_keys),
+					},
+					location,
+				))
 			}
 		}
 	}
@@ -185,11 +248,16 @@ impl StorageDecoder {
 
 // Metadata info for maps/doublemaps contains a vec of hashers for each key type,
 // and a Type representing the key(s). We expect the number of keys and hashers to
-// line up, so let's resolve the keys into something easier to work with.
+// line up, so let's resolve the keys into something easier to work with. A map with
+// a single hasher has a single key, even if that key is a tuple.
 //
 // See https://github.com/paritytech/subxt/blob/793c945fbd2de022f523c39a84ee02609ba423a9/codegen/src/api/storage.rs#L105
 // for another example of this being handled in code.
-fn storage_map_key_to_type_id_vec(metadata: &Metadata, key: &ScaleInfoTypeId) -> Vec<TypeId> {
+pub(crate) fn storage_map_key_to_type_id_vec(
+	metadata: &Metadata,
+	key: &ScaleInfoTypeId,
+	num_hashers: usize,
+) -> Vec<TypeId> {
 	let ty = match metadata.resolve(key) {
 		Some(ty) => ty,
 		None => panic!("Metadata inconsistency: type #{} not found", key.id()),
@@ -197,7 +265,9 @@ fn storage_map_key_to_type_id_vec(metadata: &Metadata, key: &ScaleInfoTypeId) ->
 
 	match ty.type_def() {
 		// Multiple keys:
-		scale_info::TypeDef::Tuple(vals) => vals.fields().iter().map(|f| TypeId::from_u32(f.id())).collect(),
+		scale_info::TypeDef::Tuple(vals) if num_hashers != 1 => {
+			vals.fields().iter().map(|f| TypeId::from_u32(f.id())).collect()
+		}
 		// Single key:
 		_ => vec![key.into()],
 	}
@@ -228,6 +298,22 @@ impl<'m, 'b> StorageEntry<'m, 'b> {
 	}
 }
 
+/// Details about the decoded storage key, as well as the decoded value at that location.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StorageEntryWithValue<'m, 'b> {
+	/// Details about the decoded storage key.
+	pub entry: StorageEntry<'m, 'b>,
+	/// The decoded value. If no value was provided, this is the default value for the
+	/// storage entry, or `None` if the entry has no default value.
+	pub value: Option<Value<TypeId>>,
+}
+
+impl<'m, 'b> StorageEntryWithValue<'m, 'b> {
+	pub fn into_owned(self) -> StorageEntryWithValue<'static, 'static> {
+		StorageEntryWithValue { entry: self.entry.into_owned(), value: self.value }
+	}
+}
+
 /// This is similar to [`frame_metadata::v14::StorageEntryType`], but also includes
 /// decoded values, and doesn't include the value type, which instead exists in the
 /// [`StorageEntry`] struct.
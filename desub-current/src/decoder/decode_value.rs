@@ -14,19 +14,96 @@
 // You should have received a copy of the GNU General Public License
 // along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
 
+use super::visitor::{decode_with_visitor, Visitor};
 use crate::value::{BitSequence, Composite, Primitive, Value, ValueDef, Variant};
 use crate::{Type, TypeId};
-use codec::{Compact, Decode};
-use scale_info::{
-	form::PortableForm, Field, PortableRegistry, TypeDefArray, TypeDefBitSequence, TypeDefCompact, TypeDefComposite,
-	TypeDefPrimitive, TypeDefSequence, TypeDefTuple, TypeDefVariant,
-};
+use desub_common::{PathSegment, ValuePath};
+use scale_info::{form::PortableForm, Field, PortableRegistry};
+
+/// An error decoding a [`Value`]. This contains the path to the value that could not be decoded, the
+/// byte offset at which it starts, and the innermost named type that we were decoding, as well as
+/// details about what went wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeValueError {
+	path: ValuePath,
+	type_path: Option<String>,
+	offset: usize,
+	// Boxed, since some kinds of error are quite large.
+	kind: Box<DecodeValueErrorKind>,
+}
+
+impl DecodeValueError {
+	/// The path to the offending value, made up of field names, variant names and `[n]` sequence
+	/// indexes. This is empty if the top level value could not be decoded.
+	pub fn path(&self) -> &[String] {
+		self.path.segments()
+	}
+
+	/// The path of the innermost named type that we were decoding when things went wrong,
+	/// for example `pallet_balances::pallet::Call`.
+	pub fn type_path(&self) -> Option<&str> {
+		self.type_path.as_deref()
+	}
 
-// This is used in several places below.
-type TypeDef = scale_info::TypeDef<PortableForm>;
+	/// The offset, from the start of the input bytes, of the value that could not be decoded.
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+
+	/// Details about what went wrong.
+	pub fn kind(&self) -> &DecodeValueErrorKind {
+		&self.kind
+	}
+
+	pub(crate) fn at(mut self, segment: impl Into<PathSegment>) -> Self {
+		self.path.prepend(segment);
+		self
+	}
+
+	// The first named type that the error bubbles up through is the one we report.
+	pub(crate) fn in_type(mut self, ty: &Type) -> Self {
+		if self.type_path.is_none() && !ty.path().is_empty() {
+			self.type_path = Some(ty.path().segments().join("::"));
+		}
+		self
+	}
+
+	// Offsets start out relative to the value being decoded, and are shifted as
+	// the error makes its way out to wherever the input began.
+	pub(crate) fn offset_by(mut self, offset: usize) -> Self {
+		self.offset += offset;
+		self
+	}
+}
+
+impl From<DecodeValueErrorKind> for DecodeValueError {
+	fn from(kind: DecodeValueErrorKind) -> Self {
+		DecodeValueError { path: ValuePath::new(), type_path: None, offset: 0, kind: Box::new(kind) }
+	}
+}
+
+impl From<codec::Error> for DecodeValueError {
+	fn from(e: codec::Error) -> Self {
+		DecodeValueErrorKind::CodecError(e).into()
+	}
+}
+
+impl std::fmt::Display for DecodeValueError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if !self.path.is_empty() {
+			write!(f, "field `{}` ", self.path)?;
+		}
+		match &self.type_path {
+			Some(type_path) => write!(f, "(type `{}`, at byte {}): {}", type_path, self.offset, self.kind),
+			None => write!(f, "(at byte {}): {}", self.offset, self.kind),
+		}
+	}
+}
+
+impl std::error::Error for DecodeValueError {}
 
 #[derive(Debug, Clone, thiserror::Error, PartialEq)]
-pub enum DecodeValueError {
+pub enum DecodeValueErrorKind {
 	#[error("{0}")]
 	CodecError(#[from] codec::Error),
 	#[error("{0} is expected to be a valid char, but is not")]
@@ -49,204 +126,132 @@ pub fn decode_value_by_id<Id: Into<TypeId>>(
 	ty_id: Id,
 	types: &PortableRegistry,
 ) -> Result<Value<TypeId>, DecodeValueError> {
-	let ty_id = ty_id.into();
-	let ty = types.resolve(ty_id.id()).ok_or_else(|| DecodeValueError::TypeIdNotFound(ty_id.id()))?;
-
-	let value = match ty.type_def() {
-		TypeDef::Composite(inner) => decode_composite_value(data, inner, types).map(ValueDef::Composite),
-		TypeDef::Sequence(inner) => decode_sequence_value(data, inner, types).map(ValueDef::Composite),
-		TypeDef::Array(inner) => decode_array_value(data, inner, types).map(ValueDef::Composite),
-		TypeDef::Tuple(inner) => decode_tuple_value(data, inner, types).map(ValueDef::Composite),
-		TypeDef::Variant(inner) => decode_variant_value(data, inner, types).map(ValueDef::Variant),
-		TypeDef::Primitive(inner) => decode_primitive_value(data, inner).map(ValueDef::Primitive),
-		TypeDef::Compact(inner) => decode_compact_value(data, inner, types),
-		TypeDef::BitSequence(inner) => decode_bit_sequence_value(data, inner, types).map(ValueDef::BitSequence),
-	}?;
-
-	Ok(Value { value, context: ty_id })
+	let mut visitor = ValueVisitor::default();
+	decode_with_visitor(data, ty_id.into(), types, &mut visitor)?;
+	Ok(visitor.value.expect("a value is always visited if decoding succeeds; qed"))
 }
 
-fn decode_composite_value(
-	data: &mut &[u8],
-	ty: &TypeDefComposite<PortableForm>,
-	types: &PortableRegistry,
-) -> Result<Composite<TypeId>, DecodeValueError> {
-	decode_fields(data, ty.fields(), types)
+/// A [`Visitor`] which builds up a [`Value`] from the parts that it is handed.
+#[derive(Default)]
+struct ValueVisitor {
+	/// The composites, sequences and variants that we're partway through building.
+	stack: Vec<Frame>,
+	/// The finished value, once the outermost value has been visited.
+	value: Option<Value<TypeId>>,
 }
 
-fn decode_variant_value(
-	data: &mut &[u8],
-	ty: &TypeDefVariant<PortableForm>,
-	types: &PortableRegistry,
-) -> Result<Variant<TypeId>, DecodeValueError> {
-	let index = *data.get(0).ok_or(DecodeValueError::Eof)?;
-	*data = &data[1..];
-
-	// Does a variant exist with the index we're looking for?
-	let variant = ty
-		.variants()
-		.iter()
-		.find(|v| v.index() == index)
-		.ok_or_else(|| DecodeValueError::VariantNotFound(index, ty.clone()))?;
-
-	let fields = decode_fields(data, variant.fields(), types)?;
-	Ok(Variant { name: variant.name().clone(), values: fields })
+struct Frame {
+	ty: TypeId,
+	kind: FrameKind,
+	/// The name of the field that the next value belongs to.
+	field_name: Option<String>,
+	/// The values seen so far, alongside their field names (which are empty if they are unnamed).
+	values: Vec<(String, Value<TypeId>)>,
 }
 
-/// Variant and Composite types both have fields; this will decode them into values.
-fn decode_fields(
-	data: &mut &[u8],
-	fields: &[Field<PortableForm>],
-	types: &PortableRegistry,
-) -> Result<Composite<TypeId>, DecodeValueError> {
-	let are_named = fields.iter().any(|f| f.name().is_some());
-	let named_field_vals = fields.iter().map(|f| {
-		let name = f.name().cloned().unwrap_or_default();
-		decode_value_by_id(data, f.ty(), types).map(|val| (name, val))
-	});
-
-	if are_named {
-		let vals = named_field_vals.collect::<Result<_, _>>()?;
-		Ok(Composite::Named(vals))
-	} else {
-		let vals = named_field_vals.map(|r| r.map(|(_, v)| v)).collect::<Result<_, _>>()?;
-		Ok(Composite::Unnamed(vals))
+enum FrameKind {
+	Composite { named: bool },
+	Variant { name: String, named: bool },
+}
+
+impl ValueVisitor {
+	fn push_frame(&mut self, ty: TypeId, kind: FrameKind) {
+		// Sequence lengths come from the input, so we don't allocate room for values up front.
+		self.stack.push(Frame { ty, kind, field_name: None, values: Vec::new() });
+	}
+
+	fn push_value(&mut self, value: Value<TypeId>) {
+		match self.stack.last_mut() {
+			Some(frame) => frame.values.push((frame.field_name.take().unwrap_or_default(), value)),
+			None => self.value = Some(value),
+		}
+	}
+
+	fn pop_frame(&mut self) {
+		let frame = self.stack.pop().expect("every end is preceeded by a start; qed");
+		let to_composite = |named, values: Vec<(String, Value<TypeId>)>| {
+			if named {
+				Composite::Named(values)
+			} else {
+				Composite::Unnamed(values.into_iter().map(|(_, v)| v).collect())
+			}
+		};
+
+		let value = match frame.kind {
+			FrameKind::Composite { named } => ValueDef::Composite(to_composite(named, frame.values)),
+			FrameKind::Variant { name, named } => {
+				ValueDef::Variant(Variant { name, values: to_composite(named, frame.values) })
+			}
+		};
+		self.push_value(Value { value, context: frame.ty });
 	}
 }
 
-fn decode_sequence_value(
-	data: &mut &[u8],
-	ty: &TypeDefSequence<PortableForm>,
-	types: &PortableRegistry,
-) -> Result<Composite<TypeId>, DecodeValueError> {
-	// We assume that the sequence is preceeded by a compact encoded length, so that
-	// we know how many values to try pulling out of the data.
-	let len = Compact::<u64>::decode(data)?;
-	let values: Vec<_> =
-		(0..len.0).map(|_| decode_value_by_id(data, ty.type_param(), types)).collect::<Result<_, _>>()?;
-
-	Ok(Composite::Unnamed(values))
+fn are_named(fields: &[Field<PortableForm>]) -> bool {
+	fields.iter().any(|f| f.name().is_some())
 }
 
-fn decode_array_value(
-	data: &mut &[u8],
-	ty: &TypeDefArray<PortableForm>,
-	types: &PortableRegistry,
-) -> Result<Composite<TypeId>, DecodeValueError> {
-	// The length is known based on the type we want to decode into, so we pull out the number of items according
-	// to that, and don't need a length to exist in the SCALE encoded bytes
-	let values: Vec<_> =
-		(0..ty.len()).map(|_| decode_value_by_id(data, ty.type_param(), types)).collect::<Result<_, _>>()?;
+impl Visitor for ValueVisitor {
+	type Error = DecodeValueError;
 
-	Ok(Composite::Unnamed(values))
-}
+	fn visit_primitive(&mut self, ty: TypeId, value: Primitive) -> Result<(), Self::Error> {
+		self.push_value(Value { value: ValueDef::Primitive(value), context: ty });
+		Ok(())
+	}
 
-fn decode_tuple_value(
-	data: &mut &[u8],
-	ty: &TypeDefTuple<PortableForm>,
-	types: &PortableRegistry,
-) -> Result<Composite<TypeId>, DecodeValueError> {
-	let values: Vec<_> = ty.fields().iter().map(|f| decode_value_by_id(data, f, types)).collect::<Result<_, _>>()?;
+	fn visit_composite_start(&mut self, ty: TypeId, fields: &[Field<PortableForm>]) -> Result<(), Self::Error> {
+		self.push_frame(ty, FrameKind::Composite { named: are_named(fields) });
+		Ok(())
+	}
 
-	Ok(Composite::Unnamed(values))
-}
+	fn visit_composite_end(&mut self, _ty: TypeId) -> Result<(), Self::Error> {
+		self.pop_frame();
+		Ok(())
+	}
 
-fn decode_primitive_value(data: &mut &[u8], ty: &TypeDefPrimitive) -> Result<Primitive, DecodeValueError> {
-	let val = match ty {
-		TypeDefPrimitive::Bool => Primitive::Bool(bool::decode(data)?),
-		TypeDefPrimitive::Char => {
-			// [jsdw] TODO: There isn't a `char::decode`. Why? Is it wrong to use u32 or is there a more "proper" way?
-			let val = u32::decode(data)?;
-			Primitive::Char(char::from_u32(val).ok_or(DecodeValueError::InvalidChar(val))?)
+	fn visit_field(&mut self, name: Option<&str>) -> Result<(), Self::Error> {
+		if let Some(frame) = self.stack.last_mut() {
+			frame.field_name = name.map(ToOwned::to_owned);
 		}
-		TypeDefPrimitive::Str => Primitive::Str(String::decode(data)?),
-		TypeDefPrimitive::U8 => Primitive::U8(u8::decode(data)?),
-		TypeDefPrimitive::U16 => Primitive::U16(u16::decode(data)?),
-		TypeDefPrimitive::U32 => Primitive::U32(u32::decode(data)?),
-		TypeDefPrimitive::U64 => Primitive::U64(u64::decode(data)?),
-		TypeDefPrimitive::U128 => Primitive::U128(u128::decode(data)?),
-		TypeDefPrimitive::U256 => Primitive::U256(<[u8; 32]>::decode(data)?),
-		TypeDefPrimitive::I8 => Primitive::I8(i8::decode(data)?),
-		TypeDefPrimitive::I16 => Primitive::I16(i16::decode(data)?),
-		TypeDefPrimitive::I32 => Primitive::I32(i32::decode(data)?),
-		TypeDefPrimitive::I64 => Primitive::I64(i64::decode(data)?),
-		TypeDefPrimitive::I128 => Primitive::I128(i128::decode(data)?),
-		TypeDefPrimitive::I256 => Primitive::I256(<[u8; 32]>::decode(data)?),
-	};
-	Ok(val)
-}
+		Ok(())
+	}
 
-fn decode_compact_value(
-	data: &mut &[u8],
-	ty: &TypeDefCompact<PortableForm>,
-	types: &PortableRegistry,
-) -> Result<ValueDef<TypeId>, DecodeValueError> {
-	fn decode_compact(
-		data: &mut &[u8],
-		inner: &Type,
-		types: &PortableRegistry,
-	) -> Result<ValueDef<TypeId>, DecodeValueError> {
-		use TypeDefPrimitive::*;
-		let val = match inner.type_def() {
-			// It's obvious how to decode basic primitive unsigned types, since we have impls for them.
-			TypeDef::Primitive(U8) => ValueDef::Primitive(Primitive::U8(Compact::<u8>::decode(data)?.0)),
-			TypeDef::Primitive(U16) => ValueDef::Primitive(Primitive::U16(Compact::<u16>::decode(data)?.0)),
-			TypeDef::Primitive(U32) => ValueDef::Primitive(Primitive::U32(Compact::<u32>::decode(data)?.0)),
-			TypeDef::Primitive(U64) => ValueDef::Primitive(Primitive::U64(Compact::<u64>::decode(data)?.0)),
-			TypeDef::Primitive(U128) => ValueDef::Primitive(Primitive::U128(Compact::<u128>::decode(data)?.0)),
-			// A struct with exactly 1 field containing one of the above types can be sensibly compact encoded/decoded.
-			TypeDef::Composite(composite) => {
-				if composite.fields().len() != 1 {
-					return Err(DecodeValueError::CannotDecodeCompactIntoType(inner.clone()));
-				}
-
-				// What type is the 1 field that we are able to decode?
-				let field = &composite.fields()[0];
-				let field_type_id = field.ty().id();
-				let inner_ty = types.resolve(field_type_id).ok_or(DecodeValueError::TypeIdNotFound(field_type_id))?;
-
-				// Decode this inner type via compact decoding. This can recurse, in case
-				// the inner type is also a 1-field composite type.
-				let inner_value = Value { value: decode_compact(data, inner_ty, types)?, context: field.ty().into() };
-
-				// Wrap the inner type in a representation of this outer composite type.
-				let composite = match field.name() {
-					Some(name) => Composite::Named(vec![(name.clone(), inner_value)]),
-					None => Composite::Unnamed(vec![inner_value]),
-				};
-
-				ValueDef::Composite(composite)
-			}
-			// For now, we give up if we have been asked for any other type:
-			_cannot_decode_from => return Err(DecodeValueError::CannotDecodeCompactIntoType(inner.clone())),
-		};
+	fn visit_variant_start(
+		&mut self,
+		ty: TypeId,
+		variant: &scale_info::Variant<PortableForm>,
+	) -> Result<(), Self::Error> {
+		let kind = FrameKind::Variant { name: variant.name().clone(), named: are_named(variant.fields()) };
+		self.push_frame(ty, kind);
+		Ok(())
+	}
 
-		Ok(val)
+	fn visit_variant_end(&mut self, _ty: TypeId) -> Result<(), Self::Error> {
+		self.pop_frame();
+		Ok(())
 	}
 
-	// Pluck the inner type out and run it through our compact decoding logic.
-	let inner =
-		types.resolve(ty.type_param().id()).ok_or_else(|| DecodeValueError::TypeIdNotFound(ty.type_param().id()))?;
-	decode_compact(data, inner, types)
-}
+	fn visit_sequence_start(&mut self, ty: TypeId, _len: usize) -> Result<(), Self::Error> {
+		self.push_frame(ty, FrameKind::Composite { named: false });
+		Ok(())
+	}
 
-fn decode_bit_sequence_value(
-	data: &mut &[u8],
-	_ty: &TypeDefBitSequence<PortableForm>,
-	_types: &PortableRegistry,
-) -> Result<BitSequence, DecodeValueError> {
-	// [jsdw] TODO: might be worth checking the bit_store and bit_order types
-	// and trying to work out whether they look like Lsb0 and u8, which is what
-	// we assume here.
-	let bit_vec: BitSequence = Decode::decode(data)?;
-	Ok(bit_vec)
+	fn visit_sequence_end(&mut self, _ty: TypeId) -> Result<(), Self::Error> {
+		self.pop_frame();
+		Ok(())
+	}
+
+	fn visit_bit_sequence(&mut self, ty: TypeId, value: BitSequence) -> Result<(), Self::Error> {
+		self.push_value(Value { value: ValueDef::BitSequence(value), context: ty });
+		Ok(())
+	}
 }
 
 #[cfg(test)]
 mod test {
 
 	use super::*;
-	use codec::Encode;
+	use codec::{Compact, Encode};
 
 	/// Given a type definition, return the PortableType and PortableRegistry
 	/// that our decode functions expect.
@@ -312,6 +317,15 @@ mod test {
 		// );
 	}
 
+	#[test]
+	fn decode_truncated_sequence_with_huge_length() {
+		let (id, portable_registry) = make_type::<Vec<u8>>();
+
+		// A compact encoded length of u64::MAX, and then no items:
+		let encoded = hex::decode("13ffffffffffffffff").unwrap();
+		assert!(decode_value_by_id(&mut &*encoded, id, &portable_registry).is_err());
+	}
+
 	#[test]
 	fn decode_compact_primitives() {
 		encode_decode_check(Compact(123u8), Value::u8(123));
@@ -18,29 +18,94 @@
 //! decode various SCALE encoded values, such as extrinsics, that are compatible with that metadata.
 //!
 //! See [`decode_extrinsics`], [`decode_extrinsic`], and [`decode_unwrapped_extrinsic`] for the most
-//! common extrinsic decoding needs.
+//! common extrinsic decoding needs, and [`decode_extrinsics_resilient`] to carry on decoding a block of
+//! extrinsics past any that fail.
 //!
 //! See [`decode_storage()`] and then the documentation on [`StorageDecoder`] to decode storage lookups.
+//!
+//! See [`decode_block`], [`decode_signed_block`] and [`decode_header`] to decode whole blocks or their headers,
+//! and [`decode_consensus_digest`] and [`block_author`] to interpret the consensus digests in a header.
+//!
+//! See [`decode_with_visitor`] to walk over a value without building a [`Value`], for instance to pick out
+//! only the parts of it that you are interested in.
+//!
+//! See [`decode_events`] to decode the events stored at `System.Events`.
+//!
+//! See [`decode_runtime_api_call`] and [`decode_runtime_api_result`] to decode runtime API (`state_call`)
+//! requests and responses.
+//!
+//! See [`decode_dispatch_error`] and [`decode_module_error`] to look up the pallet error that a
+//! `DispatchError::Module` points to.
+//!
+//! See [`verify_signature`] to check the signature of a decoded, signed extrinsic.
 
+mod account_format;
+mod decode_block;
+mod decode_consensus;
+mod decode_events;
+mod decode_module_error;
+mod decode_runtime_api;
+mod decode_signature;
 mod decode_storage;
 mod decode_value;
 mod extrinsic_bytes;
+mod profile;
+mod verify_signature;
+mod visitor;
 
-use crate::metadata::Metadata;
-use crate::value::Value;
+use crate::metadata::{Metadata, PalletEnum};
+use crate::value::{TypeInfo, Value};
 use crate::TypeId;
 use codec::{Compact, Decode};
+use desub_common::PathSegment;
 use extrinsic_bytes::{AllExtrinsicBytes, ExtrinsicBytesError};
 use serde::Serialize;
-use sp_runtime::{AccountId32, MultiAddress, MultiSignature};
 use std::borrow::Cow;
 
+// The low bits of the first byte of an extrinsic hold its version. In V4 extrinsics, the top bit
+// says whether it's signed or not, and in V5 extrinsics, the top two bits give the kind of extrinsic.
+const EXTRINSIC_VERSION_MASK: u8 = 0b0011_1111;
+const EXTRINSIC_BARE: u8 = 0b0000_0000;
+const EXTRINSIC_SIGNED: u8 = 0b1000_0000;
+const EXTRINSIC_GENERAL: u8 = 0b0100_0000;
+
 // Re-export the DecodeValueError here, which we expose in our global `DecodeError` enum.
-pub use decode_value::DecodeValueError;
+pub use decode_value::{DecodeValueError, DecodeValueErrorKind};
+
+// Re-export the options for rendering account IDs in decoded output.
+pub use account_format::{AccountFormat, DEFAULT_SS58_PREFIX};
+
+// Re-export block related types that are part of our public interface.
+pub use decode_block::{Block, ConsensusEngineId, DigestItem, Header, SignedBlock};
+
+// Re-export consensus digest related types that are part of our public interface.
+pub use decode_consensus::{
+	AllowedSlots, AuraConsensusLog, AuthorityId, BabeConsensusLog, BabePreDigest, BeefyConsensusLog, ConsensusDigest,
+	GrandpaConsensusLog, GrandpaScheduledChange, VrfSignature, AURA_ENGINE_ID, BABE_ENGINE_ID, BEEFY_ENGINE_ID,
+	GRANDPA_ENGINE_ID,
+};
+
+// Re-export event related types that are part of our public interface.
+pub use decode_events::{EventData, EventRecord, Phase};
+
+// Re-export module error related types that are part of our public interface.
+pub use decode_module_error::{ModuleError, ModuleErrorDecodeError};
+
+// Re-export runtime API related types that are part of our public interface.
+pub use decode_runtime_api::{RuntimeApiCall, RuntimeApiDefinitions, RuntimeApiMethod};
+
+pub use decode_signature::{Address, Signature};
+
+pub use verify_signature::{ChainContext, VerifySignatureError};
+
+pub use visitor::Visitor;
 
 // Re-export storage related types that are part of our public interface.
+pub(crate) use decode_storage::storage_map_key_to_type_id_vec;
+
 pub use decode_storage::{
-	StorageDecodeError, StorageDecoder, StorageEntry, StorageEntryType, StorageHasher, StorageMapKey,
+	StorageDecodeError, StorageDecoder, StorageEntry, StorageEntryType, StorageEntryWithValue, StorageHasher,
+	StorageMapKey,
 };
 
 /// An enum of the possible errors that can be returned from attempting to decode bytes
@@ -55,14 +120,64 @@ pub enum DecodeError {
 	DecodeValueError(#[from] DecodeValueError),
 	#[error("Failed to decode: expected more data")]
 	EarlyEof(&'static str),
-	#[error("Failed to decode extrinsics: {0} bytes of the input were not consumed")]
+	#[error("Failed to decode: {0} bytes of the input were not consumed")]
 	ExcessBytes(usize),
 	#[error("Failed to decode unsupported extrinsic version '{0}'")]
 	CannotDecodeExtrinsicVersion(u8),
+	#[error("Failed to decode extrinsic: unsupported kind '{kind:#010b}' for version '{version}'")]
+	CannotDecodeExtrinsicKind { version: u8, kind: u8 },
 	#[error("Cannot find call corresponding to extrinsic with pallet index {0} and call index {1}")]
 	CannotFindCall(u8, u8),
 	#[error("Failed to decode extrinsic: cannot find type ID {0}")]
 	CannotFindType(u32),
+	#[error("Cannot find event corresponding to pallet index {0} and event index {1}")]
+	CannotFindEvent(u8, u8),
+	#[error("Failed to decode event phase: unexpected variant index {0}")]
+	CannotDecodePhase(u8),
+	#[error("Failed to decode digest item: unexpected variant index {0}")]
+	CannotDecodeDigestItem(u8),
+	#[error("Failed to decode {engine} consensus digest: unexpected variant index {index}")]
+	CannotDecodeConsensusDigest { engine: &'static str, index: u8 },
+	#[error("Cannot find a definition for the runtime API method '{0}'")]
+	CannotFindRuntimeApiMethod(String),
+}
+
+impl DecodeError {
+	// Value decoding errors record their offset from the start of the value that failed to decode. This
+	// shifts that offset to be relative to some earlier point in the input instead.
+	fn offset_by(self, offset: usize) -> Self {
+		match self {
+			DecodeError::DecodeValueError(e) => DecodeError::DecodeValueError(e.offset_by(offset)),
+			e => e,
+		}
+	}
+
+	// Prepend a segment to the path of a value decoding error.
+	fn at(self, segment: impl Into<PathSegment>) -> Self {
+		match self {
+			DecodeError::DecodeValueError(e) => DecodeError::DecodeValueError(e.at(segment)),
+			e => e,
+		}
+	}
+}
+
+/// Call arguments and event fields are located by their name if they have one, or otherwise their position.
+fn field_segment(name: Option<&String>, idx: usize) -> PathSegment {
+	match name {
+		Some(name) => name.into(),
+		None => idx.into(),
+	}
+}
+
+/// Decode some part of the input with the function provided, such that the offset of any value decoding error
+/// is relative to the point in the input at which `start_len` bytes remained, rather than to the part being decoded.
+fn decode_part<'d, T>(
+	start_len: usize,
+	data: &mut &'d [u8],
+	decode: impl FnOnce(&mut &'d [u8]) -> Result<T, DecodeError>,
+) -> Result<T, DecodeError> {
+	let offset = start_len - data.len();
+	decode(data).map_err(|e| e.offset_by(offset))
 }
 
 /// Decode a single [`Value`] from a piece of scale encoded data, given some metadata and the ID of the type that we
@@ -75,6 +190,49 @@ pub fn decode_value_by_id<'a, Id: Into<TypeId>>(
 	decode_value::decode_value_by_id(data, ty, metadata.types())
 }
 
+/// Decode some scale encoded data according to the ID of the type given, handing each part of it to a
+/// [`Visitor`] rather than building a [`Value`]. This avoids allocating, and allows a visitor to pick out
+/// only the parts of a value that it is interested in. [`decode_value_by_id`] is built on top of this.
+///
+/// # Example
+///
+/// ```rust
+/// use desub_current::{ Metadata, decoder::{ self, DecodeValueError, Visitor } };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// // Count the fields that we see:
+/// #[derive(Default)]
+/// struct FieldCounter(usize);
+/// impl Visitor for FieldCounter {
+///     type Error = DecodeValueError;
+///     fn visit_field(&mut self, _name: Option<&str>) -> Result<(), Self::Error> {
+///         self.0 += 1;
+///         Ok(())
+///     }
+/// }
+///
+/// // Balances.transfer call data (MultiAddress::Id(Bob), 12345):
+/// let call = metadata.pallet("Balances").unwrap().call("transfer").unwrap();
+/// let dest = call.fields().next().unwrap();
+/// let bytes = hex::decode("008eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48").unwrap();
+///
+/// let mut counter = FieldCounter::default();
+/// decoder::decode_with_visitor(&metadata, dest.ty(), &mut &*bytes, &mut counter).unwrap();
+///
+/// // The `Id` variant has one field, holding an `AccountId32`, which has one field.
+/// assert_eq!(counter.0, 2);
+/// ```
+pub fn decode_with_visitor<Id: Into<TypeId>, V: Visitor>(
+	metadata: &Metadata,
+	ty: Id,
+	data: &mut &[u8],
+	visitor: &mut V,
+) -> Result<(), V::Error> {
+	visitor::decode_with_visitor(data, ty.into(), metadata.types(), visitor)
+}
+
 /// Generate a [`StorageDecoder`] struct which is capable of decoding SCALE encoded storage keys. It's advisable
 /// to cache this struct if you are decoding lots of storage entries, since it is non-trivial to create.
 ///
@@ -132,6 +290,74 @@ pub fn decode_storage(metadata: &Metadata) -> StorageDecoder {
 	decode_storage::StorageDecoder::generate_from_metadata(metadata)
 }
 
+/// Decode the SCALE encoded events stored at `System.Events` against the metadata provided. Conceptually, these
+/// are expected to take the form `Vec<EventRecord>`, where each record is made up of the [`Phase`] in which the event
+/// was emitted, the event itself (a `u8` pallet index, a `u8` event index and then the event fields), and a vector
+/// of topics.
+///
+/// # Example
+///
+/// ```rust
+/// use hex;
+/// use desub_current::{ Metadata, decoder::{ self, Phase } };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// // A single `System.NewAccount` event emitted while applying the first extrinsic:
+/// let events_hex = "0x0400000000000003d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d00";
+/// let events_bytes = hex::decode(events_hex.strip_prefix("0x").unwrap()).unwrap();
+/// let events_cursor = &mut &*events_bytes;
+///
+/// let events = decoder::decode_events(&metadata, events_cursor).unwrap();
+///
+/// assert_eq!(events_cursor.len(), 0);
+/// assert_eq!(events.len(), 1);
+/// assert_eq!(events[0].phase, Phase::ApplyExtrinsic(0));
+/// assert_eq!(events[0].event.pallet_name, "System");
+/// assert_eq!(&*events[0].event.ty.name(), "NewAccount");
+/// ```
+pub fn decode_events<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<Vec<EventRecord<'a>>, DecodeError> {
+	decode_events::decode_events(metadata, data)
+}
+
+/// Look up the pallet error that a `DispatchError::Module { index, error }` points to, given the pallet index and the
+/// error bytes. The error can be provided in the older single byte form, or the newer `[u8; 4]` form, where the first
+/// byte identifies the error variant and the rest contain any data associated with it. That data is decoded according
+/// to the fields of the error, and handed back in [`ModuleError::arguments`].
+///
+/// # Example
+///
+/// ```rust
+/// use desub_current::{ Metadata, decoder };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// // Pallet 5 is "Balances", and error 2 is "InsufficientBalance":
+/// let error = decoder::decode_module_error(&metadata, 5, &[2, 0, 0, 0]).unwrap();
+///
+/// assert_eq!(error.pallet_name, "Balances");
+/// assert_eq!(error.ty.name(), "InsufficientBalance");
+/// assert_eq!(error.ty.docs(), &["Balance too low to send value"]);
+/// ```
+pub fn decode_module_error<'a>(
+	metadata: &'a Metadata,
+	pallet_index: u8,
+	error: &[u8],
+) -> Result<ModuleError<'a>, ModuleErrorDecodeError> {
+	decode_module_error::decode_module_error(metadata, pallet_index, error)
+}
+
+/// Look up the pallet error that a decoded `DispatchError` value (for instance, from an `ExtrinsicFailed` event)
+/// points to. An error is returned if the value is not a `DispatchError::Module`.
+pub fn decode_dispatch_error<'a, T>(
+	metadata: &'a Metadata,
+	dispatch_error: &Value<T>,
+) -> Result<ModuleError<'a>, ModuleErrorDecodeError> {
+	decode_module_error::decode_dispatch_error(metadata, dispatch_error)
+}
+
 /// Decode a SCALE encoded vector of extrinsics against the metadata provided. Conceptually, extrinsics are
 /// expected to be provided in a SCALE-encoded form equivalent to `Vec<(Compact<u32>,Extrinsic)>`; in other words, we
 /// start with a compact encoded count of how many extrinsics exist, and then each extrinsic is prefixed by
@@ -164,7 +390,8 @@ pub fn decode_extrinsics<'a>(
 
 	log::trace!("Decoding {} Total Extrinsics.", extrinsic_bytes.len());
 
-	let mut out = Vec::with_capacity(extrinsic_bytes.len());
+	// The count of extrinsics comes from the input, so we don't allocate room for them up front.
+	let mut out = Vec::new();
 	let mut extrinsics_iter = extrinsic_bytes.iter();
 	for res in &mut extrinsics_iter {
 		let single_extrinsic = match res {
@@ -196,6 +423,149 @@ pub fn decode_extrinsics<'a>(
 	Ok(out)
 }
 
+/// An extrinsic which could not be decoded by [`decode_extrinsics_resilient`].
+pub type ExtrinsicDecodeFailure = desub_common::ExtrinsicDecodeFailure<DecodeError>;
+
+/// Decode a SCALE encoded vector of extrinsics against the metadata provided, like [`decode_extrinsics`], but carry
+/// on decoding after an extrinsic fails, and return a result for each one. Since each extrinsic is prefixed with its
+/// length, one extrinsic failing to decode doesn't prevent us from decoding the rest. If the length prefixes are
+/// themselves malformed, we can't go any further, and the last failure contains all of the remaining bytes.
+///
+/// An error is returned if the bytes don't begin with a compact encoded count of extrinsics.
+///
+/// # Example
+///
+/// ```rust
+/// use hex;
+/// use desub_current::{ Metadata, decoder };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// // An extrinsic with an unknown pallet index (0xFF) in between two valid ones:
+/// let extrinsics_hex = "0x0C2004480104080c10140c04FF002004480104080c1014";
+/// let extrinsics_bytes = hex::decode(extrinsics_hex.strip_prefix("0x").unwrap()).unwrap();
+/// let extrinsics_cursor = &mut &*extrinsics_bytes;
+///
+/// let extrinsics = decoder::decode_extrinsics_resilient(&metadata, extrinsics_cursor).unwrap();
+///
+/// assert_eq!(extrinsics_cursor.len(), 0);
+/// assert_eq!(extrinsics.len(), 3);
+/// assert!(extrinsics[0].is_ok() && extrinsics[2].is_ok());
+///
+/// let failure = extrinsics[1].as_ref().unwrap_err();
+/// assert_eq!(failure.index, 1);
+/// assert_eq!(failure.bytes, vec![0x04, 0xFF, 0x00]);
+/// ```
+pub fn decode_extrinsics_resilient<'a>(
+	metadata: &'a Metadata,
+	data: &mut &[u8],
+) -> Result<Vec<Result<Extrinsic<'a>, ExtrinsicDecodeFailure>>, DecodeError> {
+	let extrinsic_bytes = AllExtrinsicBytes::new(*data)?;
+
+	log::trace!("Decoding {} Total Extrinsics.", extrinsic_bytes.len());
+
+	// The count of extrinsics comes from the input, so we don't allocate room for them up front.
+	let mut out = Vec::new();
+	let mut extrinsics_iter = extrinsic_bytes.iter();
+	while let Some(res) = extrinsics_iter.next() {
+		let index = out.len();
+		let single_extrinsic = match res {
+			Ok(bytes) => bytes.bytes(),
+			Err(e) => {
+				// We can't find the next extrinsic, so hand back everything that's left.
+				let bytes = extrinsics_iter.remaining_bytes().to_vec();
+				out.push(Err(ExtrinsicDecodeFailure { index, bytes, error: e.into() }));
+				break;
+			}
+		};
+
+		log::trace!("Extrinsic:{:?}", single_extrinsic);
+
+		let bytes = &mut &*single_extrinsic;
+		let res = match decode_unwrapped_extrinsic(metadata, bytes) {
+			Ok(_) if !bytes.is_empty() => Err(DecodeError::ExcessBytes(bytes.len())),
+			res => res,
+		};
+
+		out.push(res.map_err(|error| ExtrinsicDecodeFailure { index, bytes: single_extrinsic.to_vec(), error }));
+	}
+
+	*data = extrinsics_iter.remaining_bytes();
+
+	Ok(out)
+}
+
+/// Decode a SCALE encoded block header. This takes the shape of a `sp_runtime::generic::Header`, and doesn't
+/// depend on the metadata.
+pub fn decode_header(data: &mut &[u8]) -> Result<Header, DecodeError> {
+	decode_block::decode_header(data)
+}
+
+/// Decode a SCALE encoded block against the metadata provided. Conceptually, this is expected to take the form
+/// `(Header, Vec<Extrinsic>)`, where the extrinsics are in the same form as expected by [`decode_extrinsics`].
+///
+/// # Example
+///
+/// ```rust
+/// use hex;
+/// use desub_current::{ Metadata, decoder::{ self, DigestItem } };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// // Block 1, with a single `RuntimeEnvironmentUpdated` digest item and a single extrinsic:
+/// let (parent_hash, state_root, extrinsics_root) = ("00".repeat(32), "11".repeat(32), "22".repeat(32));
+/// let block_hex = format!("{}04{}{}0408042004480104080c1014", parent_hash, state_root, extrinsics_root);
+/// let block_bytes = hex::decode(block_hex).unwrap();
+/// let block_cursor = &mut &*block_bytes;
+///
+/// let block = decoder::decode_block(&metadata, block_cursor).unwrap();
+///
+/// assert_eq!(block_cursor.len(), 0);
+/// assert_eq!(block.header.number, 1);
+/// assert_eq!(block.header.digest, vec![DigestItem::RuntimeEnvironmentUpdated]);
+/// assert_eq!(block.extrinsics.len(), 1);
+/// ```
+pub fn decode_block<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<Block<'a>, DecodeError> {
+	decode_block::decode_block(metadata, data)
+}
+
+/// Decode a SCALE encoded signed block against the metadata provided. This is a block (see [`decode_block`]),
+/// followed by any justifications for it, and is the shape of the block returned from the `chain_getBlock` RPC call.
+pub fn decode_signed_block<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<SignedBlock<'a>, DecodeError> {
+	decode_block::decode_signed_block(metadata, data)
+}
+
+/// Decode the payload of a header digest item, if it is a pre-runtime or consensus digest from BABE, Aura, GRANDPA
+/// or BEEFY. These types aren't described in the metadata, and so are decoded according to the shapes given in the
+/// `sp_consensus_*` crates. Returns `Ok(None)` for any other digest item.
+///
+/// # Example
+///
+/// ```rust
+/// use desub_current::decoder::{ self, BabePreDigest, ConsensusDigest, DigestItem };
+///
+/// // A BABE secondary plain pre-runtime digest, with authority index 3 and slot 100:
+/// let item = DigestItem::PreRuntime(*b"BABE", hex::decode("02030000006400000000000000").unwrap());
+/// let digest = decoder::decode_consensus_digest(&item).unwrap();
+///
+/// assert_eq!(
+///     digest,
+///     Some(ConsensusDigest::BabePreDigest(BabePreDigest::SecondaryPlain { authority_index: 3, slot: 100 }))
+/// );
+/// ```
+pub fn decode_consensus_digest(item: &DigestItem) -> Result<Option<ConsensusDigest>, DecodeError> {
+	decode_consensus::decode_consensus_digest(item)
+}
+
+/// Find the author of a block from its BABE or Aura pre-runtime digest, given the authority set that was active
+/// when the block was produced (for instance, decoded from `Babe.Authorities` or `Aura.Authorities` storage).
+/// Returns `Ok(None)` if the header has no such digest, or the author isn't in the authority set provided.
+pub fn block_author<'a, A>(header: &Header, authorities: &'a [A]) -> Result<Option<&'a A>, DecodeError> {
+	decode_consensus::block_author(header, authorities)
+}
+
 /// Decode a SCALE encoded extrinsic against the metadata provided. Conceptually, an individual extrinsic is expected
 /// to be represented in terms of a compact encoded count of its length in bytes, and then the actual extrinsic
 /// information (the optional signature and call data).
@@ -263,13 +633,13 @@ pub fn decode_unwrapped_extrinsic<'a>(metadata: &'a Metadata, data: &mut &[u8])
 		return Err(DecodeError::EarlyEof("unwrapped extrinsic byte length should be > 0"));
 	}
 
-	// V4 extrinsics (the format we can decode here) are laid out roughly as follows:
+	// V4 extrinsics are laid out roughly as follows:
 	//
 	// first byte: abbbbbbb (a = 0 for unsigned, 1 for signed, b = version)
 	//
 	// signature, which is made up of (in order):
-	// - sp_runtime::MultiAddress enum (sender)
-	// - sp_runtime::MultiSignature enum
+	// - address (sender), normally a sp_runtime::MultiAddress enum (the type is given in the metadata)
+	// - signature, normally a sp_runtime::MultiSignature enum (the type is given in the metadata)
 	// - For polkadot, these extensions (but can vary by chain, so we decode generically):
 	//   - sp_runtime::generic::Era enum
 	//   - compact encoded u32 (nonce; prior transaction count)
@@ -280,26 +650,48 @@ pub fn decode_unwrapped_extrinsic<'a>(metadata: &'a Metadata, data: &mut &[u8])
 	// - u8 call index (for inner variant)
 	// - call args (types can be pulled from metadata for each arg we expect)
 	//
-	// So, we start by getting the version/signed from the first byte and go from there.
-	let is_signed = data[0] & 0b1000_0000 != 0;
-	let version = data[0] & 0b0111_1111;
+	// V5 extrinsics use the top two bits of the first byte to describe the kind of extrinsic instead:
+	//
+	// first byte: aabbbbbb (aa = 00 for bare, 01 for general, b = version)
+	//
+	// Bare extrinsics are then laid out as above. General extrinsics have no address or signature, and
+	// instead carry a u8 transaction extension version followed by the extensions, before the call data.
+	// Signed extrinsics only exist in V4; as in Substrate, a V5 extrinsic with the signed bit set is an error.
+	//
+	// So, we start by getting the version/kind from the first byte and go from there.
+	let start_len = data.len();
+	let version = data[0] & EXTRINSIC_VERSION_MASK;
+	let kind = data[0] & !EXTRINSIC_VERSION_MASK;
 	*data = &data[1..];
 
-	// We only know how to decode V4 extrinsics at the moment
-	if version != 4 {
+	// We know how to decode V4 and V5 extrinsics at the moment
+	if version != 4 && version != 5 {
 		return Err(DecodeError::CannotDecodeExtrinsicVersion(version));
 	}
 
-	// If the extrinsic is signed, decode the signature next.
-	let signature = match is_signed {
-		true => Some(decode_signature(metadata, data)?),
-		false => None,
+	// Decode the signature or extensions next, depending on the kind of extrinsic.
+	let (kind, signature) = match kind {
+		EXTRINSIC_BARE => (ExtrinsicKind::Bare, None),
+		EXTRINSIC_SIGNED if version == 4 => {
+			(ExtrinsicKind::Signed, Some(decode_part(start_len, data, |data| decode_signature(metadata, data))?))
+		}
+		EXTRINSIC_GENERAL if version == 5 => {
+			if data.is_empty() {
+				return Err(DecodeError::EarlyEof("expected a transaction extension version byte"));
+			}
+			let extension_version = u8::decode(data)?;
+			let extensions = decode_part(start_len, data, |data| decode_signed_extensions(metadata, data))?;
+			(ExtrinsicKind::General { extension_version, extensions }, None)
+		}
+		_ => return Err(DecodeError::CannotDecodeExtrinsicKind { version, kind }),
 	};
 
-	// Finally, decode the call data.
-	let call_data = decode_call_data(metadata, data)?;
+	// Finally, decode the call data, holding on to the bytes it was decoded from.
+	let call_start = *data;
+	let call_data = decode_part(start_len, data, |data| decode_call_data(metadata, data))?;
+	let call_bytes = call_start[..call_start.len() - data.len()].to_vec();
 
-	Ok(Extrinsic { call_data, signature })
+	Ok(Extrinsic { version, kind, call_data, call_bytes, signature })
 }
 
 /// Decode SCALE encoded call data. Conceptually, this is expected to take the form of
@@ -329,6 +721,7 @@ pub fn decode_unwrapped_extrinsic<'a>(metadata: &'a Metadata, data: &mut &[u8])
 /// ```
 pub fn decode_call_data<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<CallData<'a>, DecodeError> {
 	// Pluck out the u8's representing the pallet and call enum next.
+	let start_len = data.len();
 	if data.len() < 2 {
 		return Err(DecodeError::EarlyEof("expected at least 2 more bytes for the pallet/call index"));
 	}
@@ -337,30 +730,99 @@ pub fn decode_call_data<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<
 	log::trace!("pallet index: {}, call index: {}", pallet_index, call_index);
 
 	// Work out which call the extrinsic data represents and get type info for it:
-	let (pallet_name, variant) = match metadata.call_variant_by_enum_index(pallet_index, call_index) {
+	let (pallet_name, variant) = match metadata.variant_by_enum_index(PalletEnum::Calls, pallet_index, call_index) {
 		Some(call) => call,
 		None => return Err(DecodeError::CannotFindCall(pallet_index, call_index)),
 	};
 
-	// Decode each of the argument values in the extrinsic:
+	// Decode each of the argument values in the extrinsic. Errors are located by the pallet, call and
+	// argument name (or index), and their offset from the start of the call data. If the argument types
+	// are all unnamed, the pallet's call enum is the innermost named type that we were decoding.
+	let call_enum = metadata.pallet_enum_type(PalletEnum::Calls, pallet_index);
 	let arguments = variant
 		.fields()
 		.iter()
-		.map(|field| {
+		.enumerate()
+		.map(|(idx, field)| {
 			let id = field.ty().id();
-			decode_value_by_id(metadata, TypeId::from_u32(id), data).map_err(DecodeError::DecodeValueError)
+			let decode_arg = |data: &mut &[u8]| {
+				let value = decode_value_by_id(metadata, TypeId::from_u32(id), data);
+				Ok(value.map_err(|e| match call_enum {
+					Some(ty) => e.in_type(ty),
+					None => e,
+				})?)
+			};
+			decode_part(start_len, data, decode_arg)
+				.map_err(|e| e.at(field_segment(field.name(), idx)).at(variant.name()).at(pallet_name))
 		})
 		.collect::<Result<Vec<_>, _>>()?;
 
 	Ok(CallData { pallet_name: Cow::Borrowed(pallet_name), ty: Cow::Borrowed(variant), arguments })
 }
 
+/// Decode the SCALE encoded arguments given to a runtime API method (for instance, the data passed to a `state_call`
+/// RPC request), returning the name and value of each argument. The method is looked up by name (for example,
+/// `TransactionPaymentApi_query_info`) in the [`RuntimeApiDefinitions`] provided.
+///
+/// # Example
+///
+/// ```rust
+/// use hex;
+/// use desub_current::{ Metadata, Value, decoder::{ self, RuntimeApiDefinitions, RuntimeApiMethod } };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// // V14 metadata doesn't contain runtime API details, so we define the methods we need by hand, using
+/// // types found in the metadata: `System.remark` takes a `Vec<u8>`, the second argument to `Scheduler.cancel`
+/// // is a `u32`, and the `System.ExtrinsicSuccess` event holds a `DispatchInfo`:
+/// let system = metadata.pallet("System").unwrap();
+/// let vec_u8 = system.call("remark").unwrap().fields().next().unwrap().ty();
+/// let dispatch_info = system.event("ExtrinsicSuccess").unwrap().fields().next().unwrap().ty();
+/// let u32 = metadata.pallet("Scheduler").unwrap().call("cancel").unwrap().fields().nth(1).unwrap().ty();
+///
+/// let mut definitions = RuntimeApiDefinitions::new();
+/// definitions.insert(
+///     "TransactionPaymentApi_query_info",
+///     RuntimeApiMethod { inputs: vec![("uxt".into(), vec_u8), ("len".into(), u32)], output: dispatch_info },
+/// );
+///
+/// let args_bytes = hex::decode("0c01020300010000").unwrap();
+/// let method = "TransactionPaymentApi_query_info";
+/// let call = decoder::decode_runtime_api_call(&metadata, &definitions, method, &mut &*args_bytes).unwrap();
+///
+/// assert_eq!(call.arguments[0].0, "uxt");
+/// assert_eq!(call.arguments[1].0, "len");
+/// assert_eq!(call.arguments[1].1.clone().without_context(), Value::u32(256));
+/// ```
+pub fn decode_runtime_api_call<'a>(
+	metadata: &Metadata,
+	definitions: &'a RuntimeApiDefinitions,
+	method: &'a str,
+	data: &mut &[u8],
+) -> Result<RuntimeApiCall<'a>, DecodeError> {
+	decode_runtime_api::decode_runtime_api_call(metadata, definitions, method, data)
+}
+
+/// Decode the SCALE encoded value returned from a runtime API method (for instance, the result of a `state_call`
+/// RPC request). The method is looked up by name in the [`RuntimeApiDefinitions`] provided. The data should contain
+/// nothing but the returned value, and [`DecodeError::ExcessBytes`] is returned if any of it is left over.
+pub fn decode_runtime_api_result(
+	metadata: &Metadata,
+	definitions: &RuntimeApiDefinitions,
+	method: &str,
+	data: &mut &[u8],
+) -> Result<Value<TypeId>, DecodeError> {
+	decode_runtime_api::decode_runtime_api_result(metadata, definitions, method, data)
+}
+
 /// Decode the SCALE encoded data that, once signed, is used to construct a signed extrinsic. The encoded payload has the following shape:
 /// `(call_data, signed_extensions, additional_signed)`.
 pub fn decode_signer_payload<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<SignerPayload<'a>, DecodeError> {
+	let start_len = data.len();
 	let call_data = decode_call_data(metadata, data)?;
-	let signed_extensions = decode_signed_extensions(metadata, data)?;
-	let additional_signed = decode_additional_signed(metadata, data)?;
+	let signed_extensions = decode_part(start_len, data, |data| decode_signed_extensions(metadata, data))?;
+	let additional_signed = decode_part(start_len, data, |data| decode_additional_signed(metadata, data))?;
 	let extensions = signed_extensions
 		.into_iter()
 		.zip(additional_signed)
@@ -374,9 +836,11 @@ pub fn decode_signer_payload<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Re
 ///
 /// Ordinarily, one should prefer to use [`decode_extrinsic`] directly to decode the entire extrinsic at once.
 pub fn decode_signature<'a>(metadata: &'a Metadata, data: &mut &[u8]) -> Result<ExtrinsicSignature<'a>, DecodeError> {
-	let address = <MultiAddress<AccountId32, u32>>::decode(data)?;
-	let signature = MultiSignature::decode(data)?;
-	let extensions = decode_signed_extensions(metadata, data)?;
+	let start_len = data.len();
+	let address = decode_signature::decode_address(metadata, data).map_err(|e| e.at("address"))?;
+	let signature = decode_part(start_len, data, |data| decode_signature::decode_signature(metadata, data))
+		.map_err(|e| e.at("signature"))?;
+	let extensions = decode_part(start_len, data, |data| decode_signed_extensions(metadata, data))?;
 
 	Ok(ExtrinsicSignature { address, signature, extensions })
 }
@@ -389,12 +853,14 @@ pub fn decode_signed_extensions<'a>(
 	metadata: &'a Metadata,
 	data: &mut &[u8],
 ) -> Result<Vec<(Cow<'a, str>, Value<TypeId>)>, DecodeError> {
+	let start_len = data.len();
 	metadata
 		.extrinsic()
 		.signed_extensions()
 		.iter()
 		.map(|ext| {
-			let val = decode_value_by_id(metadata, &ext.ty, data)?;
+			let val = decode_part(start_len, data, |data| Ok(decode_value_by_id(metadata, &ext.ty, data)?))
+				.map_err(|e| e.at(&ext.identifier))?;
 			let name = Cow::Borrowed(&*ext.identifier);
 			Ok((name, val))
 		})
@@ -409,18 +875,39 @@ pub fn decode_additional_signed<'a>(
 	metadata: &'a Metadata,
 	data: &mut &[u8],
 ) -> Result<Vec<(Cow<'a, str>, Value<TypeId>)>, DecodeError> {
+	let start_len = data.len();
 	metadata
 		.extrinsic()
 		.signed_extensions()
 		.iter()
 		.map(|ext| {
-			let val = decode_value_by_id(metadata, &ext.additional_signed, data)?;
+			let val =
+				decode_part(start_len, data, |data| Ok(decode_value_by_id(metadata, &ext.additional_signed, data)?))
+					.map_err(|e| e.at(&ext.identifier))?;
 			let name = Cow::Borrowed(&*ext.identifier);
 			Ok((name, val))
 		})
 		.collect()
 }
 
+/// Verify the signature of a decoded, signed extrinsic. The payload that was signed is rebuilt from the extrinsic
+/// and the [`ChainContext`] provided (which supplies the "additional signed" data that isn't included in the
+/// extrinsic itself), and then the signature is checked against it and the sender address. Payloads longer than
+/// 256 bytes are `blake2_256` hashed before being checked, as they would have been when signing.
+///
+/// Returns `Ok(true)` if the signature is valid, `Ok(false)` if it is not, and an error if the signature cannot
+/// be checked (for instance, if the extrinsic is unsigned or its address is not an account ID).
+///
+/// **Note:** Additional signed data is constructed for the `CheckSpecVersion`, `CheckTxVersion`, `CheckGenesis`
+/// and `CheckMortality` signed extensions. Any other signed extensions are expected to have no additional data.
+pub fn verify_signature(
+	metadata: &Metadata,
+	extrinsic: &Extrinsic,
+	context: &ChainContext,
+) -> Result<bool, VerifySignatureError> {
+	verify_signature::verify_signature(metadata, extrinsic, context)
+}
+
 /// Decoded call data and associated type information.
 #[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct CallData<'a> {
@@ -442,14 +929,47 @@ impl<'a> CallData<'a> {
 			arguments: self.arguments,
 		}
 	}
+
+	/// The decoded arguments, with type information attached to each of them (see [`TypeInfo`]).
+	/// The top level arguments are given the type names of the call's fields.
+	pub fn arguments_with_type_info(&self, metadata: &Metadata) -> Vec<Value<TypeInfo>> {
+		with_field_type_info(self.ty.fields(), &self.arguments, metadata)
+	}
+}
+
+// Attach type information to the values decoded for the fields of some call or event variant.
+fn with_field_type_info(
+	fields: &[scale_info::Field<scale_info::form::PortableForm>],
+	values: &[Value<TypeId>],
+	metadata: &Metadata,
+) -> Vec<Value<TypeInfo>> {
+	values
+		.iter()
+		.enumerate()
+		.map(|(idx, val)| {
+			let type_name = fields.get(idx).and_then(|f| f.type_name().cloned());
+			val.clone().with_type_info_and_name(type_name, metadata.types())
+		})
+		.collect()
 }
 
 /// The result of successfully decoding an extrinsic.
 #[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct Extrinsic<'a> {
+	/// The extrinsic format version (4 or 5). This is only serialized for V5 extrinsics, so that
+	/// V4 extrinsics serialize the same way that they always have.
+	#[serde(skip_serializing_if = "is_v4")]
+	pub version: u8,
+	/// The kind of extrinsic that this is. Bare and signed extrinsics can be told apart by
+	/// [`Extrinsic::signature`], so this is only serialized for general extrinsics.
+	#[serde(borrow, skip_serializing_if = "ExtrinsicKind::is_bare_or_signed")]
+	pub kind: ExtrinsicKind<'a>,
 	/// Decoded call data and associated type information about the call.
 	#[serde(borrow)]
 	pub call_data: CallData<'a>,
+	/// The SCALE encoded call data, exactly as it appeared in the extrinsic.
+	#[serde(skip)]
+	pub call_bytes: Vec<u8>,
 	/// The signature and signed extensions (if any) associated with the extrinsic
 	#[serde(borrow)]
 	pub signature: Option<ExtrinsicSignature<'a>>,
@@ -457,7 +977,53 @@ pub struct Extrinsic<'a> {
 
 impl<'a> Extrinsic<'a> {
 	pub fn into_owned(self) -> Extrinsic<'static> {
-		Extrinsic { call_data: self.call_data.into_owned(), signature: self.signature.map(|s| s.into_owned()) }
+		Extrinsic {
+			version: self.version,
+			kind: self.kind.into_owned(),
+			call_data: self.call_data.into_owned(),
+			call_bytes: self.call_bytes,
+			signature: self.signature.map(|s| s.into_owned()),
+		}
+	}
+}
+
+fn is_v4(version: &u8) -> bool {
+	*version == 4
+}
+
+/// The kind of an extrinsic. V4 extrinsics are either bare (unsigned) or signed, and V5
+/// extrinsics can also be general transactions, which carry extensions but no signature.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum ExtrinsicKind<'a> {
+	/// An extrinsic with no signature or extensions (ie an unsigned or inherent extrinsic).
+	Bare,
+	/// A signed extrinsic. The signature and signed extensions are available in [`Extrinsic::signature`].
+	Signed,
+	/// A general transaction, which has extensions but no signature.
+	General {
+		/// The version of the transaction extensions used.
+		extension_version: u8,
+		/// The transaction extensions, which can vary by node. Here, we
+		/// return the name and value of each.
+		#[serde(borrow)]
+		extensions: Vec<(Cow<'a, str>, Value<TypeId>)>,
+	},
+}
+
+impl<'a> ExtrinsicKind<'a> {
+	fn is_bare_or_signed(&self) -> bool {
+		matches!(self, ExtrinsicKind::Bare | ExtrinsicKind::Signed)
+	}
+
+	pub fn into_owned(self) -> ExtrinsicKind<'static> {
+		match self {
+			ExtrinsicKind::Bare => ExtrinsicKind::Bare,
+			ExtrinsicKind::Signed => ExtrinsicKind::Signed,
+			ExtrinsicKind::General { extension_version, extensions } => ExtrinsicKind::General {
+				extension_version,
+				extensions: extensions.into_iter().map(|(k, v)| (Cow::Owned(k.into_owned()), v)).collect(),
+			},
+		}
 	}
 }
 
@@ -465,10 +1031,9 @@ impl<'a> Extrinsic<'a> {
 #[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct ExtrinsicSignature<'a> {
 	/// Address the extrinsic is being sent from
-	#[serde(with = "desub_common::RemoteAddress")]
-	pub address: MultiAddress<AccountId32, u32>,
+	pub address: Address,
 	/// Signature to prove validity
-	pub signature: MultiSignature,
+	pub signature: Signature,
 	/// Signed extensions, which can vary by node. Here, we
 	/// return the name and value of each.
 	#[serde(borrow)]
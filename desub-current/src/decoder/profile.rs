@@ -0,0 +1,131 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Serializing decoded extrinsics and blocks with a [`SerializeProfile`](crate::value::SerializeProfile).
+//! These mirror the derived `Serialize` impls, but hand the profile down to each [`Value`](crate::Value).
+
+use super::{Address, Block, CallData, Extrinsic, ExtrinsicKind, ExtrinsicSignature, Signature};
+use crate::value::WithProfile;
+use serde::{
+	ser::{SerializeStruct, SerializeStructVariant},
+	Serialize, Serializer,
+};
+
+impl Serialize for WithProfile<'_, Block<'_>> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let block = self.item();
+		let mut state = serializer.serialize_struct("Block", 2)?;
+		state.serialize_field("header", &block.header)?;
+		state.serialize_field("extrinsics", &self.wrap(&block.extrinsics))?;
+		state.end()
+	}
+}
+
+impl Serialize for WithProfile<'_, Extrinsic<'_>> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let ext = self.item();
+		let with_version = ext.version != 4;
+		let with_kind = !ext.kind.is_bare_or_signed();
+		let len = 2 + with_version as usize + with_kind as usize;
+
+		let mut state = serializer.serialize_struct("Extrinsic", len)?;
+		if with_version {
+			state.serialize_field("version", &ext.version)?;
+		}
+		if with_kind {
+			state.serialize_field("kind", &self.wrap(&ext.kind))?;
+		}
+		state.serialize_field("call_data", &self.wrap(&ext.call_data))?;
+		state.serialize_field("signature", &self.wrap(&ext.signature))?;
+		state.end()
+	}
+}
+
+impl Serialize for WithProfile<'_, ExtrinsicKind<'_>> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match self.item() {
+			ExtrinsicKind::Bare => serializer.serialize_unit_variant("ExtrinsicKind", 0, "Bare"),
+			ExtrinsicKind::Signed => serializer.serialize_unit_variant("ExtrinsicKind", 1, "Signed"),
+			ExtrinsicKind::General { extension_version, extensions } => {
+				let mut state = serializer.serialize_struct_variant("ExtrinsicKind", 2, "General", 2)?;
+				state.serialize_field("extension_version", extension_version)?;
+				state.serialize_field("extensions", &self.wrap(extensions))?;
+				state.end()
+			}
+		}
+	}
+}
+
+impl Serialize for WithProfile<'_, CallData<'_>> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let call_data = self.item();
+		let mut state = serializer.serialize_struct("CallData", 3)?;
+		state.serialize_field("pallet_name", &call_data.pallet_name)?;
+		state.serialize_field("ty", &call_data.ty)?;
+		state.serialize_field("arguments", &self.wrap(&call_data.arguments))?;
+		state.end()
+	}
+}
+
+impl Serialize for WithProfile<'_, ExtrinsicSignature<'_>> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let signature = self.item();
+		let mut state = serializer.serialize_struct("ExtrinsicSignature", 3)?;
+		state.serialize_field("address", &self.wrap(&signature.address))?;
+		state.serialize_field("signature", &self.wrap(&signature.signature))?;
+		state.serialize_field("extensions", &self.wrap(&signature.extensions))?;
+		state.end()
+	}
+}
+
+impl Serialize for WithProfile<'_, Address> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match self.item() {
+			Address::Value(value) => self.wrap(value).serialize(serializer),
+			address => address.serialize(serializer),
+		}
+	}
+}
+
+impl Serialize for WithProfile<'_, Signature> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match self.item() {
+			Signature::Value(value) => self.wrap(value).serialize(serializer),
+			signature => signature.serialize(serializer),
+		}
+	}
+}
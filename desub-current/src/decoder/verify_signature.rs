@@ -0,0 +1,121 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::Extrinsic;
+use crate::encoder::{ExtrinsicBuilder, ExtrinsicEncodeError};
+use crate::metadata::Metadata;
+use crate::value::{Composite, Value, ValueDef};
+use crate::TypeId;
+use sp_core::blake2_256;
+use sp_runtime::traits::Verify;
+
+/// Signer payloads longer than this many bytes are hashed before being signed.
+const MAX_UNHASHED_PAYLOAD_LEN: usize = 256;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum VerifySignatureError {
+	#[error("Cannot verify the signature of an extrinsic that isn't signed")]
+	NotSigned,
+	#[error("Cannot verify signatures from an address of this type; the account ID is needed")]
+	UnsupportedAddress,
+	#[error("Cannot verify signatures of this type; only MultiSignatures are supported")]
+	UnsupportedSignature,
+	#[error("The extrinsic is mortal, and so the checkpoint block hash is needed to verify its signature")]
+	MissingCheckpointHash,
+	#[error("Couldn't rebuild the signer payload: {0}")]
+	CouldNotBuildPayload(#[from] ExtrinsicEncodeError),
+}
+
+/// Details about the chain that an extrinsic was submitted to. These aren't part of the extrinsic itself,
+/// but are included in the payload that gets signed (via the "additional signed" data associated with
+/// each signed extension), and so are needed in order to verify signatures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainContext {
+	/// The hash of the genesis block of the chain.
+	pub genesis_hash: [u8; 32],
+	/// The spec version of the runtime that the extrinsic was signed for.
+	pub spec_version: u32,
+	/// The transaction version of the runtime that the extrinsic was signed for.
+	pub transaction_version: u32,
+	/// The hash of the block that the mortality period of the extrinsic starts from. This
+	/// is not needed for immortal extrinsics, which use the genesis hash instead.
+	pub checkpoint_hash: Option<[u8; 32]>,
+}
+
+pub(super) fn verify_signature(
+	metadata: &Metadata,
+	extrinsic: &Extrinsic,
+	context: &ChainContext,
+) -> Result<bool, VerifySignatureError> {
+	let signature = extrinsic.signature.as_ref().ok_or(VerifySignatureError::NotSigned)?;
+
+	let signer = signature.address.account_id().ok_or(VerifySignatureError::UnsupportedAddress)?;
+	let multi_signature = signature.signature.as_multi_signature().ok_or(VerifySignatureError::UnsupportedSignature)?;
+
+	// Rebuild the payload that was signed: `(call_data, signed_extensions, additional_signed)`. The call
+	// data is used exactly as it was given, since re-encoding the decoded call isn't guaranteed to hand
+	// back the same bytes.
+	let extensions: Vec<_> = signature.extensions.iter().map(|(_, value)| value.clone()).collect();
+	let additional_signed = signature
+		.extensions
+		.iter()
+		.map(|(name, value)| additional_signed_value(name, value, context))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let mut payload = ExtrinsicBuilder::from_encoded_call_data(metadata, extrinsic.call_bytes.clone())
+		.signer_payload(&extensions, &additional_signed)?;
+
+	// Long payloads are hashed before they are signed:
+	if payload.len() > MAX_UNHASHED_PAYLOAD_LEN {
+		payload = blake2_256(&payload).to_vec();
+	}
+
+	Ok(multi_signature.verify(&*payload, &signer))
+}
+
+/// Work out the additional signed value that goes with the signed extension given. We know how to
+/// construct these for the standard signed extensions, and otherwise expect there to be no value.
+fn additional_signed_value(
+	name: &str,
+	extension: &Value<TypeId>,
+	context: &ChainContext,
+) -> Result<Value<()>, VerifySignatureError> {
+	let value = match name {
+		"CheckSpecVersion" => Value::u32(context.spec_version),
+		"CheckTxVersion" => Value::u32(context.transaction_version),
+		"CheckGenesis" => hash_value(&context.genesis_hash),
+		"CheckMortality" | "CheckEra" if is_immortal(extension) => hash_value(&context.genesis_hash),
+		"CheckMortality" | "CheckEra" => {
+			hash_value(context.checkpoint_hash.as_ref().ok_or(VerifySignatureError::MissingCheckpointHash)?)
+		}
+		_ => Value::unnamed_composite(vec![]),
+	};
+	Ok(value)
+}
+
+fn hash_value(hash: &[u8; 32]) -> Value<()> {
+	Value::unnamed_composite(hash.iter().map(|&b| Value::u8(b)).collect())
+}
+
+/// Is the era (possibly wrapped in some newtype structs) immortal?
+fn is_immortal<T>(era: &Value<T>) -> bool {
+	match &era.value {
+		ValueDef::Variant(variant) => variant.name == "Immortal",
+		ValueDef::Composite(Composite::Unnamed(values)) if values.len() == 1 => is_immortal(&values[0]),
+		ValueDef::Composite(Composite::Named(values)) if values.len() == 1 => is_immortal(&values[0].1),
+		_ => false,
+	}
+}
@@ -0,0 +1,395 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{DecodeValueError, DecodeValueErrorKind};
+use crate::value::{BitSequence, Primitive};
+use crate::{Type, TypeId};
+use codec::{Compact, Decode};
+use desub_common::PathSegment;
+use scale_info::{
+	form::PortableForm, Field, PortableRegistry, TypeDefArray, TypeDefComposite, TypeDefPrimitive, TypeDefSequence,
+	TypeDefTuple, TypeDefVariant, Variant,
+};
+
+// This is used in several places below.
+type TypeDef = scale_info::TypeDef<PortableForm>;
+
+/// A [`Visitor`] is handed each part of a value as it is decoded by [`super::decode_with_visitor`], rather than
+/// the value being collected into a [`crate::Value`]. Every method has a default implementation which does
+/// nothing, so a visitor need only implement the methods it cares about.
+///
+/// Each method is given the ID of the type being decoded, which can be resolved in the metadata's type registry
+/// to find out more about it (for instance its path, in order to pick out every `AccountId32`).
+///
+/// Composite values (structs), sequences (including arrays and tuples) and variants are each bracketed by
+/// a `_start` and an `_end` call, between which the values that they contain are visited in order. Each
+/// field of a composite or variant is preceded by a call to [`Visitor::visit_field`].
+pub trait Visitor {
+	/// The error returned from visiting. Errors encountered while decoding the bytes are converted into this.
+	type Error: From<DecodeValueError>;
+
+	/// Called for each primitive value other than strings, which are handed to [`Visitor::visit_str`].
+	/// Compact encoded values are decoded and then handed to this as well.
+	fn visit_primitive(&mut self, _ty: TypeId, _value: Primitive) -> Result<(), Self::Error> {
+		Ok(())
+	}
+
+	/// Called for each string. The string is borrowed from the bytes being decoded; by default, it is
+	/// copied and handed to [`Visitor::visit_primitive`].
+	fn visit_str(&mut self, ty: TypeId, value: &str) -> Result<(), Self::Error> {
+		self.visit_primitive(ty, Primitive::Str(value.to_owned()))
+	}
+
+	/// Called at the start of a composite (struct-like) value, with the fields that it contains.
+	fn visit_composite_start(&mut self, _ty: TypeId, _fields: &[Field<PortableForm>]) -> Result<(), Self::Error> {
+		Ok(())
+	}
+
+	/// Called at the end of a composite value.
+	fn visit_composite_end(&mut self, _ty: TypeId) -> Result<(), Self::Error> {
+		Ok(())
+	}
+
+	/// Called before each field of a composite value or variant is visited, with the name of the field
+	/// if it has one.
+	fn visit_field(&mut self, _name: Option<&str>) -> Result<(), Self::Error> {
+		Ok(())
+	}
+
+	/// Called at the start of a variant (enum) value. The variant that was found in the bytes is provided.
+	fn visit_variant_start(&mut self, _ty: TypeId, _variant: &Variant<PortableForm>) -> Result<(), Self::Error> {
+		Ok(())
+	}
+
+	/// Called at the end of a variant value.
+	fn visit_variant_end(&mut self, _ty: TypeId) -> Result<(), Self::Error> {
+		Ok(())
+	}
+
+	/// Called at the start of a sequence, array or tuple, with the number of values that it contains. For sequences,
+	/// this length is read from the input, so it shouldn't be trusted when deciding how much to allocate.
+	fn visit_sequence_start(&mut self, _ty: TypeId, _len: usize) -> Result<(), Self::Error> {
+		Ok(())
+	}
+
+	/// Called at the end of a sequence, array or tuple.
+	fn visit_sequence_end(&mut self, _ty: TypeId) -> Result<(), Self::Error> {
+		Ok(())
+	}
+
+	/// Called for each bit sequence.
+	fn visit_bit_sequence(&mut self, _ty: TypeId, _value: BitSequence) -> Result<(), Self::Error> {
+		Ok(())
+	}
+}
+
+/// Walk over some SCALE encoded data according to the [`TypeId`] provided, handing each part of it to
+/// the [`Visitor`]. The provided pointer to the data slice will be moved forwards as needed depending
+/// on what was decoded.
+///
+/// If decoding fails, the [`DecodeValueError`] handed to the visitor's error type records where in the
+/// value and the input bytes things went wrong.
+pub fn decode_with_visitor<V: Visitor>(
+	data: &mut &[u8],
+	ty_id: TypeId,
+	types: &PortableRegistry,
+	visitor: &mut V,
+) -> Result<(), V::Error> {
+	let start_len = data.len();
+	visit_value(data, ty_id, types, visitor).map_err(|e| match e {
+		VisitError::Decode { error, remaining } => error.offset_by(start_len - remaining.unwrap_or(start_len)).into(),
+		VisitError::Visitor(e) => e,
+	})
+}
+
+/// Errors from decoding are located as they bubble up, and are converted into the
+/// visitor's error type once we're done. Errors from the visitor are passed straight through.
+enum VisitError<E> {
+	Decode {
+		error: DecodeValueError,
+		/// The number of bytes remaining when we began decoding the value that failed.
+		remaining: Option<usize>,
+	},
+	Visitor(E),
+}
+
+impl<E> VisitError<E> {
+	fn at(self, segment: impl Into<PathSegment>) -> Self {
+		match self {
+			VisitError::Decode { error, remaining } => VisitError::Decode { error: error.at(segment), remaining },
+			e => e,
+		}
+	}
+}
+
+// Errors created while decoding a value are given a location once they bubble out of `visit_value`.
+impl<E> From<DecodeValueError> for VisitError<E> {
+	fn from(error: DecodeValueError) -> Self {
+		VisitError::Decode { error, remaining: None }
+	}
+}
+
+impl<E> From<DecodeValueErrorKind> for VisitError<E> {
+	fn from(kind: DecodeValueErrorKind) -> Self {
+		DecodeValueError::from(kind).into()
+	}
+}
+
+impl<E> From<codec::Error> for VisitError<E> {
+	fn from(error: codec::Error) -> Self {
+		DecodeValueError::from(error).into()
+	}
+}
+
+fn visit_value<V: Visitor>(
+	data: &mut &[u8],
+	ty_id: TypeId,
+	types: &PortableRegistry,
+	visitor: &mut V,
+) -> Result<(), VisitError<V::Error>> {
+	let remaining = data.len();
+	let ty = resolve(ty_id.id(), types).map_err(|error| VisitError::Decode { error, remaining: Some(remaining) })?;
+
+	let res = match ty.type_def() {
+		TypeDef::Composite(inner) => visit_composite(data, ty_id, inner, types, visitor),
+		TypeDef::Sequence(inner) => visit_sequence(data, ty_id, inner, types, visitor),
+		TypeDef::Array(inner) => visit_array(data, ty_id, inner, types, visitor),
+		TypeDef::Tuple(inner) => visit_tuple(data, ty_id, inner, types, visitor),
+		TypeDef::Variant(inner) => visit_variant(data, ty_id, inner, types, visitor),
+		TypeDef::Primitive(inner) => visit_primitive(data, ty_id, inner, visitor),
+		TypeDef::Compact(inner) => resolve(inner.type_param().id(), types)
+			.map_err(VisitError::from)
+			.and_then(|inner_ty| visit_compact(data, ty_id, inner_ty, types, visitor)),
+		TypeDef::BitSequence(_) => {
+			// [jsdw] TODO: might be worth checking the bit_store and bit_order types
+			// and trying to work out whether they look like Lsb0 and u8, which is what
+			// we assume here.
+			BitSequence::decode(data)
+				.map_err(VisitError::from)
+				.and_then(|bits| visitor.visit_bit_sequence(ty_id, bits).map_err(VisitError::Visitor))
+		}
+	};
+
+	// The innermost value that fails is the one whose position and type we report.
+	res.map_err(|e| match e {
+		VisitError::Decode { error, remaining: r } => {
+			VisitError::Decode { error: error.in_type(ty), remaining: r.or(Some(remaining)) }
+		}
+		e => e,
+	})
+}
+
+fn resolve(id: u32, types: &PortableRegistry) -> Result<&Type, DecodeValueError> {
+	types.resolve(id).ok_or_else(|| DecodeValueErrorKind::TypeIdNotFound(id).into())
+}
+
+fn visit_composite<V: Visitor>(
+	data: &mut &[u8],
+	ty_id: TypeId,
+	ty: &TypeDefComposite<PortableForm>,
+	types: &PortableRegistry,
+	visitor: &mut V,
+) -> Result<(), VisitError<V::Error>> {
+	visitor.visit_composite_start(ty_id, ty.fields()).map_err(VisitError::Visitor)?;
+	visit_fields(data, ty.fields(), types, visitor)?;
+	visitor.visit_composite_end(ty_id).map_err(VisitError::Visitor)
+}
+
+fn visit_variant<V: Visitor>(
+	data: &mut &[u8],
+	ty_id: TypeId,
+	ty: &TypeDefVariant<PortableForm>,
+	types: &PortableRegistry,
+	visitor: &mut V,
+) -> Result<(), VisitError<V::Error>> {
+	let index = *data.first().ok_or(DecodeValueErrorKind::Eof)?;
+	*data = &data[1..];
+
+	// Does a variant exist with the index we're looking for?
+	let variant = ty
+		.variants()
+		.iter()
+		.find(|v| v.index() == index)
+		.ok_or_else(|| DecodeValueErrorKind::VariantNotFound(index, ty.clone()))?;
+
+	visitor.visit_variant_start(ty_id, variant).map_err(VisitError::Visitor)?;
+	visit_fields(data, variant.fields(), types, visitor).map_err(|e| e.at(variant.name()))?;
+	visitor.visit_variant_end(ty_id).map_err(VisitError::Visitor)
+}
+
+/// Variant and Composite types both have fields; this will visit each of them in turn.
+fn visit_fields<V: Visitor>(
+	data: &mut &[u8],
+	fields: &[Field<PortableForm>],
+	types: &PortableRegistry,
+	visitor: &mut V,
+) -> Result<(), VisitError<V::Error>> {
+	for (idx, field) in fields.iter().enumerate() {
+		visitor.visit_field(field.name().map(|n| n.as_str())).map_err(VisitError::Visitor)?;
+		visit_value(data, field.ty().into(), types, visitor).map_err(|e| match field_segment(fields, idx) {
+			Some(segment) => e.at(segment),
+			None => e,
+		})?;
+	}
+	Ok(())
+}
+
+/// The segment of an error path that a field is known by. A lone unnamed field (for instance, in a
+/// newtype wrapper or a variant like `RuntimeCall::Balances(..)`) doesn't add anything to the path.
+fn field_segment(fields: &[Field<PortableForm>], idx: usize) -> Option<PathSegment> {
+	match fields[idx].name() {
+		Some(name) => Some(name.into()),
+		None if fields.len() > 1 => Some(idx.into()),
+		None => None,
+	}
+}
+
+fn visit_sequence<V: Visitor>(
+	data: &mut &[u8],
+	ty_id: TypeId,
+	ty: &TypeDefSequence<PortableForm>,
+	types: &PortableRegistry,
+	visitor: &mut V,
+) -> Result<(), VisitError<V::Error>> {
+	// We assume that the sequence is preceeded by a compact encoded length, so that
+	// we know how many values to try pulling out of the data.
+	let len = Compact::<u64>::decode(data)?.0 as usize;
+	visit_items(data, ty_id, (0..len).map(|_| ty.type_param()), len, types, visitor)
+}
+
+fn visit_array<V: Visitor>(
+	data: &mut &[u8],
+	ty_id: TypeId,
+	ty: &TypeDefArray<PortableForm>,
+	types: &PortableRegistry,
+	visitor: &mut V,
+) -> Result<(), VisitError<V::Error>> {
+	// The length is known based on the type we want to decode into, so we pull out the number of items according
+	// to that, and don't need a length to exist in the SCALE encoded bytes
+	let len = ty.len() as usize;
+	visit_items(data, ty_id, (0..len).map(|_| ty.type_param()), len, types, visitor)
+}
+
+fn visit_tuple<V: Visitor>(
+	data: &mut &[u8],
+	ty_id: TypeId,
+	ty: &TypeDefTuple<PortableForm>,
+	types: &PortableRegistry,
+	visitor: &mut V,
+) -> Result<(), VisitError<V::Error>> {
+	visit_items(data, ty_id, ty.fields().iter(), ty.fields().len(), types, visitor)
+}
+
+/// Sequences, arrays and tuples all contain some number of unnamed values; this will visit each of them in turn.
+fn visit_items<'a, V: Visitor>(
+	data: &mut &[u8],
+	ty_id: TypeId,
+	item_tys: impl Iterator<Item = &'a crate::ScaleInfoTypeId>,
+	len: usize,
+	types: &PortableRegistry,
+	visitor: &mut V,
+) -> Result<(), VisitError<V::Error>> {
+	visitor.visit_sequence_start(ty_id, len).map_err(VisitError::Visitor)?;
+	for (idx, item_ty) in item_tys.enumerate() {
+		visit_value(data, item_ty.into(), types, visitor).map_err(|e| e.at(idx))?;
+	}
+	visitor.visit_sequence_end(ty_id).map_err(VisitError::Visitor)
+}
+
+fn visit_primitive<V: Visitor>(
+	data: &mut &[u8],
+	ty_id: TypeId,
+	ty: &TypeDefPrimitive,
+	visitor: &mut V,
+) -> Result<(), VisitError<V::Error>> {
+	fn decode<T: Decode>(data: &mut &[u8]) -> Result<T, codec::Error> {
+		T::decode(data)
+	}
+
+	let val = match ty {
+		TypeDefPrimitive::Bool => Primitive::Bool(decode(data)?),
+		TypeDefPrimitive::Char => {
+			// [jsdw] TODO: There isn't a `char::decode`. Why? Is it wrong to use u32 or is there a more "proper" way?
+			let val = decode(data)?;
+			Primitive::Char(char::from_u32(val).ok_or(DecodeValueErrorKind::InvalidChar(val))?)
+		}
+		TypeDefPrimitive::Str => {
+			// Strings are borrowed straight out of the data, to avoid allocating.
+			let len = decode::<Compact<u32>>(data)?.0 as usize;
+			if data.len() < len {
+				return Err(DecodeValueErrorKind::Eof.into());
+			}
+			let (bytes, rest) = data.split_at(len);
+			let s = std::str::from_utf8(bytes).map_err(|_| codec::Error::from("Invalid utf8 in string"))?;
+			*data = rest;
+			return visitor.visit_str(ty_id, s).map_err(VisitError::Visitor);
+		}
+		TypeDefPrimitive::U8 => Primitive::U8(decode(data)?),
+		TypeDefPrimitive::U16 => Primitive::U16(decode(data)?),
+		TypeDefPrimitive::U32 => Primitive::U32(decode(data)?),
+		TypeDefPrimitive::U64 => Primitive::U64(decode(data)?),
+		TypeDefPrimitive::U128 => Primitive::U128(decode(data)?),
+		TypeDefPrimitive::U256 => Primitive::U256(decode(data)?),
+		TypeDefPrimitive::I8 => Primitive::I8(decode(data)?),
+		TypeDefPrimitive::I16 => Primitive::I16(decode(data)?),
+		TypeDefPrimitive::I32 => Primitive::I32(decode(data)?),
+		TypeDefPrimitive::I64 => Primitive::I64(decode(data)?),
+		TypeDefPrimitive::I128 => Primitive::I128(decode(data)?),
+		TypeDefPrimitive::I256 => Primitive::I256(decode(data)?),
+	};
+	visitor.visit_primitive(ty_id, val).map_err(VisitError::Visitor)
+}
+
+fn visit_compact<V: Visitor>(
+	data: &mut &[u8],
+	ty_id: TypeId,
+	inner: &Type,
+	types: &PortableRegistry,
+	visitor: &mut V,
+) -> Result<(), VisitError<V::Error>> {
+	fn decode<T>(data: &mut &[u8]) -> Result<T, codec::Error>
+	where
+		Compact<T>: Decode,
+	{
+		Ok(Compact::<T>::decode(data)?.0)
+	}
+
+	use TypeDefPrimitive::*;
+	let val = match inner.type_def() {
+		// It's obvious how to decode basic primitive unsigned types, since we have impls for them.
+		TypeDef::Primitive(U8) => Primitive::U8(decode::<u8>(data)?),
+		TypeDef::Primitive(U16) => Primitive::U16(decode::<u16>(data)?),
+		TypeDef::Primitive(U32) => Primitive::U32(decode::<u32>(data)?),
+		TypeDef::Primitive(U64) => Primitive::U64(decode::<u64>(data)?),
+		TypeDef::Primitive(U128) => Primitive::U128(decode::<u128>(data)?),
+		// A struct with exactly 1 field containing one of the above types can be sensibly compact encoded/decoded.
+		TypeDef::Composite(composite) if composite.fields().len() == 1 => {
+			// What type is the 1 field that we are able to decode?
+			let field = &composite.fields()[0];
+			let inner_ty = resolve(field.ty().id(), types)?;
+
+			// Decode this inner type via compact decoding. This can recurse, in case
+			// the inner type is also a 1-field composite type.
+			visitor.visit_composite_start(ty_id, composite.fields()).map_err(VisitError::Visitor)?;
+			visitor.visit_field(field.name().map(|n| n.as_str())).map_err(VisitError::Visitor)?;
+			visit_compact(data, field.ty().into(), inner_ty, types, visitor)?;
+			return visitor.visit_composite_end(ty_id).map_err(VisitError::Visitor);
+		}
+		// For now, we give up if we have been asked for any other type:
+		_cannot_decode_from => return Err(DecodeValueErrorKind::CannotDecodeCompactIntoType(inner.clone()).into()),
+	};
+	visitor.visit_primitive(ty_id, val).map_err(VisitError::Visitor)
+}
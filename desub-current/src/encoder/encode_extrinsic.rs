@@ -0,0 +1,224 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::encode_value::{encode_value_by_id, EncodeValueError};
+use crate::decoder::{Address, CallData, Signature};
+use crate::metadata::Metadata;
+use crate::value::Value;
+use crate::ScaleInfoTypeId;
+use codec::{Compact, Encode};
+use frame_metadata::v14::SignedExtensionMetadata;
+use scale_info::form::PortableForm;
+
+/// The version of the extrinsic format that we know how to construct.
+const EXTRINSIC_VERSION: u8 = 4;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ExtrinsicEncodeError {
+	#[error("Couldn't find a call named '{call}' in the pallet '{pallet}'")]
+	CallNotFound { pallet: String, call: String },
+	#[error("Wrong number of arguments provided for this call; expected {expected} but got {got}")]
+	WrongNumberOfArguments { expected: usize, got: usize },
+	#[error("Couldn't encode the value provided for argument '{argument}': {encode_error}")]
+	CouldNotEncodeArgument { argument: String, encode_error: EncodeValueError },
+	#[error("The metadata does not contain the address type, so the address can't be encoded from a Value")]
+	AddressTypeNotFound,
+	#[error("Couldn't encode the address: {0}")]
+	CouldNotEncodeAddress(EncodeValueError),
+	#[error("The metadata does not contain the signature type, so the signature can't be encoded from a Value")]
+	SignatureTypeNotFound,
+	#[error("Couldn't encode the signature: {0}")]
+	CouldNotEncodeSignature(EncodeValueError),
+	#[error("Wrong number of signed extensions provided; expected {expected} but got {got}")]
+	WrongNumberOfSignedExtensions { expected: usize, got: usize },
+	#[error("Couldn't encode the value provided for signed extension '{name}': {encode_error}")]
+	CouldNotEncodeSignedExtension { name: String, encode_error: EncodeValueError },
+}
+
+/// This struct is capable of constructing SCALE encoded extrinsics from some call. See
+/// [`super::build_extrinsic`] for an example of its use.
+pub struct ExtrinsicBuilder<'a> {
+	metadata: &'a Metadata,
+	call_data: Vec<u8>,
+}
+
+impl<'a> ExtrinsicBuilder<'a> {
+	/// Call [`super::build_extrinsic()`] to construct an [`ExtrinsicBuilder`].
+	pub(super) fn new<T>(
+		metadata: &'a Metadata,
+		pallet: &str,
+		call: &str,
+		arguments: &[Value<T>],
+	) -> Result<ExtrinsicBuilder<'a>, ExtrinsicEncodeError> {
+		let call_data = encode_call_data(metadata, pallet, call, arguments)?;
+		Ok(ExtrinsicBuilder { metadata, call_data })
+	}
+
+	/// Call [`super::build_extrinsic_from_call_data()`] to construct an [`ExtrinsicBuilder`].
+	pub(super) fn from_call_data(
+		metadata: &'a Metadata,
+		call_data: &CallData<'_>,
+	) -> Result<ExtrinsicBuilder<'a>, ExtrinsicEncodeError> {
+		ExtrinsicBuilder::new(metadata, &call_data.pallet_name, call_data.ty.name(), &call_data.arguments)
+	}
+
+	/// Construct an [`ExtrinsicBuilder`] around call data that has already been SCALE encoded.
+	pub(crate) fn from_encoded_call_data(metadata: &'a Metadata, call_data: Vec<u8>) -> ExtrinsicBuilder<'a> {
+		ExtrinsicBuilder { metadata, call_data }
+	}
+
+	/// The SCALE encoded call data that this builder will wrap into an extrinsic.
+	pub fn call_data(&self) -> &[u8] {
+		&self.call_data
+	}
+
+	/// Build an unsigned extrinsic.
+	pub fn unsigned(&self) -> Vec<u8> {
+		let mut extrinsic = vec![EXTRINSIC_VERSION];
+		extrinsic.extend(&self.call_data);
+		wrap_extrinsic(extrinsic)
+	}
+
+	/// Build a signed extrinsic. Values for each of the signed extensions must be provided, in the same
+	/// order as they are listed in the metadata (see [`crate::metadata::MetadataExtrinsic::signed_extensions`]).
+	///
+	/// Standard `MultiAddress` and `MultiSignature` types can be converted into an [`Address`] and [`Signature`]
+	/// using `.into()`. See [`ExtrinsicBuilder::signed_with_values`] to provide other address and signature types.
+	pub fn signed<T>(
+		&self,
+		address: &Address,
+		signature: &Signature,
+		extensions: &[Value<T>],
+	) -> Result<Vec<u8>, ExtrinsicEncodeError> {
+		let mut extrinsic = vec![EXTRINSIC_VERSION | 0b1000_0000];
+		match address {
+			Address::MultiAddress(address) => address.encode_to(&mut extrinsic),
+			Address::Value(address) => self.encode_address(address, &mut extrinsic)?,
+		}
+		match signature {
+			Signature::MultiSignature(signature) => signature.encode_to(&mut extrinsic),
+			Signature::Value(signature) => self.encode_signature(signature, &mut extrinsic)?,
+		}
+		self.encode_signed_extensions(extensions, |ext| &ext.ty, &mut extrinsic)?;
+		extrinsic.extend(&self.call_data);
+		Ok(wrap_extrinsic(extrinsic))
+	}
+
+	/// Build a signed extrinsic, like [`ExtrinsicBuilder::signed`], but with the address and signature provided as
+	/// [`Value`]s, which are encoded according to the address and signature types given in the metadata. This
+	/// allows for chains that use other address and signature types, such as `AccountId20` and `EthereumSignature`.
+	pub fn signed_with_values<T, U>(
+		&self,
+		address: &Value<T>,
+		signature: &Value<T>,
+		extensions: &[Value<U>],
+	) -> Result<Vec<u8>, ExtrinsicEncodeError> {
+		let mut extrinsic = vec![EXTRINSIC_VERSION | 0b1000_0000];
+		self.encode_address(address, &mut extrinsic)?;
+		self.encode_signature(signature, &mut extrinsic)?;
+		self.encode_signed_extensions(extensions, |ext| &ext.ty, &mut extrinsic)?;
+		extrinsic.extend(&self.call_data);
+		Ok(wrap_extrinsic(extrinsic))
+	}
+
+	/// Build the payload that must be signed in order to construct a signed extrinsic. This takes the form
+	/// `(call_data, signed_extensions, additional_signed)`, and is the counterpart to
+	/// [`crate::decoder::decode_signer_payload`]. Values for each signed extension and the additional data
+	/// that goes with it must be provided, in the same order as they are listed in the metadata.
+	///
+	/// **Note:** Payloads longer than 256 bytes are expected to be `blake2_256` hashed before signing them.
+	pub fn signer_payload<T, U>(
+		&self,
+		extensions: &[Value<T>],
+		additional_signed: &[Value<U>],
+	) -> Result<Vec<u8>, ExtrinsicEncodeError> {
+		let mut payload = self.call_data.clone();
+		self.encode_signed_extensions(extensions, |ext| &ext.ty, &mut payload)?;
+		self.encode_signed_extensions(additional_signed, |ext| &ext.additional_signed, &mut payload)?;
+		Ok(payload)
+	}
+
+	fn encode_address<T>(&self, address: &Value<T>, out: &mut Vec<u8>) -> Result<(), ExtrinsicEncodeError> {
+		let ty = self.metadata.extrinsic().address_type().ok_or(ExtrinsicEncodeError::AddressTypeNotFound)?;
+		encode_value_by_id(address, ty, self.metadata.types(), out).map_err(ExtrinsicEncodeError::CouldNotEncodeAddress)
+	}
+
+	fn encode_signature<T>(&self, signature: &Value<T>, out: &mut Vec<u8>) -> Result<(), ExtrinsicEncodeError> {
+		let ty = self.metadata.extrinsic().signature_type().ok_or(ExtrinsicEncodeError::SignatureTypeNotFound)?;
+		encode_value_by_id(signature, ty, self.metadata.types(), out)
+			.map_err(ExtrinsicEncodeError::CouldNotEncodeSignature)
+	}
+
+	// Encode a value for each signed extension, using the type that the function provided
+	// picks out for each one.
+	fn encode_signed_extensions<T>(
+		&self,
+		values: &[Value<T>],
+		ty: impl Fn(&SignedExtensionMetadata<PortableForm>) -> &ScaleInfoTypeId,
+		out: &mut Vec<u8>,
+	) -> Result<(), ExtrinsicEncodeError> {
+		let signed_extensions = self.metadata.extrinsic().signed_extensions();
+		if values.len() != signed_extensions.len() {
+			return Err(ExtrinsicEncodeError::WrongNumberOfSignedExtensions {
+				expected: signed_extensions.len(),
+				got: values.len(),
+			});
+		}
+
+		for (value, ext) in values.iter().zip(signed_extensions) {
+			encode_value_by_id(value, ty(ext), self.metadata.types(), out).map_err(|e| {
+				ExtrinsicEncodeError::CouldNotEncodeSignedExtension { name: ext.identifier.clone(), encode_error: e }
+			})?;
+		}
+		Ok(())
+	}
+}
+
+/// Encode call data, which takes the form `(u8, u8, arguments)`, where the `u8`s are the pallet and
+/// call indexes, and the arguments are encoded according to the type of each field in the call.
+fn encode_call_data<T>(
+	metadata: &Metadata,
+	pallet: &str,
+	call: &str,
+	arguments: &[Value<T>],
+) -> Result<Vec<u8>, ExtrinsicEncodeError> {
+	let (pallet_index, variant) = metadata
+		.call_variant_by_name(pallet, call)
+		.ok_or_else(|| ExtrinsicEncodeError::CallNotFound { pallet: pallet.to_owned(), call: call.to_owned() })?;
+
+	let fields = variant.fields();
+	if arguments.len() != fields.len() {
+		return Err(ExtrinsicEncodeError::WrongNumberOfArguments { expected: fields.len(), got: arguments.len() });
+	}
+
+	let mut out = vec![pallet_index, variant.index()];
+	for (idx, (value, field)) in arguments.iter().zip(fields).enumerate() {
+		encode_value_by_id(value, field.ty(), metadata.types(), &mut out).map_err(|e| {
+			ExtrinsicEncodeError::CouldNotEncodeArgument {
+				argument: field.name().cloned().unwrap_or_else(|| idx.to_string()),
+				encode_error: e,
+			}
+		})?;
+	}
+	Ok(out)
+}
+
+/// Extrinsics are prefixed with their compact encoded length in bytes.
+fn wrap_extrinsic(extrinsic: Vec<u8>) -> Vec<u8> {
+	let mut out = Compact(extrinsic.len() as u32).encode();
+	out.extend(extrinsic);
+	out
+}
@@ -0,0 +1,102 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::encode_value::{encode_value_by_id, EncodeValueError};
+use crate::decoder::storage_map_key_to_type_id_vec;
+use crate::metadata::Metadata;
+use crate::value::Value;
+use frame_metadata::v14::{StorageEntryType as FrameStorageEntryType, StorageHasher as FrameStorageHasher};
+use sp_core::{blake2_128, blake2_256, twox_128, twox_256, twox_64};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum StorageEncodeError {
+	#[error("Couldn't find a storage entry with the prefix '{prefix}' and name '{name}'")]
+	EntryNotFound { prefix: String, name: String },
+	#[error("Too many keys provided for this storage entry; expected at most {expected} but got {got}")]
+	TooManyKeys { expected: usize, got: usize },
+	#[error(
+		"The metadata for this storage entry has {keys} key types but {hashers} hashers, so keys can't be encoded"
+	)]
+	KeysAndHashersMismatch { keys: usize, hashers: usize },
+	#[error("Couldn't encode the value provided for key {key}: {encode_error}")]
+	CouldNotEncodeKey { key: usize, encode_error: EncodeValueError },
+}
+
+pub(super) fn encode_storage_key<T>(
+	metadata: &Metadata,
+	prefix: &str,
+	name: &str,
+	keys: &[Value<T>],
+) -> Result<Vec<u8>, StorageEncodeError> {
+	let storage_entry = metadata
+		.storage_entry_by_name(prefix, name)
+		.ok_or_else(|| StorageEncodeError::EntryNotFound { prefix: prefix.to_owned(), name: name.to_owned() })?;
+
+	// Every storage key starts with the hashed prefix and name:
+	let mut out = Vec::with_capacity(32);
+	out.extend(twox_128(storage_entry.prefix.as_bytes()));
+	out.extend(twox_128(storage_entry.metadata.name.as_bytes()));
+
+	match &storage_entry.metadata.ty {
+		FrameStorageEntryType::Plain(_) => {
+			if !keys.is_empty() {
+				return Err(StorageEncodeError::TooManyKeys { expected: 0, got: keys.len() });
+			}
+		}
+		FrameStorageEntryType::Map { hashers, key, .. } => {
+			let key_types = storage_map_key_to_type_id_vec(metadata, key, hashers.len());
+			if key_types.len() != hashers.len() {
+				return Err(StorageEncodeError::KeysAndHashersMismatch {
+					keys: key_types.len(),
+					hashers: hashers.len(),
+				});
+			}
+			if keys.len() > hashers.len() {
+				return Err(StorageEncodeError::TooManyKeys { expected: hashers.len(), got: keys.len() });
+			}
+
+			// Fewer keys than hashers is fine; the result is a partial key which can be used
+			// to iterate over all of the entries that share it as a prefix.
+			for (idx, ((value, hasher), ty)) in keys.iter().zip(hashers).zip(key_types).enumerate() {
+				let mut encoded = Vec::new();
+				encode_value_by_id(value, ty, metadata.types(), &mut encoded)
+					.map_err(|e| StorageEncodeError::CouldNotEncodeKey { key: idx, encode_error: e })?;
+				hash_key(hasher, &encoded, &mut out);
+			}
+		}
+	}
+
+	Ok(out)
+}
+
+/// Hash the SCALE encoded key using the hasher provided, pushing the resulting bytes to the output.
+fn hash_key(hasher: &FrameStorageHasher, encoded: &[u8], out: &mut Vec<u8>) {
+	match hasher {
+		FrameStorageHasher::Blake2_128 => out.extend(blake2_128(encoded)),
+		FrameStorageHasher::Blake2_256 => out.extend(blake2_256(encoded)),
+		FrameStorageHasher::Blake2_128Concat => {
+			out.extend(blake2_128(encoded));
+			out.extend(encoded);
+		}
+		FrameStorageHasher::Twox128 => out.extend(twox_128(encoded)),
+		FrameStorageHasher::Twox256 => out.extend(twox_256(encoded)),
+		FrameStorageHasher::Twox64Concat => {
+			out.extend(twox_64(encoded));
+			out.extend(encoded);
+		}
+		FrameStorageHasher::Identity => out.extend(encoded),
+	}
+}
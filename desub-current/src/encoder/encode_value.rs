@@ -0,0 +1,674 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::metadata::primitive_name;
+use crate::value::{Composite, Primitive, Value, ValueDef};
+use crate::TypeId;
+use codec::{Compact, Encode};
+use desub_common::{PathSegment, ValuePath};
+use scale_info::{
+	form::PortableForm, Field, PortableRegistry, TypeDefArray, TypeDefCompact, TypeDefComposite, TypeDefPrimitive,
+	TypeDefSequence, TypeDefTuple, TypeDefVariant,
+};
+use std::convert::TryFrom;
+
+// This is used in several places below.
+type TypeDef = scale_info::TypeDef<PortableForm>;
+
+/// An error encoding a [`Value`]. This contains the path to the value that could not be
+/// encoded, as well as details about what went wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodeValueError {
+	path: ValuePath,
+	kind: EncodeValueErrorKind,
+}
+
+impl EncodeValueError {
+	/// The path to the offending value, made up of field names, variant names and
+	/// sequence indexes. This is empty if the top level value could not be encoded.
+	pub fn path(&self) -> &[String] {
+		self.path.segments()
+	}
+
+	/// Details about what went wrong.
+	pub fn kind(&self) -> &EncodeValueErrorKind {
+		&self.kind
+	}
+
+	fn at(mut self, segment: impl Into<PathSegment>) -> Self {
+		self.path.prepend(segment);
+		self
+	}
+}
+
+impl From<EncodeValueErrorKind> for EncodeValueError {
+	fn from(kind: EncodeValueErrorKind) -> Self {
+		EncodeValueError { path: ValuePath::new(), kind }
+	}
+}
+
+impl std::fmt::Display for EncodeValueError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.path.is_empty() {
+			write!(f, "{}", self.kind)
+		} else {
+			write!(f, "field `{}` {}", self.path, self.kind)
+		}
+	}
+}
+
+impl std::error::Error for EncodeValueError {}
+
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum EncodeValueErrorKind {
+	#[error("cannot find type with ID {0}")]
+	TypeIdNotFound(u32),
+	#[error("expected {expected}, got {got}")]
+	WrongShape { expected: &'static str, got: String },
+	#[error("expected {expected} values, got {got}")]
+	WrongLength { expected: usize, got: usize },
+	#[error("could not find variant with name '{0}'")]
+	VariantNotFound(String),
+	#[error("could not find a value for the field '{0}'")]
+	FieldNotFound(String),
+	#[error("{value} is out of range for {expected}")]
+	NumberOutOfRange { value: String, expected: &'static str },
+	#[error("could not compact encode value into the type with ID {0:?}")]
+	CannotEncodeCompactIntoType(TypeId),
+}
+
+/// Encode the [`Value`] provided according to the [`TypeId`] provided, pushing
+/// the SCALE encoded bytes onto the output vector.
+pub fn encode_value_by_id<T, Id: Into<TypeId>>(
+	value: &Value<T>,
+	ty_id: Id,
+	types: &PortableRegistry,
+	out: &mut Vec<u8>,
+) -> Result<(), EncodeValueError> {
+	let ty_id = ty_id.into();
+	let ty = types.resolve(ty_id.id()).ok_or_else(|| EncodeValueErrorKind::TypeIdNotFound(ty_id.id()))?;
+
+	match ty.type_def() {
+		TypeDef::Composite(inner) => encode_composite_value(value, inner, types, out),
+		TypeDef::Sequence(inner) => encode_sequence_value(value, inner, types, out),
+		TypeDef::Array(inner) => encode_array_value(value, inner, types, out),
+		TypeDef::Tuple(inner) => encode_tuple_value(value, inner, types, out),
+		TypeDef::Variant(inner) => encode_variant_value(value, inner, types, out),
+		TypeDef::Primitive(inner) => encode_primitive_value(value, inner, out),
+		TypeDef::Compact(inner) => encode_compact_value(value, inner, types, out),
+		TypeDef::BitSequence(_) => encode_bit_sequence_value(value, out),
+	}
+}
+
+fn encode_composite_value<T>(
+	value: &Value<T>,
+	ty: &TypeDefComposite<PortableForm>,
+	types: &PortableRegistry,
+	out: &mut Vec<u8>,
+) -> Result<(), EncodeValueError> {
+	let fields = ty.fields();
+	let start_len = out.len();
+
+	// A composite value with the right number of fields can be encoded as-is.
+	let res = match &value.value {
+		ValueDef::Composite(composite) if composite.len() == fields.len() => {
+			encode_fields(composite, fields, types, out)
+		}
+		_ if fields.len() == 1 => return encode_value_by_id(value, fields[0].ty(), types, out),
+		_ => return Err(wrong_shape("composite", value)),
+	};
+
+	// Newtype wrappers are common, and so if the value doesn't line up with the wrapper,
+	// we try encoding it into the single field inside the wrapper instead. Anything written
+	// by the first attempt is discarded first.
+	match res {
+		Err(e) if fields.len() == 1 => {
+			out.truncate(start_len);
+			encode_value_by_id(value, fields[0].ty(), types, out).map_err(|_| {
+				out.truncate(start_len);
+				e
+			})
+		}
+		res => res,
+	}
+}
+
+fn encode_variant_value<T>(
+	value: &Value<T>,
+	ty: &TypeDefVariant<PortableForm>,
+	types: &PortableRegistry,
+	out: &mut Vec<u8>,
+) -> Result<(), EncodeValueError> {
+	let variant = match &value.value {
+		ValueDef::Variant(variant) => variant,
+		_ => return Err(wrong_shape("variant", value)),
+	};
+
+	// Does a variant exist with the name we're looking for?
+	let variant_ty = ty
+		.variants()
+		.iter()
+		.find(|v| v.name() == &variant.name)
+		.ok_or_else(|| EncodeValueErrorKind::VariantNotFound(variant.name.clone()))?;
+
+	variant_ty.index().encode_to(out);
+	encode_fields(&variant.values, variant_ty.fields(), types, out).map_err(|e| e.at(&variant.name))
+}
+
+/// Variant and Composite types both have fields; this will encode values into them.
+fn encode_fields<T>(
+	composite: &Composite<T>,
+	fields: &[Field<PortableForm>],
+	types: &PortableRegistry,
+	out: &mut Vec<u8>,
+) -> Result<(), EncodeValueError> {
+	if composite.len() != fields.len() {
+		return Err(EncodeValueErrorKind::WrongLength { expected: fields.len(), got: composite.len() }.into());
+	}
+
+	let are_named = fields.iter().all(|f| f.name().is_some());
+	match composite {
+		// Named values are matched up to named fields by name, so that order doesn't matter:
+		Composite::Named(values) if are_named => {
+			for field in fields {
+				let name = field.name().expect("all fields are named; checked above");
+				let (_, value) = values
+					.iter()
+					.find(|(n, _)| n == name)
+					.ok_or_else(|| EncodeValueErrorKind::FieldNotFound(name.clone()))?;
+				encode_value_by_id(value, field.ty(), types, out).map_err(|e| e.at(name))?;
+			}
+		}
+		// Otherwise we just encode values in the order that they are provided:
+		_ => {
+			for (idx, (value, field)) in composite_values(composite).zip(fields).enumerate() {
+				encode_value_by_id(value, field.ty(), types, out).map_err(|e| match field.name() {
+					Some(name) => e.at(name),
+					None => e.at(idx),
+				})?;
+			}
+		}
+	}
+	Ok(())
+}
+
+fn encode_sequence_value<T>(
+	value: &Value<T>,
+	ty: &TypeDefSequence<PortableForm>,
+	types: &PortableRegistry,
+	out: &mut Vec<u8>,
+) -> Result<(), EncodeValueError> {
+	let composite = match &value.value {
+		ValueDef::Composite(composite) => composite,
+		_ => return Err(wrong_shape("sequence", value)),
+	};
+
+	// Sequences are prefixed with their compact encoded length:
+	Compact(composite.len() as u64).encode_to(out);
+	for (idx, value) in composite_values(composite).enumerate() {
+		encode_value_by_id(value, ty.type_param(), types, out).map_err(|e| e.at(idx))?;
+	}
+	Ok(())
+}
+
+fn encode_array_value<T>(
+	value: &Value<T>,
+	ty: &TypeDefArray<PortableForm>,
+	types: &PortableRegistry,
+	out: &mut Vec<u8>,
+) -> Result<(), EncodeValueError> {
+	let composite = match &value.value {
+		ValueDef::Composite(composite) => composite,
+		_ => return Err(wrong_shape("array", value)),
+	};
+
+	// The length is known based on the type, so it isn't encoded, but must line up:
+	if composite.len() != ty.len() as usize {
+		return Err(EncodeValueErrorKind::WrongLength { expected: ty.len() as usize, got: composite.len() }.into());
+	}
+	for (idx, value) in composite_values(composite).enumerate() {
+		encode_value_by_id(value, ty.type_param(), types, out).map_err(|e| e.at(idx))?;
+	}
+	Ok(())
+}
+
+fn encode_tuple_value<T>(
+	value: &Value<T>,
+	ty: &TypeDefTuple<PortableForm>,
+	types: &PortableRegistry,
+	out: &mut Vec<u8>,
+) -> Result<(), EncodeValueError> {
+	let composite = match &value.value {
+		ValueDef::Composite(composite) => composite,
+		_ => return Err(wrong_shape("tuple", value)),
+	};
+
+	if composite.len() != ty.fields().len() {
+		return Err(EncodeValueErrorKind::WrongLength { expected: ty.fields().len(), got: composite.len() }.into());
+	}
+	for (idx, (value, field)) in composite_values(composite).zip(ty.fields()).enumerate() {
+		encode_value_by_id(value, field, types, out).map_err(|e| e.at(idx))?;
+	}
+	Ok(())
+}
+
+fn encode_primitive_value<T>(
+	value: &Value<T>,
+	ty: &TypeDefPrimitive,
+	out: &mut Vec<u8>,
+) -> Result<(), EncodeValueError> {
+	let primitive = match &value.value {
+		ValueDef::Primitive(primitive) => primitive,
+		_ => return Err(wrong_shape(primitive_name(ty), value)),
+	};
+
+	match (ty, primitive) {
+		(TypeDefPrimitive::Bool, Primitive::Bool(v)) => v.encode_to(out),
+		(TypeDefPrimitive::Char, Primitive::Char(v)) => (*v as u32).encode_to(out),
+		(TypeDefPrimitive::Str, Primitive::Str(v)) => v.encode_to(out),
+		(TypeDefPrimitive::U8, _) => to_number::<u8>(primitive, "u8")?.encode_to(out),
+		(TypeDefPrimitive::U16, _) => to_number::<u16>(primitive, "u16")?.encode_to(out),
+		(TypeDefPrimitive::U32, _) => to_number::<u32>(primitive, "u32")?.encode_to(out),
+		(TypeDefPrimitive::U64, _) => to_number::<u64>(primitive, "u64")?.encode_to(out),
+		(TypeDefPrimitive::U128, _) => to_number::<u128>(primitive, "u128")?.encode_to(out),
+		(TypeDefPrimitive::I8, _) => to_number::<i8>(primitive, "i8")?.encode_to(out),
+		(TypeDefPrimitive::I16, _) => to_number::<i16>(primitive, "i16")?.encode_to(out),
+		(TypeDefPrimitive::I32, _) => to_number::<i32>(primitive, "i32")?.encode_to(out),
+		(TypeDefPrimitive::I64, _) => to_number::<i64>(primitive, "i64")?.encode_to(out),
+		(TypeDefPrimitive::I128, _) => to_number::<i128>(primitive, "i128")?.encode_to(out),
+		(TypeDefPrimitive::U256, Primitive::U256(v)) => v.encode_to(out),
+		(TypeDefPrimitive::I256, Primitive::I256(v)) => v.encode_to(out),
+		// Smaller numbers can be extended to fit into the 256 bit types:
+		(TypeDefPrimitive::U256, _) => {
+			let n = to_number::<u128>(primitive, "u256")?;
+			let mut bytes = [0u8; 32];
+			bytes[..16].copy_from_slice(&n.to_le_bytes());
+			bytes.encode_to(out)
+		}
+		(TypeDefPrimitive::I256, _) => {
+			let n = to_number::<i128>(primitive, "i256")?;
+			let mut bytes = if n < 0 { [0xffu8; 32] } else { [0u8; 32] };
+			bytes[..16].copy_from_slice(&n.to_le_bytes());
+			bytes.encode_to(out)
+		}
+		(ty, _) => return Err(wrong_shape(primitive_name(ty), value)),
+	}
+	Ok(())
+}
+
+fn encode_compact_value<T>(
+	value: &Value<T>,
+	ty: &TypeDefCompact<PortableForm>,
+	types: &PortableRegistry,
+	out: &mut Vec<u8>,
+) -> Result<(), EncodeValueError> {
+	fn encode_compact<T>(
+		value: &Value<T>,
+		inner_id: u32,
+		types: &PortableRegistry,
+		out: &mut Vec<u8>,
+	) -> Result<(), EncodeValueError> {
+		use TypeDefPrimitive::*;
+		let inner = types.resolve(inner_id).ok_or(EncodeValueErrorKind::TypeIdNotFound(inner_id))?;
+		match inner.type_def() {
+			// Basic unsigned primitives can be compact encoded:
+			TypeDef::Primitive(prim @ (U8 | U16 | U32 | U64 | U128)) => {
+				let primitive = match &value.value {
+					ValueDef::Primitive(primitive) => primitive,
+					_ => return Err(wrong_shape(primitive_name(prim), value)),
+				};
+				match prim {
+					U8 => Compact(to_number::<u8>(primitive, "u8")?).encode_to(out),
+					U16 => Compact(to_number::<u16>(primitive, "u16")?).encode_to(out),
+					U32 => Compact(to_number::<u32>(primitive, "u32")?).encode_to(out),
+					U64 => Compact(to_number::<u64>(primitive, "u64")?).encode_to(out),
+					_ => Compact(to_number::<u128>(primitive, "u128")?).encode_to(out),
+				}
+				Ok(())
+			}
+			// A struct with exactly 1 field containing one of the above types can be compact encoded,
+			// and we'll accept the wrapped or unwrapped value here.
+			TypeDef::Composite(composite) if composite.fields().len() == 1 => {
+				let inner_value = match &value.value {
+					ValueDef::Composite(composite) if composite.len() == 1 => {
+						composite_values(composite).next().expect("1 value exists; checked above")
+					}
+					_ => value,
+				};
+				encode_compact(inner_value, composite.fields()[0].ty().id(), types, out)
+			}
+			_cannot_encode_into => {
+				Err(EncodeValueErrorKind::CannotEncodeCompactIntoType(TypeId::from_u32(inner_id)).into())
+			}
+		}
+	}
+
+	// Run the inner type through our compact encoding logic.
+	encode_compact(value, ty.type_param().id(), types, out)
+}
+
+fn encode_bit_sequence_value<T>(value: &Value<T>, out: &mut Vec<u8>) -> Result<(), EncodeValueError> {
+	// We assume the same Lsb0 + u8 bit order and store that we decode into.
+	match &value.value {
+		ValueDef::BitSequence(bits) => {
+			bits.encode_to(out);
+			Ok(())
+		}
+		_ => Err(wrong_shape("bit sequence", value)),
+	}
+}
+
+/// Iterate over the values in a composite, ignoring any names.
+fn composite_values<T>(composite: &Composite<T>) -> Box<dyn Iterator<Item = &Value<T>> + '_> {
+	match composite {
+		Composite::Named(values) => Box::new(values.iter().map(|(_, v)| v)),
+		Composite::Unnamed(values) => Box::new(values.iter()),
+	}
+}
+
+/// Convert any integer primitive into the numeric type asked for, if it fits.
+fn to_number<N>(primitive: &Primitive, expected: &'static str) -> Result<N, EncodeValueError>
+where
+	N: TryFrom<u128> + TryFrom<i128>,
+{
+	let out_of_range =
+		|value: String| EncodeValueError::from(EncodeValueErrorKind::NumberOutOfRange { value, expected });
+	match *primitive {
+		Primitive::U8(n) => N::try_from(n as u128).map_err(|_| out_of_range(n.to_string())),
+		Primitive::U16(n) => N::try_from(n as u128).map_err(|_| out_of_range(n.to_string())),
+		Primitive::U32(n) => N::try_from(n as u128).map_err(|_| out_of_range(n.to_string())),
+		Primitive::U64(n) => N::try_from(n as u128).map_err(|_| out_of_range(n.to_string())),
+		Primitive::U128(n) => N::try_from(n).map_err(|_| out_of_range(n.to_string())),
+		Primitive::I8(n) => N::try_from(n as i128).map_err(|_| out_of_range(n.to_string())),
+		Primitive::I16(n) => N::try_from(n as i128).map_err(|_| out_of_range(n.to_string())),
+		Primitive::I32(n) => N::try_from(n as i128).map_err(|_| out_of_range(n.to_string())),
+		Primitive::I64(n) => N::try_from(n as i128).map_err(|_| out_of_range(n.to_string())),
+		Primitive::I128(n) => N::try_from(n).map_err(|_| out_of_range(n.to_string())),
+		_ => {
+			Err(EncodeValueErrorKind::WrongShape { expected, got: primitive_value_name(primitive).to_string() }.into())
+		}
+	}
+}
+
+fn wrong_shape<T>(expected: &'static str, value: &Value<T>) -> EncodeValueError {
+	EncodeValueErrorKind::WrongShape { expected, got: value_name(value).to_string() }.into()
+}
+
+/// A short description of the shape of some value, for use in errors.
+fn value_name<T>(value: &Value<T>) -> &'static str {
+	match &value.value {
+		ValueDef::Composite(_) => "composite",
+		ValueDef::Variant(_) => "variant",
+		ValueDef::BitSequence(_) => "bit sequence",
+		ValueDef::Primitive(primitive) => primitive_value_name(primitive),
+	}
+}
+
+fn primitive_value_name(primitive: &Primitive) -> &'static str {
+	match primitive {
+		Primitive::Bool(_) => "bool",
+		Primitive::Char(_) => "char",
+		Primitive::Str(_) => "str",
+		Primitive::U8(_) => "u8",
+		Primitive::U16(_) => "u16",
+		Primitive::U32(_) => "u32",
+		Primitive::U64(_) => "u64",
+		Primitive::U128(_) => "u128",
+		Primitive::U256(_) => "u256",
+		Primitive::I8(_) => "i8",
+		Primitive::I16(_) => "i16",
+		Primitive::I32(_) => "i32",
+		Primitive::I64(_) => "i64",
+		Primitive::I128(_) => "i128",
+		Primitive::I256(_) => "i256",
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+	use crate::value::BitSequence;
+
+	/// Given a type definition, return the TypeId and PortableRegistry
+	/// that our encode functions expect.
+	fn make_type<T: scale_info::TypeInfo + 'static>() -> (TypeId, PortableRegistry) {
+		let m = scale_info::MetaType::new::<T>();
+		let mut types = scale_info::Registry::new();
+		let id = types.register_type(&m);
+		let portable_registry: PortableRegistry = types.into();
+
+		(id.into(), portable_registry)
+	}
+
+	/// Encode the value given using the type information from `T`.
+	fn encode_as<T: scale_info::TypeInfo + 'static>(value: Value<()>) -> Result<Vec<u8>, EncodeValueError> {
+		let (id, portable_registry) = make_type::<T>();
+		let mut out = Vec::new();
+		encode_value_by_id(&value, id, &portable_registry, &mut out)?;
+		Ok(out)
+	}
+
+	/// Check that the value given encodes to the same bytes as the expected value does.
+	fn encode_check<T: Encode + scale_info::TypeInfo + 'static>(value: Value<()>, expected: T) {
+		let encoded = encode_as::<T>(value).expect("encoding failed");
+		assert_eq!(encoded, expected.encode(), "encoded value does not match what we expected");
+	}
+
+	#[test]
+	fn encode_primitives() {
+		encode_check(Value::bool(true), true);
+		encode_check(Value::bool(false), false);
+		encode_check(Value::str("hello".into()), "hello".to_string());
+		encode_check(Value::u8(123), 123u8);
+		encode_check(Value::u16(123), 123u16);
+		encode_check(Value::u32(123), 123u32);
+		encode_check(Value::u64(123), 123u64);
+		encode_check(Value::u128(123), 123u128);
+		encode_check(Value::i8(-123), -123i8);
+		encode_check(Value::i16(-123), -123i16);
+		encode_check(Value::i32(-123), -123i32);
+		encode_check(Value::i64(-123), -123i64);
+		encode_check(Value::i128(-123), -123i128);
+	}
+
+	#[test]
+	fn encode_chars() {
+		// Chars are encoded as u32s, which don't have the same type info:
+		let encoded = encode_as::<char>(Value::char('a')).expect("encoding failed");
+		assert_eq!(encoded, ('a' as u32).encode());
+	}
+
+	#[test]
+	fn encode_numbers_into_other_sizes() {
+		// Numbers are converted into the expected type where they fit:
+		encode_check(Value::u128(123), 123u8);
+		encode_check(Value::i8(123), 123u64);
+		encode_check(Value::u8(123), 123i16);
+
+		// And are an error when they don't fit:
+		let err = encode_as::<u8>(Value::u16(256)).unwrap_err();
+		assert_eq!(err.kind(), &EncodeValueErrorKind::NumberOutOfRange { value: "256".into(), expected: "u8" });
+		let err = encode_as::<u32>(Value::i32(-1)).unwrap_err();
+		assert_eq!(err.kind(), &EncodeValueErrorKind::NumberOutOfRange { value: "-1".into(), expected: "u32" });
+	}
+
+	#[test]
+	fn encode_compact_primitives() {
+		encode_check(Value::u8(123), Compact(123u8));
+		encode_check(Value::u16(123), Compact(123u16));
+		encode_check(Value::u32(123), Compact(123u32));
+		encode_check(Value::u64(123), Compact(123u64));
+		encode_check(Value::u128(123), Compact(123u128));
+	}
+
+	#[test]
+	fn encode_sequence_array_tuple_types() {
+		encode_check(Value::unnamed_composite(vec![Value::i32(1), Value::i32(2), Value::i32(3)]), vec![1i32, 2, 3]);
+		encode_check(Value::unnamed_composite(vec![Value::i32(1), Value::i32(2), Value::i32(3)]), [1i32, 2, 3]);
+		encode_check(
+			Value::unnamed_composite(vec![Value::i32(1), Value::bool(true), Value::u128(123456)]),
+			(1i32, true, 123456u128),
+		);
+
+		// Arrays must have the right number of values:
+		let err = encode_as::<[u8; 3]>(Value::unnamed_composite(vec![Value::u8(1)])).unwrap_err();
+		assert_eq!(err.kind(), &EncodeValueErrorKind::WrongLength { expected: 3, got: 1 });
+	}
+
+	#[test]
+	fn encode_variant_types() {
+		#[derive(Encode, scale_info::TypeInfo)]
+		enum MyEnum {
+			Foo(bool),
+			#[codec(index = 10)]
+			Bar {
+				hi: String,
+				other: u128,
+			},
+		}
+
+		encode_check(Value::variant("Foo".into(), Composite::Unnamed(vec![Value::bool(true)])), MyEnum::Foo(true));
+		// Variant indexes are looked up from the name, and named fields can be given in any order:
+		encode_check(
+			Value::variant(
+				"Bar".into(),
+				Composite::Named(vec![("other".into(), Value::u128(123)), ("hi".into(), Value::str("hello".into()))]),
+			),
+			MyEnum::Bar { hi: "hello".into(), other: 123 },
+		);
+
+		let err = encode_as::<MyEnum>(Value::variant("Wibble".into(), Composite::Unnamed(vec![]))).unwrap_err();
+		assert_eq!(err.kind(), &EncodeValueErrorKind::VariantNotFound("Wibble".into()));
+	}
+
+	#[test]
+	fn encode_composite_types() {
+		#[derive(Encode, scale_info::TypeInfo)]
+		struct Unnamed(bool, String, Vec<u8>);
+
+		#[derive(Encode, scale_info::TypeInfo)]
+		struct Named {
+			is_valid: bool,
+			name: String,
+			bytes: Vec<u8>,
+		}
+
+		encode_check(
+			Value::unnamed_composite(vec![
+				Value::bool(true),
+				Value::str("James".into()),
+				Value::unnamed_composite(vec![Value::u8(1), Value::u8(2), Value::u8(3)]),
+			]),
+			Unnamed(true, "James".into(), vec![1, 2, 3]),
+		);
+		encode_check(
+			Value::named_composite(vec![
+				("name".into(), Value::str("James".into())),
+				("bytes".into(), Value::unnamed_composite(vec![Value::u8(1), Value::u8(2), Value::u8(3)])),
+				("is_valid".into(), Value::bool(true)),
+			]),
+			Named { is_valid: true, name: "James".into(), bytes: vec![1, 2, 3] },
+		);
+	}
+
+	#[test]
+	fn encode_newtype_wrappers() {
+		#[derive(Encode, scale_info::TypeInfo)]
+		struct Wrapper([u8; 2]);
+
+		// We can provide the wrapped value or the inner value:
+		encode_check(
+			Value::unnamed_composite(vec![Value::unnamed_composite(vec![Value::u8(1), Value::u8(2)])]),
+			Wrapper([1, 2]),
+		);
+		encode_check(Value::unnamed_composite(vec![Value::u8(1), Value::u8(2)]), Wrapper([1, 2]));
+	}
+
+	#[test]
+	fn encode_newtype_wrappers_after_partial_match() {
+		#[derive(Encode, scale_info::TypeInfo)]
+		struct Wrapper(Vec<(u8, u8)>);
+
+		// This looks like the wrapper at first, and some bytes are written before we find out that it isn't
+		// and try the inner value instead. Those bytes shouldn't end up in the output:
+		encode_check(
+			Value::unnamed_composite(vec![Value::unnamed_composite(vec![Value::u8(1), Value::u8(2)])]),
+			Wrapper(vec![(1, 2)]),
+		);
+	}
+
+	#[test]
+	fn encode_bit_sequence() {
+		use bitvec::{bitvec, order::Lsb0};
+
+		encode_check(Value::bit_sequence(bitvec![u8, Lsb0; 0, 1, 1, 0, 1, 0]), bitvec![u8, Lsb0; 0, 1, 1, 0, 1, 0]);
+		encode_check(Value::bit_sequence(BitSequence::new()), BitSequence::new());
+	}
+
+	#[test]
+	fn errors_contain_the_path_to_the_value() {
+		#[derive(Encode, scale_info::TypeInfo)]
+		enum MultiAddress {
+			Id([u8; 4]),
+			Index(u32),
+		}
+
+		#[derive(Encode, scale_info::TypeInfo)]
+		struct Transfer {
+			dest: MultiAddress,
+			value: u128,
+		}
+
+		// Values of the right shape encode fine:
+		encode_check(
+			Value::named_composite(vec![
+				("dest".into(), Value::variant("Index".into(), Composite::Unnamed(vec![Value::u32(1)]))),
+				("value".into(), Value::u128(1000)),
+			]),
+			Transfer { dest: MultiAddress::Index(1), value: 1000 },
+		);
+		encode_check(
+			Value::variant("Id".into(), Composite::Unnamed(vec![Value::unnamed_composite(vec![Value::u8(1); 4])])),
+			MultiAddress::Id([1; 4]),
+		);
+
+		// But otherwise, we are told where the problem is:
+		let err = encode_as::<Transfer>(Value::named_composite(vec![
+			("dest".into(), Value::u32(1)),
+			("value".into(), Value::u128(1000)),
+		]))
+		.unwrap_err();
+		assert_eq!(err.path(), &["dest".to_string()]);
+		assert_eq!(err.to_string(), "field `dest` expected variant, got u32");
+
+		let err = encode_as::<Vec<Transfer>>(Value::unnamed_composite(vec![Value::unnamed_composite(vec![
+			Value::variant("Id".into(), Composite::Unnamed(vec![Value::unnamed_composite(vec![Value::u8(1); 3])])),
+			Value::u128(1000),
+		])]))
+		.unwrap_err();
+		assert_eq!(err.to_string(), "field `[0].dest.Id[0]` expected 4 values, got 3");
+
+		// Errors at the top level have no path:
+		let err = encode_as::<Transfer>(Value::bool(true)).unwrap_err();
+		assert!(err.path().is_empty());
+		assert_eq!(err.to_string(), "expected composite, got bool");
+
+		// Variants must be given the right number of values:
+		let err = encode_as::<MultiAddress>(Value::variant("Index".into(), Composite::Unnamed(vec![]))).unwrap_err();
+		assert_eq!(err.to_string(), "field `Index` expected 1 values, got 0");
+	}
+}
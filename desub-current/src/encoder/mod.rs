@@ -0,0 +1,209 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encode [`Value`]s into SCALE encoded bytes, given some metadata. This is the
+//! counterpart to [`crate::decoder`].
+
+mod encode_extrinsic;
+mod encode_storage;
+mod encode_value;
+mod value_from_json;
+
+use crate::decoder::CallData;
+use crate::metadata::Metadata;
+use crate::value::Value;
+use crate::TypeId;
+
+pub use encode_extrinsic::{ExtrinsicBuilder, ExtrinsicEncodeError};
+pub use encode_storage::StorageEncodeError;
+pub use encode_value::{EncodeValueError, EncodeValueErrorKind};
+pub use value_from_json::{JsonValueError, JsonValueErrorKind};
+
+/// SCALE encode a single [`Value`], given some metadata and the ID of the type that it should be encoded as. This
+/// is the inverse of [`crate::decoder::decode_value_by_id`].
+///
+/// Composite values are matched up to named fields by name where possible, and variants are found by name, so that
+/// the values needn't carry any type information themselves. If the value doesn't line up with the type, the
+/// error returned includes the path to the offending value.
+///
+/// # Example
+///
+/// ```rust
+/// use codec::Encode;
+/// use desub_current::{ Metadata, Value, decoder, encoder };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// // Find the type of the values stored at Timestamp.Now:
+/// let key = encoder::encode_storage_key::<()>(&metadata, "Timestamp", "Now", &[]).unwrap();
+/// let entry = decoder::decode_storage(&metadata).decode_key(&metadata, &mut &*key).unwrap();
+///
+/// let bytes = encoder::encode_value_by_id(&metadata, entry.ty, &Value::u64(123)).unwrap();
+/// assert_eq!(bytes, 123u64.encode());
+/// ```
+pub fn encode_value_by_id<T, Id: Into<TypeId>>(
+	metadata: &Metadata,
+	ty: Id,
+	value: &Value<T>,
+) -> Result<Vec<u8>, EncodeValueError> {
+	let mut out = Vec::new();
+	encode_value::encode_value_by_id(value, ty, metadata.types(), &mut out)?;
+	Ok(out)
+}
+
+/// Convert some JSON into a [`Value`] of the type given, so that it can then be encoded or handed to
+/// [`build_extrinsic`]. The JSON needn't say anything about the widths of numbers or how things are laid out;
+/// that's all taken from the type information in the metadata.
+///
+/// JSON is accepted in the shapes that polkadot-js uses, as well as the shapes that [`Value`]s serialize to:
+///
+/// - Structs are objects keyed by field name (or the camelCase equivalent), or arrays of their fields in order.
+///   Types with a single field can be given as the value of that field.
+/// - Enum variants are objects with a single key, `{ "Id": .. }`, or just the variant name for variants without
+///   any fields. Variant names are matched case insensitively if there is no exact match. Options can also be
+///   given as `null` or as the value itself; a string is always taken to be the value itself, and never the name
+///   of a variant.
+/// - Numbers can be JSON numbers, or strings containing decimal or `0x` prefixed hex numbers to avoid losing
+///   precision. They are checked to fit the type they are converted into.
+/// - Byte sequences and arrays can be `0x` prefixed hex strings, and byte sequences can also be plain strings.
+/// - Account IDs can be SS58 addresses.
+///
+/// # Example
+///
+/// ```rust
+/// use desub_current::{ Metadata, Value, decoder, encoder };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// // Find the type of the values stored at Timestamp.Now:
+/// let key = encoder::encode_storage_key::<()>(&metadata, "Timestamp", "Now", &[]).unwrap();
+/// let entry = decoder::decode_storage(&metadata).decode_key(&metadata, &mut &*key).unwrap();
+///
+/// let value = encoder::value_from_json(&metadata, entry.ty, &serde_json::json!("123")).unwrap();
+/// assert_eq!(value.without_context(), Value::u64(123));
+/// ```
+pub fn value_from_json<Id: Into<TypeId>>(
+	metadata: &Metadata,
+	ty: Id,
+	json: &serde_json::Value,
+) -> Result<Value<TypeId>, JsonValueError> {
+	value_from_json::value_from_json(json, ty, metadata.types())
+}
+
+/// Convert some JSON into the arguments for the call given, ready to hand to [`build_extrinsic`]. The arguments
+/// can be given as an object keyed by argument name, or as an array in the order that they are declared. Each
+/// argument is converted as described in [`value_from_json`].
+///
+/// # Example
+///
+/// ```rust
+/// use hex;
+/// use desub_current::{ Metadata, encoder };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// let json = serde_json::json!({
+///     "dest": { "Id": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY" },
+///     "value": 12345
+/// });
+/// let arguments = encoder::call_arguments_from_json(&metadata, "Balances", "transfer", &json).unwrap();
+/// let builder = encoder::build_extrinsic(&metadata, "Balances", "transfer", &arguments).unwrap();
+///
+/// assert_eq!(
+///     hex::encode(builder.call_data()),
+///     "050000d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27de5c0"
+/// );
+/// ```
+pub fn call_arguments_from_json(
+	metadata: &Metadata,
+	pallet: &str,
+	call: &str,
+	json: &serde_json::Value,
+) -> Result<Vec<Value<TypeId>>, JsonValueError> {
+	value_from_json::call_arguments_from_json(metadata, pallet, call, json)
+}
+
+/// Encode a storage key which can be used to look up the value of some storage entry. The key takes the form
+/// `twox_128(prefix) + twox_128(name) + hashed_keys`, where each of the map keys provided is SCALE encoded according
+/// to the metadata and then hashed using the relevant hasher for that storage entry.
+///
+/// Plain storage entries take no keys. Map entries may be given fewer keys than they expect, in which case a partial
+/// key is returned which is a prefix of the keys of every entry in the map that shares those first keys.
+///
+/// # Example
+///
+/// ```rust
+/// use hex;
+/// use desub_current::{ Metadata, Value, encoder };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// // System.BlockHash(1000)
+/// let key = encoder::encode_storage_key(&metadata, "System", "BlockHash", &[Value::u32(1000)]).unwrap();
+/// assert_eq!(
+///     hex::encode(key),
+///     "26aa394eea5630e07c48ae0c9558cef7a44704b568d21667356a5a050c118746b6ff6f7d467b87a9e8030000"
+/// );
+/// ```
+pub fn encode_storage_key<T>(
+	metadata: &Metadata,
+	prefix: &str,
+	name: &str,
+	keys: &[Value<T>],
+) -> Result<Vec<u8>, StorageEncodeError> {
+	encode_storage::encode_storage_key(metadata, prefix, name, keys)
+}
+
+/// Generate an [`ExtrinsicBuilder`] which can construct SCALE encoded extrinsics that make the call given. The pallet
+/// and call indexes are looked up from the metadata using the names provided, and an argument is expected for each
+/// of the fields in the call, in the order that they are declared.
+///
+/// # Example
+///
+/// ```rust
+/// use hex;
+/// use desub_current::{ Metadata, Value, encoder };
+///
+/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+///
+/// // Auctions.bid(1, 2, 3, 4, 5)
+/// let arguments = vec![Value::u32(1), Value::u32(2), Value::u32(3), Value::u32(4), Value::u128(5)];
+/// let builder = encoder::build_extrinsic(&metadata, "Auctions", "bid", &arguments).unwrap();
+///
+/// assert_eq!(hex::encode(builder.call_data()), "480104080c1014");
+/// assert_eq!(hex::encode(builder.unsigned()), "2004480104080c1014");
+/// ```
+pub fn build_extrinsic<'a, T>(
+	metadata: &'a Metadata,
+	pallet: &str,
+	call: &str,
+	arguments: &[Value<T>],
+) -> Result<ExtrinsicBuilder<'a>, ExtrinsicEncodeError> {
+	ExtrinsicBuilder::new(metadata, pallet, call, arguments)
+}
+
+/// Generate an [`ExtrinsicBuilder`] from some [`CallData`], such as that handed back from decoding an extrinsic.
+pub fn build_extrinsic_from_call_data<'a>(
+	metadata: &'a Metadata,
+	call_data: &CallData<'_>,
+) -> Result<ExtrinsicBuilder<'a>, ExtrinsicEncodeError> {
+	ExtrinsicBuilder::from_call_data(metadata, call_data)
+}
@@ -0,0 +1,747 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::metadata::Metadata;
+use crate::value::{BitSequence, Composite, Primitive, Value, ValueDef, Variant};
+use crate::{Type, TypeId};
+use desub_common::{PathSegment, ValuePath};
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, TypeDefPrimitive, TypeDefVariant};
+use serde_json::{Map, Value as Json};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+use sp_core::U256;
+use std::convert::TryFrom;
+
+/// An error converting some JSON into a [`Value`]. This contains the path to the offending
+/// JSON value, as well as details about what went wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonValueError {
+	path: ValuePath,
+	kind: JsonValueErrorKind,
+}
+
+impl JsonValueError {
+	/// The path to the offending JSON value, made up of field names, variant names and
+	/// sequence indexes. This is empty if the top level value could not be converted.
+	pub fn path(&self) -> &[String] {
+		self.path.segments()
+	}
+
+	/// Details about what went wrong.
+	pub fn kind(&self) -> &JsonValueErrorKind {
+		&self.kind
+	}
+
+	fn at(mut self, segment: impl Into<PathSegment>) -> Self {
+		self.path.prepend(segment);
+		self
+	}
+}
+
+impl From<JsonValueErrorKind> for JsonValueError {
+	fn from(kind: JsonValueErrorKind) -> Self {
+		JsonValueError { path: ValuePath::new(), kind }
+	}
+}
+
+impl std::fmt::Display for JsonValueError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.path.is_empty() {
+			write!(f, "{}", self.kind)
+		} else {
+			write!(f, "field `{}` {}", self.path, self.kind)
+		}
+	}
+}
+
+impl std::error::Error for JsonValueError {}
+
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum JsonValueErrorKind {
+	#[error("cannot find type with ID {0}")]
+	TypeIdNotFound(u32),
+	#[error("expected {expected}, got {got}")]
+	WrongShape { expected: &'static str, got: &'static str },
+	#[error("expected {expected} values, got {got}")]
+	WrongLength { expected: usize, got: usize },
+	#[error("could not find variant with name '{0}'")]
+	VariantNotFound(String),
+	#[error("could not find a value for the field '{0}'")]
+	FieldNotFound(String),
+	#[error("there is no field named '{0}'")]
+	UnexpectedField(String),
+	#[error("{value} is out of range for {expected}")]
+	NumberOutOfRange { value: String, expected: &'static str },
+	#[error("'{0}' is not a valid number")]
+	InvalidNumber(String),
+	#[error("'{0}' is not valid hex")]
+	InvalidHex(String),
+	#[error("'{0}' is not a valid SS58 address")]
+	InvalidSs58(String),
+	#[error("couldn't find a call named '{call}' in the pallet '{pallet}'")]
+	CallNotFound { pallet: String, call: String },
+}
+
+/// Convert the JSON provided into a [`Value`] shaped according to the [`TypeId`] provided.
+pub(super) fn value_from_json<Id: Into<TypeId>>(
+	json: &Json,
+	ty_id: Id,
+	types: &PortableRegistry,
+) -> Result<Value<TypeId>, JsonValueError> {
+	let ty_id = ty_id.into();
+	let ty = types.resolve(ty_id.id()).ok_or(JsonValueErrorKind::TypeIdNotFound(ty_id.id()))?;
+
+	let value = match ty.type_def() {
+		TypeDef::Composite(inner) => composite_from_json(json, ty, inner.fields(), types)?,
+		TypeDef::Variant(inner) => ValueDef::Variant(variant_from_json(json, ty, inner, types)?),
+		TypeDef::Sequence(inner) => sequence_from_json(json, inner.type_param().into(), None, types)?,
+		TypeDef::Array(inner) => {
+			sequence_from_json(json, inner.type_param().into(), Some(inner.len() as usize), types)?
+		}
+		TypeDef::Tuple(inner) => tuple_from_json(json, inner.fields(), types)?,
+		TypeDef::Primitive(inner) => ValueDef::Primitive(primitive_from_json(json, inner)?),
+		// Compact values look just like the values they wrap, but (as when decoding) we give them the compact type ID.
+		TypeDef::Compact(inner) => {
+			let value = value_from_json(json, inner.type_param(), types)?;
+			return Ok(Value { value: value.value, context: ty_id });
+		}
+		TypeDef::BitSequence(_) => ValueDef::BitSequence(bits_from_json(json)?),
+	};
+	Ok(Value { value, context: ty_id })
+}
+
+/// Convert the JSON provided into an argument for each of the fields in the call given.
+pub(super) fn call_arguments_from_json(
+	metadata: &Metadata,
+	pallet: &str,
+	call: &str,
+	json: &Json,
+) -> Result<Vec<Value<TypeId>>, JsonValueError> {
+	let (_, variant) = metadata
+		.call_variant_by_name(pallet, call)
+		.ok_or_else(|| JsonValueErrorKind::CallNotFound { pallet: pallet.to_owned(), call: call.to_owned() })?;
+
+	let arguments = match fields_from_json(json, variant.fields(), metadata.types())? {
+		Composite::Named(vals) => vals.into_iter().map(|(_, val)| val).collect(),
+		Composite::Unnamed(vals) => vals,
+	};
+	Ok(arguments)
+}
+
+/// Convert the JSON provided into values for each of the fields given. Named fields can be given as an object,
+/// where the keys are the field names (or their camelCase equivalents), and any fields can be given as an array
+/// of values in order. A single field can also be given on its own.
+fn fields_from_json(
+	json: &Json,
+	fields: &[Field<PortableForm>],
+	types: &PortableRegistry,
+) -> Result<Composite<TypeId>, JsonValueError> {
+	let named = fields.iter().all(|f| f.name().is_some());
+	let composite = |vals: Vec<Value<TypeId>>| {
+		if named {
+			Composite::Named(fields.iter().filter_map(|f| f.name().cloned()).zip(vals).collect())
+		} else {
+			Composite::Unnamed(vals)
+		}
+	};
+
+	if fields.is_empty() {
+		return match json {
+			Json::Null => Ok(composite(Vec::new())),
+			Json::Array(vals) if vals.is_empty() => Ok(composite(Vec::new())),
+			Json::Object(map) if map.is_empty() => Ok(composite(Vec::new())),
+			_ => Err(wrong_shape("no values", json)),
+		};
+	}
+
+	match json {
+		Json::Object(map) if named && (fields.len() > 1 || find_field(map, &fields[0]).is_some()) => {
+			named_fields_from_json(map, fields, types).map(Composite::Named)
+		}
+		Json::Array(vals) if fields.len() > 1 => {
+			if vals.len() != fields.len() {
+				return Err(JsonValueErrorKind::WrongLength { expected: fields.len(), got: vals.len() }.into());
+			}
+			let vals = vals
+				.iter()
+				.zip(fields)
+				.enumerate()
+				.map(|(idx, (json, field))| {
+					value_from_json(json, field.ty(), types).map_err(|e| match field.name() {
+						Some(name) => e.at(name),
+						None => e.at(idx),
+					})
+				})
+				.collect::<Result<_, _>>()?;
+			Ok(composite(vals))
+		}
+		_ if fields.len() == 1 => {
+			// A lone field is usually given on its own, but we also allow it to be wrapped in an array.
+			let field = &fields[0];
+			let at_field = |e: JsonValueError| match field.name() {
+				Some(name) => e.at(name),
+				None => e,
+			};
+			match (value_from_json(json, field.ty(), types), json) {
+				(Ok(val), _) => Ok(composite(vec![val])),
+				(Err(e), Json::Array(vals)) if vals.len() == 1 => value_from_json(&vals[0], field.ty(), types)
+					.map(|val| composite(vec![val]))
+					.map_err(|_| at_field(e)),
+				(Err(e), _) => Err(at_field(e)),
+			}
+		}
+		_ => Err(wrong_shape(if named { "object or array" } else { "array" }, json)),
+	}
+}
+
+fn named_fields_from_json(
+	map: &Map<String, Json>,
+	fields: &[Field<PortableForm>],
+	types: &PortableRegistry,
+) -> Result<Vec<(String, Value<TypeId>)>, JsonValueError> {
+	if let Some(key) = map.keys().find(|key| !fields.iter().any(|f| is_field(f, key))) {
+		return Err(JsonValueErrorKind::UnexpectedField(key.clone()).into());
+	}
+	fields
+		.iter()
+		.map(|field| {
+			let name = field.name().expect("fields are named; checked by caller");
+			let json = find_field(map, field).ok_or_else(|| JsonValueErrorKind::FieldNotFound(name.clone()))?;
+			let value = value_from_json(json, field.ty(), types).map_err(|e| e.at(name))?;
+			Ok((name.clone(), value))
+		})
+		.collect()
+}
+
+fn composite_from_json(
+	json: &Json,
+	ty: &Type,
+	fields: &[Field<PortableForm>],
+	types: &PortableRegistry,
+) -> Result<ValueDef<TypeId>, JsonValueError> {
+	// Account IDs can be given as SS58 addresses, as well as hex strings.
+	if let (Some("AccountId32"), Json::String(s)) = (ty.path().segments().last().map(|s| s.as_str()), json) {
+		if !s.starts_with("0x") {
+			let account = AccountId32::from_ss58check(s).map_err(|_| JsonValueErrorKind::InvalidSs58(s.clone()))?;
+			let hex = Json::String(format!("0x{}", hex::encode(account)));
+			return composite_from_json(&hex, ty, fields, types);
+		}
+	}
+	fields_from_json(json, fields, types).map(ValueDef::Composite)
+}
+
+fn variant_from_json(
+	json: &Json,
+	ty: &Type,
+	variants: &TypeDefVariant<PortableForm>,
+	types: &PortableRegistry,
+) -> Result<Variant<TypeId>, JsonValueError> {
+	// Options can be given as `null` or as the value itself, as well as in the usual variant shapes. A string
+	// is always the value itself, so that something like `"none"` can't be mistaken for `None`.
+	let is_option = ty.path().segments() == ["Option"];
+	let (name, values) = match json {
+		Json::Null if is_option => ("None", &Json::Null),
+		Json::String(_) if is_option => ("Some", json),
+		Json::String(name) => (name.as_str(), &Json::Null),
+		// Our own serialized form, `{ "name": "Foo", "values": .. }`:
+		Json::Object(map) if map.len() == 2 && matches!(map.get("name"), Some(Json::String(_))) => {
+			match (map.get("name"), map.get("values")) {
+				(Some(Json::String(name)), Some(values)) => (name.as_str(), values),
+				_ if is_option => ("Some", json),
+				_ => return Err(wrong_shape("a variant name or an object with one key", json)),
+			}
+		}
+		// The form that polkadot-js uses, `{ "Foo": .. }`:
+		Json::Object(map) if map.len() == 1 => {
+			let (name, values) = map.iter().next().expect("map has one entry");
+			(name.as_str(), values)
+		}
+		_ if is_option => ("Some", json),
+		_ => return Err(wrong_shape("a variant name or an object with one key", json)),
+	};
+
+	// polkadot-js uses camelCase variant names, so we fall back to a case insensitive match. Option variants
+	// must match exactly; anything else is the value in `Some`.
+	let variant = variants
+		.variants()
+		.iter()
+		.find(|v| v.name() == name)
+		.or_else(|| variants.variants().iter().find(|v| !is_option && v.name().eq_ignore_ascii_case(name)));
+	let (variant, values) = match variant {
+		Some(variant) => (variant, values),
+		None if is_option => (variants.variants().iter().find(|v| v.name() == "Some").expect("Option has Some"), json),
+		None => return Err(JsonValueErrorKind::VariantNotFound(name.to_owned()).into()),
+	};
+
+	let values = fields_from_json(values, variant.fields(), types).map_err(|e| e.at(variant.name()))?;
+	Ok(Variant { name: variant.name().clone(), values })
+}
+
+fn sequence_from_json(
+	json: &Json,
+	inner_id: TypeId,
+	len: Option<usize>,
+	types: &PortableRegistry,
+) -> Result<ValueDef<TypeId>, JsonValueError> {
+	let is_bytes =
+		matches!(types.resolve(inner_id.id()).map(|t| t.type_def()), Some(TypeDef::Primitive(TypeDefPrimitive::U8)));
+
+	let vals: Vec<_> = match json {
+		// Byte arrays can be given as hex strings, and byte sequences as hex strings or UTF-8 strings.
+		Json::String(s) if is_bytes => {
+			let bytes = match s.strip_prefix("0x") {
+				Some(hex) => hex::decode(hex).map_err(|_| JsonValueErrorKind::InvalidHex(s.clone()))?,
+				None if len.is_none() => s.as_bytes().to_vec(),
+				None => return Err(JsonValueErrorKind::InvalidHex(s.clone()).into()),
+			};
+			bytes
+				.into_iter()
+				.map(|b| Value { value: ValueDef::Primitive(Primitive::U8(b)), context: inner_id })
+				.collect()
+		}
+		Json::Array(vals) => vals
+			.iter()
+			.enumerate()
+			.map(|(idx, json)| value_from_json(json, inner_id, types).map_err(|e| e.at(idx)))
+			.collect::<Result<_, _>>()?,
+		_ if is_bytes => return Err(wrong_shape("an array or a hex string", json)),
+		_ => return Err(wrong_shape("an array", json)),
+	};
+
+	match len {
+		Some(len) if len != vals.len() => {
+			Err(JsonValueErrorKind::WrongLength { expected: len, got: vals.len() }.into())
+		}
+		_ => Ok(ValueDef::Composite(Composite::Unnamed(vals))),
+	}
+}
+
+fn tuple_from_json(
+	json: &Json,
+	fields: &[scale_info::interner::UntrackedSymbol<std::any::TypeId>],
+	types: &PortableRegistry,
+) -> Result<ValueDef<TypeId>, JsonValueError> {
+	let vals: &[Json] = match json {
+		Json::Null if fields.is_empty() => &[],
+		Json::Array(vals) => vals,
+		_ => return Err(wrong_shape("an array", json)),
+	};
+	if vals.len() != fields.len() {
+		return Err(JsonValueErrorKind::WrongLength { expected: fields.len(), got: vals.len() }.into());
+	}
+	let vals = vals
+		.iter()
+		.zip(fields)
+		.enumerate()
+		.map(|(idx, (json, field))| value_from_json(json, field, types).map_err(|e| e.at(idx)))
+		.collect::<Result<_, _>>()?;
+	Ok(ValueDef::Composite(Composite::Unnamed(vals)))
+}
+
+fn primitive_from_json(json: &Json, ty: &TypeDefPrimitive) -> Result<Primitive, JsonValueError> {
+	let primitive = match ty {
+		TypeDefPrimitive::Bool => Primitive::Bool(json.as_bool().ok_or_else(|| wrong_shape("a bool", json))?),
+		TypeDefPrimitive::Char => {
+			let s = json.as_str().ok_or_else(|| wrong_shape("a char", json))?;
+			let mut chars = s.chars();
+			match (chars.next(), chars.next()) {
+				(Some(c), None) => Primitive::Char(c),
+				_ => return Err(wrong_shape("a char", json)),
+			}
+		}
+		TypeDefPrimitive::Str => Primitive::Str(json.as_str().ok_or_else(|| wrong_shape("a string", json))?.to_owned()),
+		TypeDefPrimitive::U8 => Primitive::U8(unsigned(json, "u8")?),
+		TypeDefPrimitive::U16 => Primitive::U16(unsigned(json, "u16")?),
+		TypeDefPrimitive::U32 => Primitive::U32(unsigned(json, "u32")?),
+		TypeDefPrimitive::U64 => Primitive::U64(unsigned(json, "u64")?),
+		TypeDefPrimitive::U128 => Primitive::U128(unsigned(json, "u128")?),
+		TypeDefPrimitive::U256 => {
+			let (negative, n, s) = parse_number(json)?;
+			if negative && !n.is_zero() {
+				return Err(out_of_range(s, "u256"));
+			}
+			let mut bytes = [0; 32];
+			n.to_little_endian(&mut bytes);
+			Primitive::U256(bytes)
+		}
+		TypeDefPrimitive::I8 => Primitive::I8(signed(json, "i8")?),
+		TypeDefPrimitive::I16 => Primitive::I16(signed(json, "i16")?),
+		TypeDefPrimitive::I32 => Primitive::I32(signed(json, "i32")?),
+		TypeDefPrimitive::I64 => Primitive::I64(signed(json, "i64")?),
+		TypeDefPrimitive::I128 => Primitive::I128(signed(json, "i128")?),
+		TypeDefPrimitive::I256 => {
+			let (negative, n, s) = parse_number(json)?;
+			let min_magnitude = U256::one() << 255;
+			let n = match negative {
+				true if n <= min_magnitude => (!n).overflowing_add(U256::one()).0,
+				false if n < min_magnitude => n,
+				_ => return Err(out_of_range(s, "i256")),
+			};
+			let mut bytes = [0; 32];
+			n.to_little_endian(&mut bytes);
+			Primitive::I256(bytes)
+		}
+	};
+	Ok(primitive)
+}
+
+fn bits_from_json(json: &Json) -> Result<BitSequence, JsonValueError> {
+	let vals = json.as_array().ok_or_else(|| wrong_shape("an array of bits", json))?;
+	vals.iter()
+		.enumerate()
+		.map(|(idx, json)| match json {
+			Json::Bool(b) => Ok(*b),
+			Json::Number(n) if n.as_u64() == Some(0) => Ok(false),
+			Json::Number(n) if n.as_u64() == Some(1) => Ok(true),
+			_ => Err(wrong_shape("a bool, 0 or 1", json).at(idx)),
+		})
+		.collect()
+}
+
+fn unsigned<N: TryFrom<u128>>(json: &Json, expected: &'static str) -> Result<N, JsonValueError> {
+	let (negative, n, s) = parse_number(json)?;
+	if negative && !n.is_zero() || n > U256::from(u128::MAX) {
+		return Err(out_of_range(s, expected));
+	}
+	N::try_from(n.as_u128()).map_err(|_| out_of_range(s, expected))
+}
+
+fn signed<N: TryFrom<i128>>(json: &Json, expected: &'static str) -> Result<N, JsonValueError> {
+	let (negative, n, s) = parse_number(json)?;
+	if n > U256::from(i128::MAX as u128) + U256::from(negative as u8) {
+		return Err(out_of_range(s, expected));
+	}
+	let n = if negative { (n.as_u128() as i128).wrapping_neg() } else { n.as_u128() as i128 };
+	N::try_from(n).map_err(|_| out_of_range(s, expected))
+}
+
+/// Numbers can be given as JSON numbers, or as strings (to avoid losing precision), in which case they
+/// can also be `0x` prefixed hex. This returns whether the number is negative, its magnitude, and how
+/// it was written.
+fn parse_number(json: &Json) -> Result<(bool, U256, String), JsonValueError> {
+	let s = match json {
+		Json::Number(n) => n.to_string(),
+		Json::String(s) => s.clone(),
+		_ => return Err(wrong_shape("a number", json)),
+	};
+	let (negative, digits) = match s.strip_prefix('-') {
+		Some(digits) => (true, digits),
+		None => (false, s.as_str()),
+	};
+	let n = match digits.strip_prefix("0x") {
+		Some(hex) if !hex.is_empty() && hex.len() <= 64 => U256::from_str_radix(hex, 16).ok(),
+		Some(_) => None,
+		None if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) => {
+			// Anything that doesn't fit in a U256 is valid, but out of range.
+			match U256::from_dec_str(digits) {
+				Ok(n) => Some(n),
+				Err(_) => return Err(out_of_range(s, "u256")),
+			}
+		}
+		None => None,
+	};
+	match n {
+		Some(n) => Ok((negative, n, s)),
+		None => Err(JsonValueErrorKind::InvalidNumber(s).into()),
+	}
+}
+
+fn is_field(field: &Field<PortableForm>, key: &str) -> bool {
+	matches!(field.name(), Some(name) if name == key || camel_case(name) == key)
+}
+
+fn find_field<'a>(map: &'a Map<String, Json>, field: &Field<PortableForm>) -> Option<&'a Json> {
+	let name = field.name()?;
+	map.get(name).or_else(|| map.get(&camel_case(name)))
+}
+
+/// polkadot-js uses camelCase names for fields, so we accept those too.
+fn camel_case(name: &str) -> String {
+	let mut out = String::with_capacity(name.len());
+	let mut upper = false;
+	for c in name.chars() {
+		match c {
+			'_' if !out.is_empty() => upper = true,
+			c if upper => {
+				out.push(c.to_ascii_uppercase());
+				upper = false;
+			}
+			c => out.push(c),
+		}
+	}
+	out
+}
+
+fn out_of_range(value: String, expected: &'static str) -> JsonValueError {
+	JsonValueErrorKind::NumberOutOfRange { value, expected }.into()
+}
+
+fn wrong_shape(expected: &'static str, json: &Json) -> JsonValueError {
+	let got = match json {
+		Json::Null => "null",
+		Json::Bool(_) => "a bool",
+		Json::Number(_) => "a number",
+		Json::String(_) => "a string",
+		Json::Array(_) => "an array",
+		Json::Object(_) => "an object",
+	};
+	JsonValueErrorKind::WrongShape { expected, got }.into()
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+	use crate::encoder::encode_value::encode_value_by_id;
+	use codec::{Compact, Encode};
+	use serde_json::json;
+
+	/// Convert the JSON given into a value using the type information from `T`.
+	fn from_json<T: scale_info::TypeInfo + 'static>(json: Json) -> Result<Value<TypeId>, JsonValueError> {
+		let m = scale_info::MetaType::new::<T>();
+		let mut types = scale_info::Registry::new();
+		let id = types.register_type(&m);
+		let portable_registry: PortableRegistry = types.into();
+
+		value_from_json(&json, id, &portable_registry)
+	}
+
+	/// Check that the JSON given is converted into a value which encodes to the same bytes as the expected value.
+	fn json_check<T: Encode + scale_info::TypeInfo + 'static>(json: Json, expected: T) {
+		let m = scale_info::MetaType::new::<T>();
+		let mut types = scale_info::Registry::new();
+		let id = types.register_type(&m);
+		let portable_registry: PortableRegistry = types.into();
+
+		let value = value_from_json(&json, id, &portable_registry).expect("conversion failed");
+		let mut encoded = Vec::new();
+		encode_value_by_id(&value, id, &portable_registry, &mut encoded).expect("encoding failed");
+		assert_eq!(encoded, expected.encode(), "encoded value does not match what we expected");
+	}
+
+	fn kind_of<T: scale_info::TypeInfo + 'static>(json: Json) -> JsonValueErrorKind {
+		from_json::<T>(json).unwrap_err().kind().clone()
+	}
+
+	#[test]
+	fn primitives_from_json() {
+		json_check(json!(true), true);
+		json_check(json!("hello"), "hello".to_string());
+		assert_eq!(primitive_from_json(&json!("a"), &TypeDefPrimitive::Char).unwrap(), Primitive::Char('a'));
+		assert!(primitive_from_json(&json!("ab"), &TypeDefPrimitive::Char).is_err());
+		json_check(json!(123), 123u8);
+		json_check(json!(123), 123u16);
+		json_check(json!("123"), 123u32);
+		json_check(json!("0x7b"), 123u64);
+		json_check(json!("340282366920938463463374607431768211455"), u128::MAX);
+		json_check(json!(-123), -123i8);
+		json_check(json!("-170141183460469231731687303715884105728"), i128::MIN);
+		json_check(json!("170141183460469231731687303715884105727"), i128::MAX);
+	}
+
+	#[test]
+	fn big_numbers_from_json() {
+		let mut one = [0u8; 32];
+		one[0] = 1;
+		let mut max = [0u8; 32];
+		max[31] = 0x7f;
+		max[..31].fill(0xff);
+		let i256_max = U256::from_little_endian(&max).to_string();
+		assert_eq!(primitive_from_json(&json!(i256_max), &TypeDefPrimitive::I256).unwrap(), Primitive::I256(max));
+		assert_eq!(primitive_from_json(&json!("-1"), &TypeDefPrimitive::I256).unwrap(), Primitive::I256([0xff; 32]));
+		assert_eq!(primitive_from_json(&json!("0x01"), &TypeDefPrimitive::U256).unwrap(), Primitive::U256(one));
+
+		let err = primitive_from_json(&json!("-1"), &TypeDefPrimitive::U256).unwrap_err();
+		assert_eq!(err.kind(), &JsonValueErrorKind::NumberOutOfRange { value: "-1".into(), expected: "u256" });
+	}
+
+	#[test]
+	fn numbers_are_range_checked() {
+		assert_eq!(
+			kind_of::<u8>(json!(256)),
+			JsonValueErrorKind::NumberOutOfRange { value: "256".into(), expected: "u8" }
+		);
+		assert_eq!(
+			kind_of::<u32>(json!(-1)),
+			JsonValueErrorKind::NumberOutOfRange { value: "-1".into(), expected: "u32" }
+		);
+		assert_eq!(
+			kind_of::<i8>(json!("-129")),
+			JsonValueErrorKind::NumberOutOfRange { value: "-129".into(), expected: "i8" }
+		);
+		assert_eq!(kind_of::<u64>(json!(1.5)), JsonValueErrorKind::InvalidNumber("1.5".into()));
+		assert_eq!(kind_of::<u64>(json!("12a")), JsonValueErrorKind::InvalidNumber("12a".into()));
+		assert_eq!(kind_of::<u64>(json!(true)), JsonValueErrorKind::WrongShape { expected: "a number", got: "a bool" });
+	}
+
+	#[test]
+	fn compact_from_json() {
+		json_check(json!(123), Compact(123u64));
+		json_check(json!("1000000000000"), Compact(1_000_000_000_000u128));
+	}
+
+	#[test]
+	fn bytes_from_json() {
+		json_check(json!("0x010203"), vec![1u8, 2, 3]);
+		json_check(json!("hello"), b"hello".to_vec());
+		json_check(json!([1, 2, 3]), vec![1u8, 2, 3]);
+		json_check(json!("0x0102"), [1u8, 2]);
+
+		assert_eq!(kind_of::<[u8; 2]>(json!("0x010203")), JsonValueErrorKind::WrongLength { expected: 2, got: 3 });
+		assert_eq!(kind_of::<[u8; 2]>(json!("hi")), JsonValueErrorKind::InvalidHex("hi".into()));
+		assert_eq!(kind_of::<Vec<u8>>(json!("0xzz")), JsonValueErrorKind::InvalidHex("0xzz".into()));
+	}
+
+	#[test]
+	fn sequences_and_tuples_from_json() {
+		json_check(json!([1, 2, 3]), vec![1u32, 2, 3]);
+		json_check(json!([1, "two", true]), (1u16, "two".to_string(), true));
+		json_check(json!(null), ());
+
+		let err = from_json::<Vec<u32>>(json!([1, "x"])).unwrap_err();
+		assert_eq!(err.path(), &["[1]".to_string()]);
+		assert_eq!(kind_of::<(u8, u8)>(json!([1])), JsonValueErrorKind::WrongLength { expected: 2, got: 1 });
+	}
+
+	#[test]
+	fn composites_from_json() {
+		#[derive(Encode, scale_info::TypeInfo)]
+		struct Unnamed(bool, u8);
+
+		#[derive(Encode, scale_info::TypeInfo)]
+		struct Named {
+			is_valid: bool,
+			new_free: u128,
+		}
+
+		#[derive(Encode, scale_info::TypeInfo)]
+		struct Wrapper(Vec<u32>);
+
+		json_check(json!([true, 1]), Unnamed(true, 1));
+		json_check(json!({ "new_free": 1, "is_valid": true }), Named { is_valid: true, new_free: 1 });
+		json_check(json!({ "isValid": true, "newFree": "2" }), Named { is_valid: true, new_free: 2 });
+		json_check(json!([true, 3]), Named { is_valid: true, new_free: 3 });
+
+		// Newtype wrappers can be given the inner value, or the inner value wrapped in an array:
+		json_check(json!([1]), Wrapper(vec![1]));
+		json_check(json!([[1]]), Wrapper(vec![1]));
+
+		assert_eq!(kind_of::<Named>(json!({ "is_valid": true })), JsonValueErrorKind::FieldNotFound("new_free".into()));
+		assert_eq!(
+			kind_of::<Named>(json!({ "is_valid": true, "new_free": 1, "other": 2 })),
+			JsonValueErrorKind::UnexpectedField("other".into())
+		);
+	}
+
+	#[test]
+	fn variants_from_json() {
+		#[derive(Encode, scale_info::TypeInfo)]
+		enum MyEnum {
+			Foo,
+			Bar(u8, bool),
+			Wibble { hello: u32 },
+			SingleValue(u64),
+		}
+
+		json_check(json!("Foo"), MyEnum::Foo);
+		json_check(json!({ "Foo": null }), MyEnum::Foo);
+		json_check(json!({ "Bar": [1, true] }), MyEnum::Bar(1, true));
+		json_check(json!({ "Wibble": { "hello": 2 } }), MyEnum::Wibble { hello: 2 });
+		json_check(json!({ "singleValue": 3 }), MyEnum::SingleValue(3));
+		json_check(json!({ "name": "SingleValue", "values": [4] }), MyEnum::SingleValue(4));
+
+		assert_eq!(kind_of::<MyEnum>(json!("Nope")), JsonValueErrorKind::VariantNotFound("Nope".into()));
+		assert_eq!(
+			kind_of::<MyEnum>(json!(1)),
+			JsonValueErrorKind::WrongShape { expected: "a variant name or an object with one key", got: "a number" }
+		);
+	}
+
+	#[test]
+	fn options_from_json() {
+		json_check(json!(null), None::<u8>);
+		json_check(json!(1), Some(1u8));
+		json_check(json!({ "Some": 1 }), Some(1u8));
+		json_check(json!({ "None": null }), None::<u8>);
+
+		// Strings are always the value in `Some`, and never the name of a variant:
+		json_check(json!("none"), Some("none".to_string()));
+		json_check(json!("Some"), Some("Some".to_string()));
+		json_check(json!("None"), Some("None".to_string()));
+		json_check(json!("none"), Some(b"none".to_vec()));
+		assert_eq!(kind_of::<Option<u8>>(json!("None")), JsonValueErrorKind::InvalidNumber("None".into()));
+
+		#[derive(Encode, scale_info::TypeInfo)]
+		struct Foo {
+			a: u8,
+		}
+
+		// Values that don't look like an Option variant are assumed to be the value in `Some`:
+		json_check(json!({ "a": 1 }), Some(Foo { a: 1 }));
+	}
+
+	#[test]
+	fn account_ids_from_json() {
+		let alice = sp_keyring::AccountKeyring::Alice.to_account_id();
+		json_check(json!(alice.to_ss58check()), alice.clone());
+		json_check(json!(format!("0x{}", hex::encode(&alice))), alice);
+
+		assert_eq!(kind_of::<AccountId32>(json!("nope")), JsonValueErrorKind::InvalidSs58("nope".into()));
+	}
+
+	#[test]
+	fn bit_sequences_from_json() {
+		use bitvec::{bitvec, order::Lsb0};
+
+		json_check(json!([false, true, 1, 0]), bitvec![u8, Lsb0; 0, 1, 1, 0]);
+		assert_eq!(from_json::<BitSequence>(json!([true, 2])).unwrap_err().path(), &["[1]".to_string()]);
+	}
+
+	#[test]
+	fn errors_contain_the_path_to_the_value() {
+		#[derive(Encode, scale_info::TypeInfo)]
+		enum MultiAddress {
+			Id([u8; 4]),
+			Index(u32),
+		}
+
+		#[derive(Encode, scale_info::TypeInfo)]
+		struct Transfer {
+			dest: MultiAddress,
+			value: u128,
+		}
+
+		json_check(
+			json!([
+				{ "dest": { "Index": 1 }, "value": 1 },
+				{ "dest": { "Id": "0x01020304" }, "value": 2 }
+			]),
+			vec![
+				Transfer { dest: MultiAddress::Index(1), value: 1 },
+				Transfer { dest: MultiAddress::Id([1, 2, 3, 4]), value: 2 },
+			],
+		);
+
+		let err = from_json::<Vec<Transfer>>(json!([
+			{ "dest": { "Index": 1 }, "value": 1 },
+			{ "dest": { "Id": "0x0102" }, "value": 1 }
+		]))
+		.unwrap_err();
+
+		assert_eq!(err.path(), &["[1]".to_string(), "dest".into(), "Id".into()]);
+		assert_eq!(err.kind(), &JsonValueErrorKind::WrongLength { expected: 4, got: 2 });
+		assert_eq!(err.to_string(), "field `[1].dest.Id` expected 4 values, got 2");
+	}
+}
@@ -15,11 +15,12 @@
 // along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
 
 //! A crate to decode extrinsics, signer payloads and storage keys for substrate nodes using V14+ metadata.
-//! See [`decoder`] for more information.
+//! See [`decoder`] for more information, and [`encoder`] for going the other way.
 
 mod type_id;
 
 pub mod decoder;
+pub mod encoder;
 pub mod metadata;
 pub mod value;
 
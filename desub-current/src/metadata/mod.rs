@@ -17,23 +17,33 @@
 //! Decode SCALE encoded metadata from a substrate node into a format that
 //! we can make use of for decoding (see [`crate::decoder`]).
 
+mod pallet;
 mod readonly_array;
 mod u8_map;
 mod version_14;
+mod version_15;
 
-use crate::{ScaleInfoTypeId, Type, TypeId};
+use crate::decoder::{self, DecodeValueError};
+use crate::{ScaleInfoTypeId, Type, TypeId, Value};
 use codec::Decode;
 use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
 use readonly_array::ReadonlyArray;
-use scale_info::{form::PortableForm, PortableRegistry};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDefPrimitive};
+use std::collections::{BTreeMap, HashMap};
 use u8_map::U8Map;
 
+pub use pallet::{CallMetadata, ErrorMetadata, EventMetadata, FieldMetadata, Pallet, StorageMetadata, VariantMetadata};
+
 // Some type aliases used below. `scale-info` is re-exported at the root,
 // so to avoid confusion we only publicly export all scale-info types from that
 // one place.
 type TypeDefVariant = scale_info::TypeDefVariant<PortableForm>;
-type SignedExtensionMetadata = frame_metadata::SignedExtensionMetadata<PortableForm>;
+type SignedExtensionMetadata = frame_metadata::v14::SignedExtensionMetadata<PortableForm>;
 type StorageEntryMetadata = frame_metadata::v14::StorageEntryMetadata<scale_info::form::PortableForm>;
+type PalletConstantMetadata = frame_metadata::v14::PalletConstantMetadata<PortableForm>;
+type RuntimeApiMetadata = frame_metadata::v15::RuntimeApiMetadata<PortableForm>;
+type RuntimeApiMethodMetadata = frame_metadata::v15::RuntimeApiMethodMetadata<PortableForm>;
+type CustomValueMetadata = frame_metadata::v15::CustomValueMetadata<PortableForm>;
 
 /// An enum of the possible errors that can be returned from attempting to construct
 /// a [`Metadata`] struct.
@@ -56,14 +66,28 @@ pub enum MetadataError {
 pub struct Metadata {
 	/// Details about the extrinsic format.
 	extrinsic: MetadataExtrinsic,
-	/// Hash pallet calls by index, since when decoding, we'll have the pallet/call
-	/// `u8`'s available to us to look them up by.
-	pallet_calls_by_index: U8Map<MetadataPalletCalls>,
+	/// The position in `pallets` of each pallet, by pallet index, since when decoding
+	/// calls, events and errors, we'll have the pallet `u8` available to us to look them up by.
+	pallets_by_index: U8Map<usize>,
+	/// When encoding, we'll have pallet names available to us, and need
+	/// to find the corresponding pallet indexes.
+	pallet_indexes_by_name: HashMap<String, u8>,
 	/// Store storage entry information as a readonly array, allowing us to look up a
 	/// specific storage entry using a key like `(usize,usize)`. Since the order of
 	/// entries in this array is not guaranteed between metadata versions, it should
 	/// not be exposed.
 	pallet_storage: ReadonlyArray<MetadataPalletStorage>,
+	/// General details about each pallet, in the order that they appear in the
+	/// metadata. This is what we expose for introspection.
+	pallets: ReadonlyArray<MetadataPallet>,
+	/// Runtime API definitions. These are only available from V15 metadata.
+	runtime_apis: Vec<RuntimeApiMetadata>,
+	/// The outer call, event and error enum types. These are only available
+	/// from V15 metadata.
+	outer_enums: Option<OuterEnums>,
+	/// Custom, chain specific values, by name. These are only available from
+	/// V15 metadata.
+	custom: BTreeMap<String, CustomValueMetadata>,
 	/// Type information lives inside this.
 	types: PortableRegistry,
 }
@@ -95,6 +119,10 @@ impl Metadata {
 				log::trace!("V14 metadata found.");
 				version_14::decode(meta_v14)
 			}
+			RuntimeMetadata::V15(meta_v15) => {
+				log::trace!("V15 metadata found.");
+				version_15::decode(meta_v15)
+			}
 			unsupported_meta => Err(MetadataError::UnsupportedVersion(unsupported_meta.version())),
 		}
 	}
@@ -104,13 +132,38 @@ impl Metadata {
 		&self.extrinsic
 	}
 
+	/// Return the runtime API definitions in this metadata. This is empty prior to V15 metadata.
+	pub fn runtime_apis(&self) -> &[RuntimeApiMetadata] {
+		&self.runtime_apis
+	}
+
+	/// Return details about the runtime API method given, if it exists.
+	pub fn runtime_api_method(&self, api: &str, method: &str) -> Option<&RuntimeApiMethodMetadata> {
+		self.runtime_apis.iter().find(|a| a.name == api)?.methods.iter().find(|m| m.name == method)
+	}
+
+	/// Return the types of the outer call, event and error enums. These are only available from V15 metadata.
+	pub fn outer_enums(&self) -> Option<OuterEnums> {
+		self.outer_enums
+	}
+
+	/// Return each of the custom values in the metadata. This is empty prior to V15 metadata.
+	pub fn custom_values(&self) -> impl Iterator<Item = CustomValue<'_>> {
+		self.custom.iter().map(move |(name, custom)| CustomValue { name, custom, metadata: self })
+	}
+
+	/// Return the custom value with the name given, if it exists.
+	pub fn custom_value(&self, name: &str) -> Option<CustomValue<'_>> {
+		self.custom.get_key_value(name).map(|(name, custom)| CustomValue { name, custom, metadata: self })
+	}
+
 	/// Given a [`crate::TypeId`], return the corresponding type from the type registry, if possible.
 	pub fn resolve<Id: Into<TypeId>>(&self, id: Id) -> Option<&Type> {
 		self.types.resolve(id.into().id())
 	}
 
 	/// Return a reference to the [`scale_info`] type registry.
-	pub(crate) fn types(&self) -> &PortableRegistry {
+	pub fn types(&self) -> &PortableRegistry {
 		&self.types
 	}
 
@@ -128,27 +181,105 @@ impl Metadata {
 		StorageEntry { prefix: &pallet.prefix, metadata: entry }
 	}
 
+	/// Retrieve the storage entry with the prefix and name given, if one exists.
+	pub(crate) fn storage_entry_by_name(&self, prefix: &str, name: &str) -> Option<StorageEntry<'_>> {
+		let pallet = self.pallet_storage.iter().find(|p| p.prefix == prefix)?;
+		let entry = pallet.storage_entries.iter().find(|e| e.name == name)?;
+
+		Some(StorageEntry { prefix: &pallet.prefix, metadata: entry })
+	}
+
 	/// In order to generate a lookup table to decode storage entries, we need to be able to
 	/// iterate over them.
 	pub(crate) fn storage_entries(&self) -> impl Iterator<Item = &MetadataPalletStorage> {
 		self.pallet_storage.iter()
 	}
 
-	/// Given the `u8` variant index of a pallet and call, this returns the pallet name and the call Variant
-	/// if found, or `None` if no such call exists at those indexes, or we don't have suitable call data.
-	pub(crate) fn call_variant_by_enum_index(
+	/// Return details about each of the pallets in the metadata, in the order that they are given.
+	pub fn pallets(&self) -> impl Iterator<Item = Pallet<'_>> {
+		self.pallets.iter().map(move |pallet| Pallet { pallet, metadata: self })
+	}
+
+	/// Return details about the pallet with the name given, or `None` if no such pallet exists.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use desub_current::Metadata;
+	///
+	/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+	/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+	///
+	/// let transfer = metadata.pallet("Balances").unwrap().call("transfer").unwrap();
+	/// let arg_names: Vec<_> = transfer.fields().map(|f| f.name().unwrap()).collect();
+	/// assert_eq!(arg_names, vec!["dest", "value"]);
+	/// ```
+	pub fn pallet(&self, name: &str) -> Option<Pallet<'_>> {
+		self.pallets().find(|p| p.name() == name)
+	}
+
+	/// Return details about the pallet with the index given, or `None` if no such pallet exists.
+	pub fn pallet_by_index(&self, index: u8) -> Option<Pallet<'_>> {
+		let pallet = self.pallets.get(*self.pallets_by_index.get(index)?)?;
+		Some(Pallet { pallet, metadata: self })
+	}
+
+	/// Return details about each of the constants in the pallet given, or `None` if no such pallet exists.
+	pub fn pallet_constants(&self, pallet: &str) -> Option<impl Iterator<Item = PalletConstant<'_>>> {
+		Some(self.pallet(pallet)?.constants())
+	}
+
+	/// Return details about the constant with the pallet and name given, or `None` if it does not exist.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use desub_current::{ Metadata, Value };
+	///
+	/// let metadata_scale_encoded = include_bytes!("../../tests/data/v14_metadata_polkadot.scale");
+	/// let metadata = Metadata::from_bytes(metadata_scale_encoded).unwrap();
+	///
+	/// let ss58_prefix = metadata.constant("System", "SS58Prefix").unwrap();
+	/// assert_eq!(ss58_prefix.value().unwrap().without_context(), Value::u16(0));
+	/// ```
+	pub fn constant(&self, pallet: &str, name: &str) -> Option<PalletConstant<'_>> {
+		self.pallet_constants(pallet)?.find(|c| c.name() == name)
+	}
+
+	/// Given the `u8` index of a pallet and of one of its calls, events or errors, this returns the pallet name
+	/// and the Variant if found, or `None` if no such variant exists at those indexes, or we don't have suitable
+	/// data for the pallet.
+	pub(crate) fn variant_by_enum_index(
 		&self,
+		kind: PalletEnum,
 		pallet: u8,
-		call: u8,
+		index: u8,
 	) -> Option<(&str, &scale_info::Variant<PortableForm>)> {
-		self.pallet_calls_by_index.get(pallet).and_then(|p| {
-			p.calls.as_ref().and_then(|calls| {
-				let type_def_variant = self.get_variant(calls.calls_type_id)?;
-				let index = *calls.call_variant_indexes.get(call)?;
-				let variant = type_def_variant.variants().get(index)?;
-				Some((&*p.name, variant))
-			})
-		})
+		let pallet = self.pallets.get(*self.pallets_by_index.get(pallet)?)?;
+		let variants = pallet.variants(kind)?;
+		let position = *variants.variant_indexes.get(index)?;
+		let variant = self.get_variant(variants.type_id)?.variants().get(position)?;
+		Some((&*pallet.name, variant))
+	}
+
+	/// The enum type describing the calls, events or errors of the pallet with the index given.
+	pub(crate) fn pallet_enum_type(&self, kind: PalletEnum, pallet: u8) -> Option<&Type> {
+		let pallet = self.pallets.get(*self.pallets_by_index.get(pallet)?)?;
+		self.resolve(pallet.variants(kind)?.type_id)
+	}
+
+	/// Given the name of a pallet and call, this returns the `u8` index of the pallet and the call Variant
+	/// (which contains the call index) if found, or `None` if no such call exists.
+	pub(crate) fn call_variant_by_name(
+		&self,
+		pallet: &str,
+		call: &str,
+	) -> Option<(u8, &scale_info::Variant<PortableForm>)> {
+		let pallet_index = *self.pallet_indexes_by_name.get(pallet)?;
+		let pallet = self.pallets.get(*self.pallets_by_index.get(pallet_index)?)?;
+		let calls = pallet.variants(PalletEnum::Calls)?;
+		let variant = self.get_variant(calls.type_id)?.variants().iter().find(|v| v.name() == call)?;
+		Some((pallet_index, variant))
 	}
 
 	/// A helper function to get hold of a Variant given a type ID, or None if it's not found.
@@ -180,23 +311,148 @@ impl MetadataPalletStorage {
 }
 
 #[derive(Debug)]
-struct MetadataPalletCalls {
+struct MetadataPallet {
 	/// The pallet name.
 	name: String,
-	/// Metadata may not contain call information. If it does,
-	/// it'll be here.
-	calls: Option<MetadataCalls>,
+	/// The pallet index.
+	index: u8,
+	/// The index into `pallet_storage` where storage information for
+	/// this pallet lives, if the pallet has any storage.
+	storage: Option<usize>,
+	/// Details for each constant in the pallet.
+	constants: Vec<PalletConstantMetadata>,
+	/// Metadata may not contain call information. If it does, it'll be here.
+	calls: Option<MetadataVariants>,
+	/// Metadata may not contain event information. If it does, it'll be here.
+	events: Option<MetadataVariants>,
+	/// Metadata may not contain error information. If it does, it'll be here.
+	errors: Option<MetadataVariants>,
+}
+
+impl MetadataPallet {
+	fn variants(&self, kind: PalletEnum) -> Option<&MetadataVariants> {
+		match kind {
+			PalletEnum::Calls => self.calls.as_ref(),
+			PalletEnum::Events => self.events.as_ref(),
+			PalletEnum::Errors => self.errors.as_ref(),
+		}
+	}
+}
+
+/// The calls, events and errors of a pallet are each described by the variants of some enum type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PalletEnum {
+	Calls,
+	Events,
+	Errors,
 }
 
 #[derive(Debug)]
-struct MetadataCalls {
+struct MetadataVariants {
 	/// This allows us to find the type information corresponding to
-	/// the call in the [`PortableRegistry`]/
-	calls_type_id: ScaleInfoTypeId,
-	/// This allows us to map a u8 enum index to the correct call variant
-	/// from the calls type, above. The variant contains information on the
-	/// fields and such that the call has.
-	call_variant_indexes: U8Map<usize>,
+	/// the enum in the [`PortableRegistry`].
+	type_id: ScaleInfoTypeId,
+	/// This allows us to map a u8 enum index to the correct variant
+	/// from the enum type, above. The variant contains information on the
+	/// fields and such that the call, event or error has.
+	variant_indexes: U8Map<usize>,
+}
+
+/// Details about a single pallet constant, obtained from [`Metadata::constant`] or
+/// [`Metadata::pallet_constants`].
+#[derive(Debug, Clone, Copy)]
+pub struct PalletConstant<'a> {
+	pallet: &'a str,
+	constant: &'a PalletConstantMetadata,
+	metadata: &'a Metadata,
+}
+
+impl<'a> PalletConstant<'a> {
+	/// The name of the pallet that this constant belongs to.
+	pub fn pallet(&self) -> &'a str {
+		self.pallet
+	}
+
+	/// The name of the constant.
+	pub fn name(&self) -> &'a str {
+		&self.constant.name
+	}
+
+	/// The type of the constant. Use [`Metadata::resolve`] to obtain more information about it.
+	pub fn ty(&self) -> TypeId {
+		TypeId::from(&self.constant.ty)
+	}
+
+	/// Documentation for the constant.
+	pub fn docs(&self) -> &'a [String] {
+		&self.constant.docs
+	}
+
+	/// The SCALE encoded value of the constant.
+	pub fn bytes(&self) -> &'a [u8] {
+		&self.constant.value
+	}
+
+	/// Decode the value of the constant into a [`Value`].
+	pub fn value(&self) -> Result<Value<TypeId>, DecodeValueError> {
+		decoder::decode_value_by_id(self.metadata, self.ty(), &mut self.bytes())
+	}
+}
+
+/// The types of the outer enums which wrap every pallet's calls, events and errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OuterEnums {
+	call_enum_ty: TypeId,
+	event_enum_ty: TypeId,
+	error_enum_ty: TypeId,
+}
+
+impl OuterEnums {
+	/// The type of the outer call enum, in which each variant is a pallet's calls.
+	pub fn call_enum_type(&self) -> TypeId {
+		self.call_enum_ty
+	}
+
+	/// The type of the outer event enum, in which each variant is a pallet's events.
+	pub fn event_enum_type(&self) -> TypeId {
+		self.event_enum_ty
+	}
+
+	/// The type of the outer error enum, in which each variant is a pallet's errors.
+	pub fn error_enum_type(&self) -> TypeId {
+		self.error_enum_ty
+	}
+}
+
+/// A custom value from the metadata, obtained from [`Metadata::custom_value`] or
+/// [`Metadata::custom_values`].
+#[derive(Debug, Clone, Copy)]
+pub struct CustomValue<'a> {
+	name: &'a str,
+	custom: &'a CustomValueMetadata,
+	metadata: &'a Metadata,
+}
+
+impl<'a> CustomValue<'a> {
+	/// The name of the custom value.
+	pub fn name(&self) -> &'a str {
+		self.name
+	}
+
+	/// The type of the custom value. Use [`Metadata::resolve`] to obtain more information about it.
+	pub fn ty(&self) -> TypeId {
+		TypeId::from(&self.custom.ty)
+	}
+
+	/// The SCALE encoded custom value.
+	pub fn bytes(&self) -> &'a [u8] {
+		&self.custom.value
+	}
+
+	/// Decode the custom value into a [`Value`].
+	pub fn value(&self) -> Result<Value<TypeId>, DecodeValueError> {
+		decoder::decode_value_by_id(self.metadata, self.ty(), &mut self.bytes())
+	}
 }
 
 /// Information about the extrinsic format supported on the substrate node
@@ -205,6 +461,10 @@ struct MetadataCalls {
 pub struct MetadataExtrinsic {
 	version: u8,
 	signed_extensions: Vec<SignedExtensionMetadata>,
+	/// The type of the address in signed extrinsics, if the metadata tells us.
+	address_ty: Option<ScaleInfoTypeId>,
+	/// The type of the signature in signed extrinsics, if the metadata tells us.
+	signature_ty: Option<ScaleInfoTypeId>,
 }
 
 impl MetadataExtrinsic {
@@ -216,9 +476,21 @@ impl MetadataExtrinsic {
 
 	/// Part of the extrinsic signature area can be varied to include whatever information
 	/// a node decides is important. This returns details about that part.
-	pub(crate) fn signed_extensions(&self) -> &[SignedExtensionMetadata] {
+	pub fn signed_extensions(&self) -> &[SignedExtensionMetadata] {
 		&self.signed_extensions
 	}
+
+	/// The type of the address found in signed extrinsics. If this isn't known, a
+	/// `MultiAddress<AccountId32, u32>` is assumed.
+	pub(crate) fn address_type(&self) -> Option<TypeId> {
+		self.address_ty.as_ref().map(TypeId::from)
+	}
+
+	/// The type of the signature found in signed extrinsics. If this isn't known, a
+	/// `MultiSignature` is assumed.
+	pub(crate) fn signature_type(&self) -> Option<TypeId> {
+		self.signature_ty.as_ref().map(TypeId::from)
+	}
 }
 
 /// An opaque struct that can be used to obtain details for a specific
@@ -234,3 +506,24 @@ pub(crate) struct StorageEntry<'a> {
 	pub prefix: &'a str,
 	pub metadata: &'a StorageEntryMetadata,
 }
+
+/// The Rust name of a primitive type, for example `u32`.
+pub fn primitive_name(ty: &TypeDefPrimitive) -> &'static str {
+	match ty {
+		TypeDefPrimitive::Bool => "bool",
+		TypeDefPrimitive::Char => "char",
+		TypeDefPrimitive::Str => "str",
+		TypeDefPrimitive::U8 => "u8",
+		TypeDefPrimitive::U16 => "u16",
+		TypeDefPrimitive::U32 => "u32",
+		TypeDefPrimitive::U64 => "u64",
+		TypeDefPrimitive::U128 => "u128",
+		TypeDefPrimitive::U256 => "u256",
+		TypeDefPrimitive::I8 => "i8",
+		TypeDefPrimitive::I16 => "i16",
+		TypeDefPrimitive::I32 => "i32",
+		TypeDefPrimitive::I64 => "i64",
+		TypeDefPrimitive::I128 => "i128",
+		TypeDefPrimitive::I256 => "i256",
+	}
+}
@@ -0,0 +1,241 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A read-only view of the pallets in some [`Metadata`], and the calls, events, errors,
+//! storage entries and constants that each pallet exposes.
+
+use super::{Metadata, MetadataPallet, PalletConstant, PalletEnum, StorageEntryMetadata};
+use crate::decoder::storage_map_key_to_type_id_vec;
+use crate::TypeId;
+use frame_metadata::v14::{StorageEntryModifier, StorageEntryType, StorageHasher};
+use scale_info::form::PortableForm;
+
+/// Details about a single pallet, obtained from [`Metadata::pallet`], [`Metadata::pallet_by_index`]
+/// or [`Metadata::pallets`].
+#[derive(Debug, Clone, Copy)]
+pub struct Pallet<'a> {
+	pub(super) pallet: &'a MetadataPallet,
+	pub(super) metadata: &'a Metadata,
+}
+
+impl<'a> Pallet<'a> {
+	/// The name of the pallet.
+	pub fn name(&self) -> &'a str {
+		&self.pallet.name
+	}
+
+	/// The index of the pallet, which is the first byte of any call or event from it.
+	pub fn index(&self) -> u8 {
+		self.pallet.index
+	}
+
+	/// Details about each of the calls in this pallet.
+	pub fn calls(&self) -> impl Iterator<Item = CallMetadata<'a>> {
+		self.variants(PalletEnum::Calls)
+	}
+
+	/// Details about the call with the name given, if it exists.
+	pub fn call(&self, name: &str) -> Option<CallMetadata<'a>> {
+		self.calls().find(|c| c.name() == name)
+	}
+
+	/// Details about each of the events in this pallet.
+	pub fn events(&self) -> impl Iterator<Item = EventMetadata<'a>> {
+		self.variants(PalletEnum::Events)
+	}
+
+	/// Details about the event with the name given, if it exists.
+	pub fn event(&self, name: &str) -> Option<EventMetadata<'a>> {
+		self.events().find(|e| e.name() == name)
+	}
+
+	/// Details about each of the errors in this pallet.
+	pub fn errors(&self) -> impl Iterator<Item = ErrorMetadata<'a>> {
+		self.variants(PalletEnum::Errors)
+	}
+
+	/// Details about the error with the name given, if it exists.
+	pub fn error(&self, name: &str) -> Option<ErrorMetadata<'a>> {
+		self.errors().find(|e| e.name() == name)
+	}
+
+	/// Details about each of the storage entries in this pallet.
+	pub fn storage_entries(&self) -> impl Iterator<Item = StorageMetadata<'a>> {
+		let metadata = self.metadata;
+		let storage = self.pallet.storage.and_then(|idx| metadata.pallet_storage.get(idx));
+		storage.into_iter().flat_map(move |storage| {
+			storage.storage_entries.iter().map(move |entry| StorageMetadata {
+				prefix: &storage.prefix,
+				entry,
+				metadata,
+			})
+		})
+	}
+
+	/// Details about the storage entry with the name given, if it exists.
+	pub fn storage_entry(&self, name: &str) -> Option<StorageMetadata<'a>> {
+		self.storage_entries().find(|e| e.name() == name)
+	}
+
+	/// Details about each of the constants in this pallet.
+	pub fn constants(&self) -> impl Iterator<Item = PalletConstant<'a>> {
+		let (pallet, metadata) = (self.pallet, self.metadata);
+		pallet.constants.iter().map(move |constant| PalletConstant { pallet: &pallet.name, constant, metadata })
+	}
+
+	/// Details about the constant with the name given, if it exists.
+	pub fn constant(&self, name: &str) -> Option<PalletConstant<'a>> {
+		self.constants().find(|c| c.name() == name)
+	}
+
+	/// Calls, events and errors are each described by the variants of some enum type.
+	fn variants(&self, kind: PalletEnum) -> impl Iterator<Item = VariantMetadata<'a>> {
+		let ty = self.pallet.variants(kind).map(|v| v.type_id);
+		let variants = ty.and_then(|ty| self.metadata.get_variant(ty)).map(|v| v.variants()).unwrap_or_default();
+		variants.iter().map(|variant| VariantMetadata { variant })
+	}
+}
+
+/// Details about a pallet call.
+pub type CallMetadata<'a> = VariantMetadata<'a>;
+
+/// Details about a pallet event.
+pub type EventMetadata<'a> = VariantMetadata<'a>;
+
+/// Details about a pallet error.
+pub type ErrorMetadata<'a> = VariantMetadata<'a>;
+
+/// Details about a single call, event or error in a pallet.
+#[derive(Debug, Clone, Copy)]
+pub struct VariantMetadata<'a> {
+	variant: &'a scale_info::Variant<PortableForm>,
+}
+
+impl<'a> VariantMetadata<'a> {
+	/// The name of the call, event or error.
+	pub fn name(&self) -> &'a str {
+		self.variant.name()
+	}
+
+	/// The index of the call, event or error within its pallet.
+	pub fn index(&self) -> u8 {
+		self.variant.index()
+	}
+
+	/// Details about each of the fields (for calls, the arguments).
+	pub fn fields(&self) -> impl Iterator<Item = FieldMetadata<'a>> {
+		self.variant.fields().iter().map(|field| FieldMetadata { field })
+	}
+
+	/// Documentation for the call, event or error.
+	pub fn docs(&self) -> &'a [String] {
+		self.variant.docs()
+	}
+
+	/// The underlying [`scale_info`] type information.
+	pub fn ty(&self) -> &'a scale_info::Variant<PortableForm> {
+		self.variant
+	}
+}
+
+/// Details about a single field of a call, event or error.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldMetadata<'a> {
+	field: &'a scale_info::Field<PortableForm>,
+}
+
+impl<'a> FieldMetadata<'a> {
+	/// The name of the field, if it has one.
+	pub fn name(&self) -> Option<&'a str> {
+		self.field.name().map(|n| &**n)
+	}
+
+	/// The type of the field. Use [`Metadata::resolve`] to obtain more information about it.
+	pub fn ty(&self) -> TypeId {
+		self.field.ty().into()
+	}
+
+	/// The name of the type as it was written in the source code, if known.
+	pub fn type_name(&self) -> Option<&'a str> {
+		self.field.type_name().map(|n| &**n)
+	}
+
+	/// Documentation for the field.
+	pub fn docs(&self) -> &'a [String] {
+		self.field.docs()
+	}
+}
+
+/// Details about a single storage entry in a pallet.
+#[derive(Debug, Clone, Copy)]
+pub struct StorageMetadata<'a> {
+	prefix: &'a str,
+	entry: &'a StorageEntryMetadata,
+	metadata: &'a Metadata,
+}
+
+impl<'a> StorageMetadata<'a> {
+	/// The storage prefix (normally identical to the pallet name).
+	pub fn prefix(&self) -> &'a str {
+		self.prefix
+	}
+
+	/// The name of the storage entry.
+	pub fn name(&self) -> &'a str {
+		&self.entry.name
+	}
+
+	/// Whether the storage entry has a default value, or returns `None` if no value is stored.
+	pub fn modifier(&self) -> &'a StorageEntryModifier {
+		&self.entry.modifier
+	}
+
+	/// The hashers used for each of the map keys. This is empty for plain storage entries.
+	pub fn hashers(&self) -> &'a [StorageHasher] {
+		match &self.entry.ty {
+			StorageEntryType::Plain(_) => &[],
+			StorageEntryType::Map { hashers, .. } => hashers,
+		}
+	}
+
+	/// The types of each of the map keys. This is empty for plain storage entries.
+	pub fn key_types(&self) -> Vec<TypeId> {
+		match &self.entry.ty {
+			StorageEntryType::Plain(_) => Vec::new(),
+			StorageEntryType::Map { key, hashers, .. } => {
+				storage_map_key_to_type_id_vec(self.metadata, key, hashers.len())
+			}
+		}
+	}
+
+	/// The type of the values stored in this storage entry.
+	pub fn value_type(&self) -> TypeId {
+		match &self.entry.ty {
+			StorageEntryType::Plain(value) => value.into(),
+			StorageEntryType::Map { value, .. } => value.into(),
+		}
+	}
+
+	/// The SCALE encoded default value for this storage entry.
+	pub fn default_bytes(&self) -> &'a [u8] {
+		&self.entry.default
+	}
+
+	/// Documentation for the storage entry.
+	pub fn docs(&self) -> &'a [String] {
+		&self.entry.docs
+	}
+}
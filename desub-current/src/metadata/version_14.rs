@@ -15,48 +15,62 @@
 // along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::u8_map::U8Map;
-use super::{Metadata, MetadataCalls, MetadataError, MetadataExtrinsic, MetadataPalletCalls, MetadataPalletStorage};
-use frame_metadata::RuntimeMetadataV14;
+use super::{Metadata, MetadataError, MetadataExtrinsic, MetadataPallet, MetadataPalletStorage, MetadataVariants};
+use crate::ScaleInfoTypeId;
+use frame_metadata::v14::{PalletMetadata, RuntimeMetadataV14};
+use scale_info::{form::PortableForm, PortableRegistry, Type};
+use std::collections::{BTreeMap, HashMap};
 
 /// Decode V14 metadata into our general Metadata struct
 pub fn decode(meta: RuntimeMetadataV14) -> Result<Metadata, MetadataError> {
-	let registry = meta.types;
-	let mut pallet_calls_by_index = U8Map::new();
+	// Gather some details about the extrinsic itself:
+	let (address_ty, signature_ty) = extrinsic_address_and_signature(&meta.types, &meta.extrinsic.ty);
+	let extrinsic = MetadataExtrinsic {
+		version: meta.extrinsic.version,
+		signed_extensions: meta.extrinsic.signed_extensions,
+		address_ty,
+		signature_ty,
+	};
+
+	decode_pallets(meta.types, meta.pallets, extrinsic)
+}
+
+/// Build our Metadata struct from the pallet information and extrinsic details given. Pallets look the same
+/// in V14 and V15 metadata, and so this is shared with [`super::version_15`].
+pub(super) fn decode_pallets(
+	registry: PortableRegistry,
+	pallets: Vec<PalletMetadata<PortableForm>>,
+	extrinsic: MetadataExtrinsic,
+) -> Result<Metadata, MetadataError> {
+	let mut pallets_by_index = U8Map::new();
+	let mut pallet_indexes_by_name = HashMap::new();
 	let mut pallet_storage = Vec::new();
+	let mut pallet_details = Vec::new();
 
-	// Gather some details about the extrinsic itself:
-	let extrinsic =
-		MetadataExtrinsic { version: meta.extrinsic.version, signed_extensions: meta.extrinsic.signed_extensions };
-
-	// Gather information about the calls/storage in use:
-	for pallet in meta.pallets {
-		// capture the call information in this pallet:
-		let calls = pallet
-			.calls
-			.map(|call_md| {
-				// Get the type representing the variant of available calls:
-				let calls_type_id = call_md.ty;
-				let calls_type = registry
-					.resolve(calls_type_id.id())
-					.ok_or_else(|| MetadataError::TypeNotFound(calls_type_id.id()))?;
-
-				// Expect that type to be a variant:
-				let calls_type_def = calls_type.type_def();
-				let calls_variant = match calls_type_def {
-					scale_info::TypeDef::Variant(variant) => variant,
-					_ => {
-						return Err(MetadataError::ExpectedVariantType { got: format!("{:?}", calls_type_def) });
-					}
-				};
-
-				// Store the mapping from u8 index to variant slice index for quicker decode lookup:
-				let call_variant_indexes =
-					calls_variant.variants().iter().enumerate().map(|(idx, v)| (v.index(), idx)).collect();
-
-				Ok(MetadataCalls { calls_type_id, call_variant_indexes })
+	// Gather information about the calls/events/storage in use:
+	for pallet in pallets {
+		pallet_indexes_by_name.insert(pallet.name.clone(), pallet.index);
+
+		// capture the calls, events and errors in this pallet, which are each the variants of some enum:
+		let variants = |ty: Option<ScaleInfoTypeId>| {
+			ty.map(|type_id| {
+				variant_indexes(&registry, &type_id)
+					.map(|variant_indexes| MetadataVariants { type_id, variant_indexes })
 			})
-			.transpose()?;
-		pallet_calls_by_index.insert(pallet.index, MetadataPalletCalls { name: pallet.name, calls });
+			.transpose()
+		};
+
+		// capture general details and the constants in this pallet:
+		pallets_by_index.insert(pallet.index, pallet_details.len());
+		pallet_details.push(MetadataPallet {
+			name: pallet.name,
+			index: pallet.index,
+			storage: pallet.storage.as_ref().map(|_| pallet_storage.len()),
+			constants: pallet.constants,
+			calls: variants(pallet.calls.map(|calls| calls.ty))?,
+			events: variants(pallet.event.map(|event| event.ty))?,
+			errors: variants(pallet.error.map(|error| error.ty))?,
+		});
 
 		// Capture the storage information in this pallet:
 		if let Some(storage_metadata) = pallet.storage {
@@ -67,5 +81,62 @@ pub fn decode(meta: RuntimeMetadataV14) -> Result<Metadata, MetadataError> {
 		}
 	}
 
-	Ok(Metadata { pallet_calls_by_index, pallet_storage: pallet_storage.into(), extrinsic, types: registry })
+	Ok(Metadata {
+		pallets_by_index,
+		pallet_indexes_by_name,
+		pallet_storage: pallet_storage.into(),
+		pallets: pallet_details.into(),
+		extrinsic,
+		runtime_apis: Vec::new(),
+		outer_enums: None,
+		custom: BTreeMap::new(),
+		types: registry,
+	})
+}
+
+/// The extrinsic type should be an `UncheckedExtrinsic`, whose generic parameters tell us the address and
+/// signature types used in signed extrinsics. Some older V14 metadata points to some other type instead,
+/// in which case we look for an `UncheckedExtrinsic` in the registry. If we can't find the types, we return
+/// `None` for them and leave the decoder to assume the usual Substrate defaults.
+fn extrinsic_address_and_signature(
+	registry: &PortableRegistry,
+	extrinsic_ty: &ScaleInfoTypeId,
+) -> (Option<ScaleInfoTypeId>, Option<ScaleInfoTypeId>) {
+	let has_address_param = |ty: &&Type<PortableForm>| ty.type_params().iter().any(|p| p.name() == "Address");
+
+	let unchecked_extrinsic = registry.resolve(extrinsic_ty.id()).filter(has_address_param).or_else(|| {
+		registry
+			.types()
+			.iter()
+			.map(|ty| ty.ty())
+			.filter(|ty| ty.path().segments().last().map(|s| &**s) == Some("UncheckedExtrinsic"))
+			.find(has_address_param)
+	});
+
+	let type_param = |name: &str| {
+		unchecked_extrinsic
+			.and_then(|ty| ty.type_params().iter().find(|p| p.name() == name))
+			.and_then(|p| p.ty())
+			.cloned()
+	};
+
+	(type_param("Address"), type_param("Signature"))
+}
+
+/// Resolve the type ID given (which is expected to point to a variant, such as the calls or events
+/// of some pallet), and store the mapping from u8 variant index to variant slice index for quicker
+/// decode lookup.
+fn variant_indexes(registry: &PortableRegistry, type_id: &ScaleInfoTypeId) -> Result<U8Map<usize>, MetadataError> {
+	let ty = registry.resolve(type_id.id()).ok_or_else(|| MetadataError::TypeNotFound(type_id.id()))?;
+
+	// Expect that type to be a variant:
+	let type_def = ty.type_def();
+	let variant = match type_def {
+		scale_info::TypeDef::Variant(variant) => variant,
+		_ => {
+			return Err(MetadataError::ExpectedVariantType { got: format!("{:?}", type_def) });
+		}
+	};
+
+	Ok(variant.variants().iter().enumerate().map(|(idx, v)| (v.index(), idx)).collect())
 }
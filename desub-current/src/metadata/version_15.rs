@@ -0,0 +1,66 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{version_14, Metadata, MetadataError, MetadataExtrinsic, OuterEnums};
+use frame_metadata::{v14, v15::RuntimeMetadataV15};
+
+/// Decode V15 metadata into our general Metadata struct
+pub fn decode(meta: RuntimeMetadataV15) -> Result<Metadata, MetadataError> {
+	// V15 metadata tells us the address and signature types directly:
+	let extrinsic = MetadataExtrinsic {
+		version: meta.extrinsic.version,
+		signed_extensions: meta
+			.extrinsic
+			.signed_extensions
+			.into_iter()
+			.map(|ext| v14::SignedExtensionMetadata {
+				identifier: ext.identifier,
+				ty: ext.ty,
+				additional_signed: ext.additional_signed,
+			})
+			.collect(),
+		address_ty: Some(meta.extrinsic.address_ty),
+		signature_ty: Some(meta.extrinsic.signature_ty),
+	};
+
+	// Pallets are the same as in V14, aside from the docs which we don't make use of:
+	let pallets = meta
+		.pallets
+		.into_iter()
+		.map(|pallet| v14::PalletMetadata {
+			name: pallet.name,
+			storage: pallet.storage,
+			calls: pallet.calls,
+			event: pallet.event,
+			constants: pallet.constants,
+			error: pallet.error,
+			index: pallet.index,
+		})
+		.collect();
+
+	let mut metadata = version_14::decode_pallets(meta.types, pallets, extrinsic)?;
+
+	// Keep hold of the things that are new in V15:
+	metadata.runtime_apis = meta.apis;
+	metadata.outer_enums = Some(OuterEnums {
+		call_enum_ty: meta.outer_enums.call_enum_ty.into(),
+		event_enum_ty: meta.outer_enums.event_enum_ty.into(),
+		error_enum_ty: meta.outer_enums.error_enum_ty.into(),
+	});
+	metadata.custom = meta.custom.map;
+
+	Ok(metadata)
+}
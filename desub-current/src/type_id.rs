@@ -32,6 +32,12 @@ impl TypeId {
 	}
 }
 
+impl From<u32> for TypeId {
+	fn from(id: u32) -> Self {
+		TypeId(id)
+	}
+}
+
 impl From<ScaleInfoTypeId> for TypeId {
 	fn from(id: ScaleInfoTypeId) -> Self {
 		TypeId(id.id())
@@ -0,0 +1,159 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+// The textual representation of values is described in the module level docs.
+
+use super::{BitSequence, Composite, Primitive, Value, ValueDef, Variant};
+use sp_core::U256;
+use std::fmt::{self, Display, Write};
+
+impl<T> Display for Value<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.value.fmt(f)
+	}
+}
+
+impl<T> Display for ValueDef<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ValueDef::Composite(val) => val.fmt(f),
+			ValueDef::Variant(val) => val.fmt(f),
+			ValueDef::BitSequence(val) => fmt_bits(val, f),
+			ValueDef::Primitive(val) => val.fmt(f),
+		}
+	}
+}
+
+impl<T> Display for Composite<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			// An empty composite is written as `()` rather than as no bytes.
+			Composite::Unnamed(vals) => match as_bytes(vals) {
+				Some(bytes) if !bytes.is_empty() => write!(f, "0x{}", hex::encode(bytes)),
+				_ => fmt_unnamed(vals, f),
+			},
+			Composite::Named(vals) if vals.is_empty() => f.write_str("{}"),
+			Composite::Named(vals) => {
+				f.write_str("{ ")?;
+				for (idx, (name, val)) in vals.iter().enumerate() {
+					if idx > 0 {
+						f.write_str(", ")?;
+					}
+					if is_ident(name) {
+						write!(f, "{}: {}", name, val)?;
+					} else {
+						write!(f, "{:?}: {}", name, val)?;
+					}
+				}
+				f.write_str(" }")
+			}
+		}
+	}
+}
+
+impl<T> Display for Variant<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// Names that aren't identifiers (such as `true`) are quoted, and then always followed by their values
+		// so that they aren't mistaken for strings.
+		if is_ident(&self.name) {
+			f.write_str(&self.name)?;
+		} else {
+			write!(f, "{:?}", self.name)?;
+		}
+		match &self.values {
+			Composite::Unnamed(vals) if vals.is_empty() && is_ident(&self.name) => Ok(()),
+			// The values of a variant are always written in brackets, even if they are all `u8`s, since
+			// `Foo(0x0102)` would be a variant containing a single composite value.
+			Composite::Unnamed(vals) => fmt_unnamed(vals, f),
+			Composite::Named(_) => write!(f, " {}", self.values),
+		}
+	}
+}
+
+impl Display for Primitive {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Primitive::Bool(v) => v.fmt(f),
+			Primitive::Char(v) => write!(f, "{:?}", v),
+			Primitive::Str(v) => write!(f, "{:?}", v),
+			Primitive::U8(v) => v.fmt(f),
+			Primitive::U16(v) => v.fmt(f),
+			Primitive::U32(v) => v.fmt(f),
+			Primitive::U64(v) => v.fmt(f),
+			Primitive::U128(v) => v.fmt(f),
+			Primitive::U256(v) => f.write_str(&u256_to_string(v)),
+			Primitive::I8(v) => v.fmt(f),
+			Primitive::I16(v) => v.fmt(f),
+			Primitive::I32(v) => v.fmt(f),
+			Primitive::I64(v) => v.fmt(f),
+			Primitive::I128(v) => v.fmt(f),
+			Primitive::I256(v) => f.write_str(&i256_to_string(v)),
+		}
+	}
+}
+
+/// Render little endian U256 bytes as a decimal string.
+pub(super) fn u256_to_string(bytes: &[u8; 32]) -> String {
+	U256::from_little_endian(bytes).to_string()
+}
+
+/// Render little endian, two's complement I256 bytes as a decimal string.
+pub(super) fn i256_to_string(bytes: &[u8; 32]) -> String {
+	let n = U256::from_little_endian(bytes);
+	if n.bit(255) {
+		format!("-{}", (!n).overflowing_add(U256::one()).0)
+	} else {
+		n.to_string()
+	}
+}
+
+fn fmt_unnamed<T>(vals: &[Value<T>], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	f.write_char('(')?;
+	for (idx, val) in vals.iter().enumerate() {
+		if idx > 0 {
+			f.write_str(", ")?;
+		}
+		val.fmt(f)?;
+	}
+	f.write_char(')')
+}
+
+fn fmt_bits(bits: &BitSequence, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	f.write_char('<')?;
+	for bit in bits.iter() {
+		f.write_char(if *bit { '1' } else { '0' })?;
+	}
+	f.write_char('>')
+}
+
+/// Can the name be written as is, rather than as a quoted string?
+pub(super) fn is_ident(name: &str) -> bool {
+	let mut chars = name.chars();
+	matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+		&& chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+		&& name != "true"
+		&& name != "false"
+}
+
+/// Return the bytes in some values, if they are all `u8`s.
+pub(super) fn as_bytes<T>(vals: &[Value<T>]) -> Option<Vec<u8>> {
+	vals.iter()
+		.map(|v| match v.value {
+			ValueDef::Primitive(Primitive::U8(b)) => Some(b),
+			_ => None,
+		})
+		.collect()
+}
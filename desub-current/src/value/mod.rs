@@ -18,11 +18,36 @@
 This module exposes the [`Value`] type and related subtypes, which are used as the runtime
 representations of SCALE encoded data (much like `serde_json::Value` is a runtime representation
 of JSON data).
+
+Values can also be written using a compact textual representation (see their [`std::fmt::Display`] impl), which
+can be parsed back into a [`Value`] using [`std::str::FromStr`]. It looks like this:
+
+```text
+Transfer { dest: Id(0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d), value: 1000 }
+```
+
+- Named composites are written `{ a: 1, b: true }`, and unnamed composites `(1, true)`.
+- Variants are a name followed by a named or unnamed composite, or just a name if they have no values. Names
+  that aren't identifiers (such as `true`) are quoted, and always followed by their values, for example `"true"()`.
+- Numbers are written in decimal, and may contain `_` separators and be negative when parsed.
+- Strings and chars are written like Rust strings and chars, for example `"hello\n"` and `'a'`.
+- Unnamed composites of `u8`s (other than the values of a variant) are written as `0x` prefixed hex, for
+  example `0x0102ff`.
+- Bit sequences are written as their bits between angle brackets, for example `<0110>`.
+
+Rather than matching on the shape of a value to pull something out of it, values can be looked up by path using
+[`Value::at()`] and [`Value::find_all()`], and then converted with accessors like [`Value::as_u128()`] which look
+through any newtype-like wrappers around the value.
 */
 
 mod deserialize;
 mod deserializer;
+mod display;
+mod parse;
+mod profile;
+mod query;
 mod serialize;
+mod type_info;
 
 use bitvec::{order::Lsb0, vec::BitVec};
 use serde::Deserialize;
@@ -289,6 +314,11 @@ impl<T> From<Primitive> for ValueDef<T> {
 /// A sequence of bits.
 pub type BitSequence = BitVec<u8, Lsb0>;
 
+pub use parse::{ParseError, ParseErrorKind};
+pub use profile::{SerializeProfile, WithProfile};
+pub use serialize::WithContext;
+pub use type_info::{TypeInfo, TypeParam};
+
 /// An opaque error that is returned if we cannot deserialize the [`Value`] type.
 pub use deserializer::Error as DeserializeError;
 
@@ -0,0 +1,465 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+// Parsing of the textual representation of values, described in the module level docs.
+
+use super::{display::is_ident, BitSequence, Composite, Primitive, Value, ValueDef, Variant};
+use sp_core::U256;
+use std::str::FromStr;
+
+/// An error parsing a [`Value`] from a string.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{kind} (at byte {offset})")]
+pub struct ParseError {
+	offset: usize,
+	kind: ParseErrorKind,
+}
+
+impl ParseError {
+	/// The byte offset into the string at which the error occurred.
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+
+	/// Details about what went wrong.
+	pub fn kind(&self) -> &ParseErrorKind {
+		&self.kind
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ParseErrorKind {
+	#[error("unexpected end of input")]
+	UnexpectedEof,
+	#[error("unexpected character {0:?}")]
+	UnexpectedChar(char),
+	#[error("expected {0}")]
+	Expected(&'static str),
+	#[error("number {0} is out of range")]
+	NumberOutOfRange(String),
+	#[error("invalid hex: {0}")]
+	InvalidHex(#[from] hex::FromHexError),
+	#[error("invalid escape sequence")]
+	InvalidEscape,
+	#[error("a char literal must contain exactly one char")]
+	InvalidChar,
+}
+
+impl FromStr for Value<()> {
+	type Err = ParseError;
+
+	/// Parse a [`Value`]. Numbers are parsed into [`Primitive::U128`] (or [`Primitive::I128`] if negative), or
+	/// [`Primitive::U256`] (or [`Primitive::I256`]) if they are too large for those.
+	///
+	/// ```
+	/// use desub_current::Value;
+	///
+	/// let value: Value<()> = "Transfer { dest: Id(0x0102), value: 1_000 }".parse().unwrap();
+	/// assert_eq!(value.to_string(), "Transfer { dest: Id(0x0102), value: 1000 }");
+	/// ```
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parser = Parser { input: s, pos: 0 };
+		let value = parser.value()?;
+		parser.skip_whitespace();
+		match parser.peek() {
+			None => Ok(value),
+			Some(c) => Err(parser.error(ParseErrorKind::UnexpectedChar(c))),
+		}
+	}
+}
+
+struct Parser<'a> {
+	input: &'a str,
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn value(&mut self) -> Result<Value<()>, ParseError> {
+		self.skip_whitespace();
+		let c = self.peek().ok_or_else(|| self.error(ParseErrorKind::UnexpectedEof))?;
+		let value = match c {
+			'{' => ValueDef::Composite(self.named_composite()?),
+			'(' => ValueDef::Composite(self.unnamed_composite()?),
+			'"' => {
+				let s = self.string()?;
+				// A quoted name followed by values is a variant whose name isn't an identifier, like `"true"()`.
+				self.skip_whitespace();
+				match self.peek() {
+					Some('(' | '{') => ValueDef::Variant(self.variant(s)?),
+					_ => ValueDef::Primitive(Primitive::Str(s)),
+				}
+			}
+			'\'' => ValueDef::Primitive(Primitive::Char(self.char()?)),
+			'<' => ValueDef::BitSequence(self.bits()?),
+			'0' if self.rest().starts_with("0x") => ValueDef::Composite(self.hex()?),
+			'-' | '0'..='9' => ValueDef::Primitive(self.number()?),
+			c if c.is_ascii_alphabetic() || c == '_' => {
+				let name = self.ident();
+				match name {
+					"true" => ValueDef::Primitive(Primitive::Bool(true)),
+					"false" => ValueDef::Primitive(Primitive::Bool(false)),
+					name => ValueDef::Variant(self.variant(name.to_string())?),
+				}
+			}
+			c => return Err(self.error(ParseErrorKind::UnexpectedChar(c))),
+		};
+		Ok(Value { value, context: () })
+	}
+
+	fn variant(&mut self, name: String) -> Result<Variant<()>, ParseError> {
+		self.skip_whitespace();
+		let values = match self.peek() {
+			Some('{') => self.named_composite()?,
+			Some('(') => self.unnamed_composite()?,
+			_ => Composite::Unnamed(Vec::new()),
+		};
+		Ok(Variant { name, values })
+	}
+
+	fn named_composite(&mut self) -> Result<Composite<()>, ParseError> {
+		let mut vals = Vec::new();
+		self.expect('{', "'{'")?;
+		self.comma_separated('}', |p| {
+			let name = match p.peek() {
+				Some('"') => p.string()?,
+				Some(c) if c.is_ascii_alphabetic() || c == '_' => p.ident().to_string(),
+				Some(c) => return Err(p.error(ParseErrorKind::UnexpectedChar(c))),
+				None => return Err(p.error(ParseErrorKind::UnexpectedEof)),
+			};
+			p.skip_whitespace();
+			p.expect(':', "':'")?;
+			vals.push((name, p.value()?));
+			Ok(())
+		})?;
+		Ok(Composite::Named(vals))
+	}
+
+	fn unnamed_composite(&mut self) -> Result<Composite<()>, ParseError> {
+		let mut vals = Vec::new();
+		self.expect('(', "'('")?;
+		self.comma_separated(')', |p| {
+			vals.push(p.value()?);
+			Ok(())
+		})?;
+		Ok(Composite::Unnamed(vals))
+	}
+
+	// Parse items separated by commas (allowing a trailing comma) until the closing char is found.
+	fn comma_separated(
+		&mut self,
+		close: char,
+		mut item: impl FnMut(&mut Self) -> Result<(), ParseError>,
+	) -> Result<(), ParseError> {
+		loop {
+			self.skip_whitespace();
+			if self.peek() == Some(close) {
+				self.next();
+				return Ok(());
+			}
+			item(self)?;
+			self.skip_whitespace();
+			match self.next() {
+				Some(',') => {}
+				Some(c) if c == close => return Ok(()),
+				Some(_) => {
+					return Err(self.error_before(ParseErrorKind::Expected(if close == '}' {
+						"',' or '}'"
+					} else {
+						"',' or ')'"
+					})))
+				}
+				None => return Err(self.error(ParseErrorKind::UnexpectedEof)),
+			}
+		}
+	}
+
+	fn string(&mut self) -> Result<String, ParseError> {
+		self.expect('"', "'\"'")?;
+		let mut out = String::new();
+		loop {
+			match self.next() {
+				Some('"') => return Ok(out),
+				Some('\\') => out.push(self.escape()?),
+				Some(c) => out.push(c),
+				None => return Err(self.error(ParseErrorKind::UnexpectedEof)),
+			}
+		}
+	}
+
+	fn char(&mut self) -> Result<char, ParseError> {
+		self.expect('\'', "'")?;
+		let c = match self.next() {
+			Some('\\') => self.escape()?,
+			Some('\'') => return Err(self.error_before(ParseErrorKind::InvalidChar)),
+			Some(c) => c,
+			None => return Err(self.error(ParseErrorKind::UnexpectedEof)),
+		};
+		match self.next() {
+			Some('\'') => Ok(c),
+			Some(_) => Err(self.error_before(ParseErrorKind::InvalidChar)),
+			None => Err(self.error(ParseErrorKind::UnexpectedEof)),
+		}
+	}
+
+	// Parse the rest of an escape sequence, once the `\` has been consumed.
+	fn escape(&mut self) -> Result<char, ParseError> {
+		let start = self.pos - 1;
+		let invalid = ParseError { offset: start, kind: ParseErrorKind::InvalidEscape };
+		let c = match self.next().ok_or_else(|| self.error(ParseErrorKind::UnexpectedEof))? {
+			'n' => '\n',
+			'r' => '\r',
+			't' => '\t',
+			'0' => '\0',
+			'\\' => '\\',
+			'\'' => '\'',
+			'"' => '"',
+			'u' => {
+				let hex = self.rest().strip_prefix('{').and_then(|s| s.split_once('}')).map(|(hex, _)| hex);
+				let c = hex.and_then(|hex| u32::from_str_radix(hex, 16).ok()).and_then(char::from_u32);
+				match (hex, c) {
+					(Some(hex), Some(c)) => {
+						self.pos += hex.len() + 2;
+						c
+					}
+					_ => return Err(invalid),
+				}
+			}
+			_ => return Err(invalid),
+		};
+		Ok(c)
+	}
+
+	fn bits(&mut self) -> Result<BitSequence, ParseError> {
+		self.expect('<', "'<'")?;
+		let mut bits = BitSequence::new();
+		loop {
+			match self.next() {
+				Some('0') => bits.push(false),
+				Some('1') => bits.push(true),
+				Some('>') => return Ok(bits),
+				Some(_) => return Err(self.error_before(ParseErrorKind::Expected("a bit (0 or 1) or '>'"))),
+				None => return Err(self.error(ParseErrorKind::UnexpectedEof)),
+			}
+		}
+	}
+
+	fn hex(&mut self) -> Result<Composite<()>, ParseError> {
+		let start = self.pos;
+		self.pos += 2;
+		let digits = self.take_while(|c| c.is_ascii_hexdigit() || c == '_').replace('_', "");
+		let bytes = hex::decode(digits).map_err(|e| ParseError { offset: start, kind: e.into() })?;
+		Ok(Composite::Unnamed(bytes.into_iter().map(Value::u8).collect()))
+	}
+
+	fn number(&mut self) -> Result<Primitive, ParseError> {
+		let start = self.pos;
+		let negative = self.peek() == Some('-');
+		if negative {
+			self.next();
+		}
+		if !matches!(self.peek(), Some('0'..='9')) {
+			return Err(self.error(ParseErrorKind::Expected("a digit")));
+		}
+		let digits = self.take_while(|c| c.is_ascii_digit() || c == '_').replace('_', "");
+		let out_of_range = || ParseError {
+			offset: start,
+			kind: ParseErrorKind::NumberOutOfRange(format!("{}{}", if negative { "-" } else { "" }, digits)),
+		};
+
+		if !negative {
+			if let Ok(n) = digits.parse::<u128>() {
+				return Ok(Primitive::U128(n));
+			}
+			let n = U256::from_dec_str(&digits).map_err(|_| out_of_range())?;
+			let mut bytes = [0; 32];
+			n.to_little_endian(&mut bytes);
+			return Ok(Primitive::U256(bytes));
+		}
+
+		if let Ok(n) = format!("-{}", digits).parse::<i128>() {
+			return Ok(Primitive::I128(n));
+		}
+		// The magnitude of a negative I256 can be at most 2^255.
+		let n = U256::from_dec_str(&digits).map_err(|_| out_of_range())?;
+		if n > U256::one() << 255 {
+			return Err(out_of_range());
+		}
+		let mut bytes = [0; 32];
+		(!n).overflowing_add(U256::one()).0.to_little_endian(&mut bytes);
+		Ok(Primitive::I256(bytes))
+	}
+
+	fn ident(&mut self) -> &'a str {
+		let ident = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+		debug_assert!(is_ident(ident) || ident == "true" || ident == "false");
+		ident
+	}
+
+	fn expect(&mut self, expected: char, desc: &'static str) -> Result<(), ParseError> {
+		match self.next() {
+			Some(c) if c == expected => Ok(()),
+			Some(_) => Err(self.error_before(ParseErrorKind::Expected(desc))),
+			None => Err(self.error(ParseErrorKind::UnexpectedEof)),
+		}
+	}
+
+	fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+		let rest = self.rest();
+		let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+		self.pos += len;
+		&rest[..len]
+	}
+
+	fn skip_whitespace(&mut self) {
+		self.take_while(char::is_whitespace);
+	}
+
+	fn rest(&self) -> &'a str {
+		&self.input[self.pos..]
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.rest().chars().next()
+	}
+
+	fn next(&mut self) -> Option<char> {
+		let c = self.peek()?;
+		self.pos += c.len_utf8();
+		Some(c)
+	}
+
+	fn error(&self, kind: ParseErrorKind) -> ParseError {
+		ParseError { offset: self.pos, kind }
+	}
+
+	// An error about the char that we just consumed.
+	fn error_before(&self, kind: ParseErrorKind) -> ParseError {
+		let len = self.input[..self.pos].chars().next_back().map(char::len_utf8).unwrap_or(0);
+		ParseError { offset: self.pos - len, kind }
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn parse(s: &str) -> Value<()> {
+		s.parse().unwrap_or_else(|e| panic!("cannot parse {:?}: {}", s, e))
+	}
+
+	fn assert_round_trips(value: Value<()>) {
+		let s = value.to_string();
+		assert_eq!(parse(&s), value, "{:?} did not round trip", s);
+	}
+
+	#[test]
+	fn parse_primitives() {
+		assert_eq!(parse("true"), Value::bool(true));
+		assert_eq!(parse(" false "), Value::bool(false));
+		assert_eq!(parse("1_000"), Value::u128(1000));
+		assert_eq!(parse("-12"), Value::i128(-12));
+		assert_eq!(parse(r#""hi \"there\"\n\u{1F600}""#), Value::str("hi \"there\"\n😀".into()));
+		assert_eq!(parse(r"'\''"), Value::char('\''));
+		assert_eq!(parse("0x01ff"), Value::unnamed_composite(vec![Value::u8(1), Value::u8(255)]));
+
+		let mut bits = BitSequence::new();
+		bits.extend([false, true, true]);
+		assert_eq!(parse("<011>"), Value::bit_sequence(bits));
+	}
+
+	#[test]
+	fn parse_big_numbers() {
+		assert_eq!(parse(&U256::MAX.to_string()), Value::primitive(Primitive::U256([255; 32])));
+		assert_eq!(parse(&u128::MAX.to_string()), Value::u128(u128::MAX));
+		assert_eq!(parse(&i128::MIN.to_string()), Value::i128(i128::MIN));
+		let min = "-57896044618658097711785492504343953926634992332820282019728792003956564819968";
+		assert_eq!(parse(min).to_string(), min);
+	}
+
+	#[test]
+	fn parse_composites_and_variants() {
+		assert_eq!(
+			parse("Transfer { dest: Id(0x0102), value: 1_000, }"),
+			Value::variant(
+				"Transfer".into(),
+				Composite::Named(vec![
+					(
+						"dest".into(),
+						Value::variant(
+							"Id".into(),
+							Composite::Unnamed(vec![Value::unnamed_composite(vec![Value::u8(1), Value::u8(2)])])
+						)
+					),
+					("value".into(), Value::u128(1000)),
+				])
+			)
+		);
+		assert_eq!(parse("None"), Value::variant("None".into(), Composite::Unnamed(vec![])));
+		assert_eq!(
+			parse("{ \"a b\": () }"),
+			Value::named_composite(vec![("a b".into(), Value::unnamed_composite(vec![]))])
+		);
+	}
+
+	#[test]
+	fn values_round_trip() {
+		assert_round_trips(Value::named_composite(vec![
+			("a".into(), Value::str("hello\t\"world\"".into())),
+			("b".into(), Value::variant("Foo".into(), Composite::Unnamed(vec![Value::u128(1), Value::bool(false)]))),
+			("c".into(), Value::variant("Bar".into(), Composite::Named(vec![("d".into(), Value::char('x'))]))),
+			("0".into(), Value::variant("Baz".into(), Composite::Unnamed(vec![]))),
+			("e".into(), Value::variant("Qux".into(), Composite::Named(vec![]))),
+			("f".into(), Value::unnamed_composite(vec![Value::u8(1), Value::u8(2)])),
+			// Numbers are parsed into 128 bit types unless they are too large, so avoid those here:
+			("h".into(), Value::primitive(Primitive::I256([128; 32]))),
+			("i".into(), Value::primitive(Primitive::U256([7; 32]))),
+			("j".into(), Value::bit_sequence(BitSequence::new())),
+			("k".into(), Value::variant("true".into(), Composite::Unnamed(vec![]))),
+			("l".into(), Value::variant("a b".into(), Composite::Named(vec![("c".into(), Value::bool(false))]))),
+		]));
+	}
+
+	#[test]
+	fn variant_names_that_are_not_identifiers_are_quoted() {
+		let value = Value::variant("false".into(), Composite::Unnamed(vec![]));
+		assert_eq!(value.to_string(), "\"false\"()");
+		assert_eq!(parse("false"), Value::bool(false));
+		assert_eq!(parse("\"false\""), Value::str("false".into()));
+	}
+
+	#[test]
+	fn variant_values_are_not_written_as_hex() {
+		let value = Value::variant("Id".into(), Composite::Unnamed(vec![Value::u8(1), Value::u8(2)]));
+		assert_eq!(value.to_string(), "Id(1, 2)");
+	}
+
+	#[test]
+	fn parse_errors() {
+		let err = |s: &str| s.parse::<Value<()>>().unwrap_err();
+
+		assert_eq!(err("(1, 2"), ParseError { offset: 5, kind: ParseErrorKind::UnexpectedEof });
+		assert_eq!(err("(1 2)"), ParseError { offset: 3, kind: ParseErrorKind::Expected("',' or ')'") });
+		assert_eq!(err("{ a 1 }"), ParseError { offset: 4, kind: ParseErrorKind::Expected("':'") });
+		assert_eq!(err("Foo bar"), ParseError { offset: 4, kind: ParseErrorKind::UnexpectedChar('b') });
+		assert_eq!(err("\"\\q\""), ParseError { offset: 1, kind: ParseErrorKind::InvalidEscape });
+		assert_eq!(err("'ab'"), ParseError { offset: 2, kind: ParseErrorKind::InvalidChar });
+		assert!(matches!(err("0x123").kind(), ParseErrorKind::InvalidHex(_)));
+		assert_eq!(err("-").to_string(), "expected a digit (at byte 1)");
+
+		let too_big = format!("{}0", U256::MAX);
+		assert_eq!(err(&too_big), ParseError { offset: 0, kind: ParseErrorKind::NumberOutOfRange(too_big.clone()) });
+	}
+}
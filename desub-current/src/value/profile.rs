@@ -0,0 +1,352 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{display, Composite, Primitive, Value, ValueDef};
+use crate::{Type, TypeId};
+use scale_info::{PortableRegistry, TypeDef, TypeDefPrimitive};
+use serde::{
+	ser::{SerializeMap, SerializeSeq},
+	Serialize, Serializer,
+};
+
+/// Options to tweak how [`Value`]s are serialized, to make the output easier to consume. By default, none
+/// of these are enabled, and values are serialized in the same way as they are without a profile.
+///
+/// A profile is applied by wrapping a value (or a decoded extrinsic or block) in [`WithProfile`], along with
+/// the type registry that it was decoded with. The type of each value is used to decide how to serialize
+/// it, so that for instance a `Vec<u8>` is always serialized as bytes, whatever its length, but a `(u8, u8)`
+/// tuple never is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerializeProfile {
+	/// Serialize sequences and arrays of bytes as `0x` prefixed hex strings.
+	pub bytes_as_hex: bool,
+	/// Serialize sequences of bytes that are printable UTF-8 as strings (only used with `bytes_as_hex`).
+	/// Strings that would start with `0x` are still serialized as hex, so that they can't be mistaken
+	/// for it.
+	pub printable_bytes_as_str: bool,
+	/// Serialize 64, 128 and 256 bit integers as decimal strings, since they can't be accurately
+	/// represented as numbers in JavaScript.
+	pub large_numbers_as_str: bool,
+	/// Serialize `None` as null and `Some(value)` as the value, and serialize `Ok` and `Err` in the
+	/// same way as serde serializes a `Result`, for example `{ "Ok": value }`.
+	pub collapse_option_and_result: bool,
+	/// Serialize variants with no values as their name.
+	pub unit_variants_as_str: bool,
+}
+
+impl SerializeProfile {
+	/// A profile which enables every option, for output that is intended to be read by humans
+	/// (or by JavaScript).
+	pub fn human_readable() -> Self {
+		SerializeProfile {
+			bytes_as_hex: true,
+			printable_bytes_as_str: true,
+			large_numbers_as_str: true,
+			collapse_option_and_result: true,
+			unit_variants_as_str: true,
+		}
+	}
+}
+
+/// Serialize a [`Value`], applying the given [`SerializeProfile`] to it and every value within it. The
+/// values in decoded extrinsics and blocks (see [`crate::decoder`]) can be serialized in the same way.
+///
+/// ```
+/// use desub_current::value::{SerializeProfile, WithProfile};
+/// use desub_current::{TypeId, Value};
+/// use scale_info::{MetaType, PortableRegistry, Registry};
+///
+/// let mut types = Registry::new();
+/// let id = TypeId::from(types.register_type(&MetaType::new::<Vec<u8>>()));
+/// let types: PortableRegistry = types.into();
+///
+/// let value = Value::unnamed_composite(vec![Value::u8(1), Value::u8(2), Value::u8(255)]).map_context(|_| id);
+/// let json = serde_json::to_value(WithProfile::new(&value, SerializeProfile::human_readable(), &types)).unwrap();
+/// assert_eq!(json, serde_json::json!("0x0102ff"));
+/// ```
+pub struct WithProfile<'a, T> {
+	item: &'a T,
+	profile: SerializeProfile,
+	types: &'a PortableRegistry,
+}
+
+impl<'a, T> WithProfile<'a, T> {
+	/// Wrap something that is to be serialized with the profile given. The type registry should be the one
+	/// that the values being serialized were decoded with.
+	pub fn new(item: &'a T, profile: SerializeProfile, types: &'a PortableRegistry) -> Self {
+		WithProfile { item, profile, types }
+	}
+
+	/// The thing being serialized.
+	pub(crate) fn item(&self) -> &'a T {
+		self.item
+	}
+
+	/// Wrap something nested inside of the item, so that it is serialized with the same profile.
+	pub(crate) fn wrap<U>(&self, item: &'a U) -> WithProfile<'a, U> {
+		WithProfile { item, profile: self.profile, types: self.types }
+	}
+}
+
+impl Serialize for WithProfile<'_, Value<TypeId>> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let profile = self.profile;
+		let ty = self.types.resolve(self.item.context.id());
+		match &self.item.value {
+			ValueDef::Composite(composite) => {
+				if profile.bytes_as_hex && ty.is_some_and(|ty| is_bytes(ty, self.types)) {
+					let bytes = match composite {
+						Composite::Unnamed(vals) => display::as_bytes(vals),
+						Composite::Named(_) => None,
+					};
+					if let Some(bytes) = bytes {
+						return serializer.serialize_str(&bytes_to_str(&bytes, profile));
+					}
+				}
+				self.wrap(composite).serialize(serializer)
+			}
+			ValueDef::Variant(variant) => {
+				if profile.collapse_option_and_result {
+					let is = |path: &str| ty.is_some_and(|ty| ty.path().segments() == [path]);
+					match (variant.name.as_str(), single_value(&variant.values)) {
+						("None", None) if is("Option") && variant.values.is_empty() => {
+							return serializer.serialize_none()
+						}
+						("Some", Some(val)) if is("Option") => return serializer.serialize_some(&self.wrap(val)),
+						("Ok", Some(val)) if is("Result") => {
+							return serializer.serialize_newtype_variant("Result", 0, "Ok", &self.wrap(val))
+						}
+						("Err", Some(val)) if is("Result") => {
+							return serializer.serialize_newtype_variant("Result", 1, "Err", &self.wrap(val))
+						}
+						_ => {}
+					}
+				}
+				if profile.unit_variants_as_str && variant.values.is_empty() {
+					return serializer.serialize_str(&variant.name);
+				}
+				let mut map = serializer.serialize_map(Some(2))?;
+				map.serialize_entry("name", &variant.name)?;
+				map.serialize_entry("values", &self.wrap(&variant.values))?;
+				map.end()
+			}
+			ValueDef::BitSequence(bits) => bits.serialize(serializer),
+			ValueDef::Primitive(primitive) => match large_number_to_str(primitive) {
+				Some(n) if profile.large_numbers_as_str => serializer.serialize_str(&n),
+				_ => primitive.serialize(serializer),
+			},
+		}
+	}
+}
+
+impl Serialize for WithProfile<'_, Composite<TypeId>> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match self.item {
+			Composite::Named(vals) => {
+				let mut map = serializer.serialize_map(Some(vals.len()))?;
+				for (key, val) in vals {
+					map.serialize_entry(key, &self.wrap(val))?;
+				}
+				map.end()
+			}
+			Composite::Unnamed(vals) => self.wrap(vals).serialize(serializer),
+		}
+	}
+}
+
+impl<'a, T> Serialize for WithProfile<'a, Vec<T>>
+where
+	WithProfile<'a, T>: Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let mut seq = serializer.serialize_seq(Some(self.item.len()))?;
+		for item in self.item {
+			seq.serialize_element(&self.wrap(item))?;
+		}
+		seq.end()
+	}
+}
+
+impl<'a, T> Serialize for WithProfile<'a, Option<T>>
+where
+	WithProfile<'a, T>: Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match self.item {
+			Some(item) => serializer.serialize_some(&self.wrap(item)),
+			None => serializer.serialize_none(),
+		}
+	}
+}
+
+/// Named values, such as signed extensions, are serialized as a `(name, value)` pair.
+impl<K: Serialize> Serialize for WithProfile<'_, (K, Value<TypeId>)> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let (name, value) = self.item;
+		(name, self.wrap(value)).serialize(serializer)
+	}
+}
+
+/// Is the type a sequence or array of `u8`s?
+fn is_bytes(ty: &Type, types: &PortableRegistry) -> bool {
+	let inner = match ty.type_def() {
+		TypeDef::Sequence(seq) => seq.type_param(),
+		TypeDef::Array(arr) => arr.type_param(),
+		_ => return false,
+	};
+	matches!(types.resolve(inner.id()).map(|ty| ty.type_def()), Some(TypeDef::Primitive(TypeDefPrimitive::U8)))
+}
+
+/// Render bytes as a string if they are printable UTF-8 and we're asked to, or as hex otherwise.
+fn bytes_to_str(bytes: &[u8], profile: SerializeProfile) -> String {
+	let printable = std::str::from_utf8(bytes).ok().filter(|s| {
+		profile.printable_bytes_as_str
+			&& !s.starts_with("0x")
+			&& s.chars().all(|c| !c.is_control() || c.is_whitespace())
+	});
+	match printable {
+		Some(s) => s.to_string(),
+		None => format!("0x{}", hex::encode(bytes)),
+	}
+}
+
+/// Render the larger integer primitives as decimal strings, returning `None` for the others.
+fn large_number_to_str(primitive: &Primitive) -> Option<String> {
+	match primitive {
+		Primitive::U64(v) => Some(v.to_string()),
+		Primitive::U128(v) => Some(v.to_string()),
+		Primitive::I64(v) => Some(v.to_string()),
+		Primitive::I128(v) => Some(v.to_string()),
+		Primitive::U256(v) => Some(display::u256_to_string(v)),
+		Primitive::I256(v) => Some(display::i256_to_string(v)),
+		_ => None,
+	}
+}
+
+/// Return the one value held by a variant, if it has exactly one unnamed value.
+fn single_value<T>(values: &Composite<T>) -> Option<&Value<T>> {
+	match values {
+		Composite::Unnamed(vals) if vals.len() == 1 => Some(&vals[0]),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	/// Serialize a value with the human readable profile, giving it (and every value in it) the ID of `T`.
+	fn assert_value_with_profile<T: scale_info::TypeInfo + 'static>(value: Value<()>, expected: serde_json::Value) {
+		let mut types = scale_info::Registry::new();
+		let id = TypeId::from(types.register_type(&scale_info::MetaType::new::<T>()));
+		let types: PortableRegistry = types.into();
+
+		let value = value.map_context(|_| id);
+		let profile = SerializeProfile::human_readable();
+		let val = serde_json::to_value(WithProfile::new(&value, profile, &types))
+			.expect("can serialize to serde_json::Value");
+		assert_eq!(val, expected);
+	}
+
+	fn bytes(bytes: &[u8]) -> Value<()> {
+		Value::unnamed_composite(bytes.iter().map(|&b| Value::u8(b)).collect())
+	}
+
+	#[test]
+	fn serialize_bytes_with_profile() {
+		assert_value_with_profile::<Vec<u8>>(bytes(&[0, 1, 255]), json!("0x0001ff"));
+		assert_value_with_profile::<Vec<u8>>(bytes(b"hello world"), json!("hello world"));
+		assert_value_with_profile::<[u8; 2]>(bytes(&[0, 1]), json!("0x0001"));
+		// Any length of bytes is serialized in the same way:
+		assert_value_with_profile::<Vec<u8>>(bytes(&[]), json!(""));
+		assert_value_with_profile::<Vec<u8>>(bytes(&[1]), json!("0x01"));
+		// Strings which look like hex aren't printed as strings:
+		assert_value_with_profile::<Vec<u8>>(bytes(b"0xab"), json!("0x30786162"));
+		// Other things that happen to contain only u8s aren't bytes:
+		assert_value_with_profile::<(u8, u8)>(bytes(&[1, 2]), json!([1, 2]));
+	}
+
+	#[test]
+	fn serialize_large_numbers_with_profile() {
+		assert_value_with_profile::<u32>(Value::u32(1), json!(1));
+		assert_value_with_profile::<u64>(Value::u64(1), json!("1"));
+		assert_value_with_profile::<u128>(Value::u128(u128::MAX), json!(u128::MAX.to_string()));
+		assert_value_with_profile::<i128>(Value::i128(-5), json!("-5"));
+
+		let mut n = [0; 32];
+		n[0] = 1;
+		n[31] = 1;
+		assert_value_with_profile::<[u8; 32]>(
+			Value::primitive(Primitive::U256(n)),
+			json!("452312848583266388373324160190187140051835877600158453279131187530910662657"),
+		);
+		assert_value_with_profile::<[u8; 32]>(Value::primitive(Primitive::I256([255; 32])), json!("-1"));
+	}
+
+	#[derive(scale_info::TypeInfo)]
+	#[allow(dead_code)]
+	enum Foo {
+		Bare,
+		Some(u8),
+		Other(u8),
+	}
+
+	#[test]
+	fn serialize_variants_with_profile() {
+		assert_value_with_profile::<Option<u8>>(Value::variant("None".into(), Composite::Unnamed(vec![])), json!(null));
+		assert_value_with_profile::<Option<u8>>(
+			Value::variant("Some".into(), Composite::Unnamed(vec![Value::u8(1)])),
+			json!(1),
+		);
+		assert_value_with_profile::<Result<u8, bool>>(
+			Value::variant("Err".into(), Composite::Unnamed(vec![Value::bool(false)])),
+			json!({ "Err": false }),
+		);
+		assert_value_with_profile::<Foo>(Value::variant("Bare".into(), Composite::Named(vec![])), json!("Bare"));
+		assert_value_with_profile::<Foo>(
+			Value::variant("Other".into(), Composite::Unnamed(vec![Value::u8(1)])),
+			json!({ "name": "Other", "values": [1] }),
+		);
+		// Only actual options are collapsed:
+		assert_value_with_profile::<Foo>(
+			Value::variant("Some".into(), Composite::Unnamed(vec![Value::u8(1)])),
+			json!({ "name": "Some", "values": [1] }),
+		);
+	}
+
+	#[test]
+	fn profile_only_applies_within_wrapper() {
+		let value = bytes(&[1, 2]);
+		assert_value_with_profile::<Vec<u8>>(value.clone(), json!("0x0102"));
+		assert_eq!(serde_json::to_value(&value).unwrap(), json!([1, 2]));
+	}
+}
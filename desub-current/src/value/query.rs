@@ -0,0 +1,354 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{Composite, Primitive, Value, ValueDef, Variant};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+
+impl<T> Value<T> {
+	/// Find the value at the path given, or `None` if there is no such value. Paths are made up of:
+	///
+	/// - `.name` (or just `name` at the start): a named field, or a variant with that name. Variants holding a
+	///   single unnamed value are stepped into, so `dest.Id` finds the account ID in a `MultiAddress::Id`.
+	///   Composites and variants with a single value are looked through if they don't match the name themselves,
+	///   so `calls[0].value` finds the `value` field of a call wrapped in a `RuntimeCall` variant.
+	/// - `[n]`: the nth value in a composite or variant.
+	/// - `..name`: a named field or variant with that name at any depth.
+	///
+	/// If more than one value matches (which can happen when a path contains `..`), the first one found is
+	/// returned. Nothing is found if the path is malformed.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use desub_current::Value;
+	///
+	/// let value: Value<()> = "Batch { calls: (Transfer { dest: Id(0x0102), value: 1 }, Remark(0x01)) }".parse().unwrap();
+	///
+	/// assert_eq!(value.at("calls[0].value").and_then(Value::as_u128), Some(1));
+	/// assert_eq!(value.at("calls[0].dest.Id").and_then(Value::as_bytes), Some(vec![1, 2]));
+	/// assert_eq!(value.at("..Remark").and_then(Value::as_bytes), Some(vec![1]));
+	/// assert!(value.at("calls[1].value").is_none());
+	/// ```
+	pub fn at(&self, path: &str) -> Option<&Value<T>> {
+		self.find_all(path).into_iter().next()
+	}
+
+	/// Find every value at the path given, in the order that they appear. See [`Value::at()`] for the
+	/// shape that paths take.
+	pub fn find_all(&self, path: &str) -> Vec<&Value<T>> {
+		let steps = match parse_path(path) {
+			Some(steps) => steps,
+			None => return Vec::new(),
+		};
+		let mut found = vec![self];
+		for step in steps {
+			found = found.into_iter().flat_map(|value| value.step(step)).collect();
+		}
+		found
+	}
+
+	/// Return the value as a `bool`, looking through any wrappers around it.
+	pub fn as_bool(&self) -> Option<bool> {
+		self.through_wrappers(|value| match value.value {
+			ValueDef::Primitive(Primitive::Bool(b)) => Some(b),
+			_ => None,
+		})
+	}
+
+	/// Return the value as a `u128` if it's an unsigned number that fits, looking through any wrappers around it.
+	pub fn as_u128(&self) -> Option<u128> {
+		self.through_wrappers(|value| match value.value {
+			ValueDef::Primitive(Primitive::U8(n)) => Some(n as u128),
+			ValueDef::Primitive(Primitive::U16(n)) => Some(n as u128),
+			ValueDef::Primitive(Primitive::U32(n)) => Some(n as u128),
+			ValueDef::Primitive(Primitive::U64(n)) => Some(n as u128),
+			ValueDef::Primitive(Primitive::U128(n)) => Some(n),
+			ValueDef::Primitive(Primitive::U256(ref n)) if n[16..].iter().all(|&b| b == 0) => {
+				Some(u128::from_le_bytes(n[..16].try_into().expect("16 bytes")))
+			}
+			_ => None,
+		})
+	}
+
+	/// Return the value as an `i128` if it's a number that fits, looking through any wrappers around it.
+	pub fn as_i128(&self) -> Option<i128> {
+		self.through_wrappers(|value| match value.value {
+			ValueDef::Primitive(Primitive::I8(n)) => Some(n as i128),
+			ValueDef::Primitive(Primitive::I16(n)) => Some(n as i128),
+			ValueDef::Primitive(Primitive::I32(n)) => Some(n as i128),
+			ValueDef::Primitive(Primitive::I64(n)) => Some(n as i128),
+			ValueDef::Primitive(Primitive::I128(n)) => Some(n),
+			_ => value.as_u128().and_then(|n| i128::try_from(n).ok()),
+		})
+	}
+
+	/// Return the value as a string, looking through any wrappers around it.
+	pub fn as_str(&self) -> Option<&str> {
+		self.through_wrappers(|value| match &value.value {
+			ValueDef::Primitive(Primitive::Str(s)) => Some(s.as_str()),
+			_ => None,
+		})
+	}
+
+	/// Return the value as bytes if it's an unnamed composite of `u8`s, such as a `Vec<u8>` or `[u8; 32]`,
+	/// looking through any wrappers around it.
+	pub fn as_bytes(&self) -> Option<Vec<u8>> {
+		self.through_wrappers(|value| match &value.value {
+			ValueDef::Composite(Composite::Unnamed(vals)) => vals
+				.iter()
+				.map(|v| match v.value {
+					ValueDef::Primitive(Primitive::U8(b)) => Some(b),
+					_ => None,
+				})
+				.collect(),
+			_ => None,
+		})
+	}
+
+	/// Return the value as a [`Variant`], looking through any composites wrapping it.
+	pub fn as_variant(&self) -> Option<&Variant<T>> {
+		self.through_wrappers(|value| match &value.value {
+			ValueDef::Variant(variant) => Some(variant),
+			_ => None,
+		})
+	}
+
+	/// Return the value as an [`AccountId32`] if it's 32 bytes, or a string containing an SS58 address (see
+	/// [`crate::decoder::AccountFormat`]). Any wrappers around it are looked through, so this works on an
+	/// `AccountId32` or on a `MultiAddress::Id`.
+	pub fn as_account_id(&self) -> Option<AccountId32> {
+		self.through_wrappers(|value| match &value.value {
+			ValueDef::Primitive(Primitive::Str(s)) => AccountId32::from_ss58check(s).ok(),
+			_ => {
+				let bytes: [u8; 32] = value.as_bytes()?.try_into().ok()?;
+				Some(AccountId32::new(bytes))
+			}
+		})
+	}
+
+	/// Hand each value to `f`, starting with this one and stepping into composites and variants with a
+	/// single value, until `f` returns something.
+	fn through_wrappers<'a, R>(&'a self, f: impl Fn(&'a Value<T>) -> Option<R>) -> Option<R> {
+		let mut value = self;
+		loop {
+			if let Some(r) = f(value) {
+				return Some(r);
+			}
+			value = only_value(values_of(value)?)?;
+		}
+	}
+
+	fn step(&self, step: Step<'_>) -> Vec<&Value<T>> {
+		match step {
+			Step::Child(name) => self.child(name).into_iter().collect(),
+			Step::Index(idx) => values_of(self).and_then(|vals| nth_value(vals, idx)).into_iter().collect(),
+			Step::Descendant(name) => {
+				let mut found = Vec::new();
+				self.descendants(name, &mut found);
+				found
+			}
+		}
+	}
+
+	/// The named field or variant given, looking through wrappers if we don't find it here.
+	fn child(&self, name: &str) -> Option<&Value<T>> {
+		self.named(name).or_else(|| only_value(values_of(self)?)?.child(name))
+	}
+
+	/// Every named field or variant with the name given, in this value or any value inside it.
+	fn descendants<'a>(&'a self, name: &str, found: &mut Vec<&'a Value<T>>) {
+		if let Some(value) = self.named(name) {
+			found.push(value);
+		}
+		if let Some(vals) = values_of(self) {
+			for value in composite_values(vals) {
+				value.descendants(name, found);
+			}
+		}
+	}
+
+	/// The named field with the name given, or the value in this variant if it has that name. A variant
+	/// that doesn't have exactly one unnamed value is handed back itself, so that its fields can be looked up.
+	fn named(&self, name: &str) -> Option<&Value<T>> {
+		match &self.value {
+			ValueDef::Variant(Variant { name: n, values: Composite::Unnamed(vals) })
+				if n == name && vals.len() == 1 =>
+			{
+				Some(&vals[0])
+			}
+			ValueDef::Variant(variant) if variant.name == name => Some(self),
+			ValueDef::Composite(Composite::Named(vals))
+			| ValueDef::Variant(Variant { values: Composite::Named(vals), .. }) => {
+				vals.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+			}
+			_ => None,
+		}
+	}
+}
+
+/// A single step in a path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step<'a> {
+	/// `.name`
+	Child(&'a str),
+	/// `[n]`
+	Index(usize),
+	/// `..name`
+	Descendant(&'a str),
+}
+
+fn parse_path(path: &str) -> Option<Vec<Step<'_>>> {
+	let mut steps = Vec::new();
+	let mut rest = path;
+	while !rest.is_empty() {
+		let (step, remaining) = if let Some(r) = rest.strip_prefix("..") {
+			let (name, r) = split_name(r)?;
+			(Step::Descendant(name), r)
+		} else if let Some(r) = rest.strip_prefix('.') {
+			let (name, r) = split_name(r)?;
+			(Step::Child(name), r)
+		} else if let Some(r) = rest.strip_prefix('[') {
+			let end = r.find(']')?;
+			(Step::Index(r[..end].trim().parse().ok()?), &r[end + 1..])
+		} else if steps.is_empty() {
+			let (name, r) = split_name(rest)?;
+			(Step::Child(name), r)
+		} else {
+			return None;
+		};
+		steps.push(step);
+		rest = remaining;
+	}
+	Some(steps)
+}
+
+/// Split a name from the start of the path given, returning `None` if it's empty.
+fn split_name(path: &str) -> Option<(&str, &str)> {
+	let end = path.find(['.', '[']).unwrap_or(path.len());
+	match end {
+		0 => None,
+		end => Some(path.split_at(end)),
+	}
+}
+
+fn values_of<T>(value: &Value<T>) -> Option<&Composite<T>> {
+	match &value.value {
+		ValueDef::Composite(composite) => Some(composite),
+		ValueDef::Variant(variant) => Some(&variant.values),
+		_ => None,
+	}
+}
+
+fn composite_values<T>(composite: &Composite<T>) -> Box<dyn Iterator<Item = &Value<T>> + '_> {
+	match composite {
+		Composite::Named(vals) => Box::new(vals.iter().map(|(_, v)| v)),
+		Composite::Unnamed(vals) => Box::new(vals.iter()),
+	}
+}
+
+fn nth_value<T>(composite: &Composite<T>, idx: usize) -> Option<&Value<T>> {
+	composite_values(composite).nth(idx)
+}
+
+fn only_value<T>(composite: &Composite<T>) -> Option<&Value<T>> {
+	match composite.len() {
+		1 => nth_value(composite, 0),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	fn value(s: &str) -> Value<()> {
+		s.parse().expect("valid value")
+	}
+
+	#[test]
+	fn parse_paths() {
+		assert_eq!(parse_path(""), Some(vec![]));
+		assert_eq!(parse_path("a.b"), Some(vec![Step::Child("a"), Step::Child("b")]));
+		assert_eq!(parse_path("[1][2].a"), Some(vec![Step::Index(1), Step::Index(2), Step::Child("a")]));
+		assert_eq!(parse_path("..who"), Some(vec![Step::Descendant("who")]));
+		assert_eq!(parse_path("calls..who"), Some(vec![Step::Child("calls"), Step::Descendant("who")]));
+
+		assert_eq!(parse_path("a."), None);
+		assert_eq!(parse_path("a[x]"), None);
+		assert_eq!(parse_path("a[1"), None);
+		assert_eq!(parse_path("a[1]b"), None);
+	}
+
+	#[test]
+	fn find_values_at_paths() {
+		let v = value("{ a: { b: (1, 2, 3) }, c: Foo { d: true }, e: Bar(Baz(\"hi\")) }");
+
+		assert_eq!(v.at(""), Some(&v));
+		assert_eq!(v.at("a.b[2]").and_then(Value::as_u128), Some(3));
+		assert_eq!(v.at("c.Foo.d").and_then(Value::as_bool), Some(true));
+		// Variants are looked through:
+		assert_eq!(v.at("c.d").and_then(Value::as_bool), Some(true));
+		assert_eq!(v.at("e.Bar.Baz").and_then(Value::as_str), Some("hi"));
+		assert_eq!(v.at("e.Baz").and_then(Value::as_str), Some("hi"));
+
+		assert_eq!(v.at("a.b[3]"), None);
+		assert_eq!(v.at("c.Bar"), None);
+		assert_eq!(v.at("nope"), None);
+		assert_eq!(v.at("a..."), None);
+	}
+
+	#[test]
+	fn find_all_values_at_paths() {
+		let v = value("(Transfer { who: 1, inner: { who: 2 } }, Other { who: 3 }, Deposit { amount: 4 })");
+
+		let who: Vec<_> = v.find_all("..who").into_iter().filter_map(Value::as_u128).collect();
+		assert_eq!(who, vec![1, 2, 3]);
+
+		let who: Vec<_> = v.find_all("[0]..who").into_iter().filter_map(Value::as_u128).collect();
+		assert_eq!(who, vec![1, 2]);
+
+		assert_eq!(v.find_all("..Deposit.amount").len(), 1);
+		assert_eq!(v.at("..who").and_then(Value::as_u128), Some(1));
+		assert!(v.find_all("..nope").is_empty());
+	}
+
+	#[test]
+	fn accessors_look_through_wrappers() {
+		assert_eq!(value("((((5))))").as_u128(), Some(5));
+		assert_eq!(value("{ inner: 5 }").as_u128(), Some(5));
+		assert_eq!(value("Some(5)").as_u128(), Some(5));
+		assert_eq!(value("Some(5)").as_variant().map(|v| v.name.as_str()), Some("Some"));
+		assert_eq!(value("(-5)").as_i128(), Some(-5));
+		assert_eq!(value("5").as_i128(), Some(5));
+		assert_eq!(value("(0x0102)").as_bytes(), Some(vec![1, 2]));
+		assert_eq!(value("(1, 2)").as_u128(), None);
+		assert_eq!(value("(1, 2)").as_bytes(), None);
+		assert_eq!(value("-5").as_u128(), None);
+	}
+
+	#[test]
+	fn account_ids() {
+		let alice = sp_keyring::AccountKeyring::Alice.to_account_id();
+		let bytes = hex::encode(&alice);
+
+		assert_eq!(value(&format!("(0x{})", bytes)).as_account_id(), Some(alice.clone()));
+		assert_eq!(value(&format!("Id((0x{}))", bytes)).as_account_id(), Some(alice.clone()));
+		assert_eq!(value(&format!("Id(\"{}\")", alice.to_ss58check())).as_account_id(), Some(alice));
+		assert_eq!(value("Id(0x0102)").as_account_id(), None);
+		assert_eq!(value("\"hello\"").as_account_id(), None);
+	}
+}
@@ -110,6 +110,79 @@ impl<T> Serialize for Variant<T> {
 	}
 }
 
+/// A wrapper around a [`Value`] which serializes the context of each value alongside it, as created by
+/// [`Value::serialize_with_context()`]. Each value is serialized as a map of `"value"` and `"context"`, where
+/// the value is serialized as normal except that any nested values are serialized in this same way.
+pub struct WithContext<'a, T>(&'a Value<T>);
+
+impl<T> Value<T> {
+	/// Return something which serializes this value along with the context of it and every value within
+	/// it. This is mostly useful with a [`super::TypeInfo`] context, so that the serialized output retains
+	/// information about the type of each value.
+	pub fn serialize_with_context(&self) -> WithContext<'_, T> {
+		WithContext(self)
+	}
+}
+
+impl<T: Serialize> Serialize for WithContext<'_, T> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		let mut map = serializer.serialize_map(Some(2))?;
+		map.serialize_entry("value", &ValueDefWithContext(&self.0.value))?;
+		map.serialize_entry("context", &self.0.context)?;
+		map.end()
+	}
+}
+
+struct ValueDefWithContext<'a, T>(&'a ValueDef<T>);
+
+impl<T: Serialize> Serialize for ValueDefWithContext<'_, T> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		match self.0 {
+			ValueDef::Composite(val) => CompositeWithContext(val).serialize(serializer),
+			ValueDef::Variant(val) => {
+				let mut map = serializer.serialize_map(Some(2))?;
+				map.serialize_entry("name", &val.name)?;
+				map.serialize_entry("values", &CompositeWithContext(&val.values))?;
+				map.end()
+			}
+			ValueDef::BitSequence(val) => val.serialize(serializer),
+			ValueDef::Primitive(val) => val.serialize(serializer),
+		}
+	}
+}
+
+struct CompositeWithContext<'a, T>(&'a Composite<T>);
+
+impl<T: Serialize> Serialize for CompositeWithContext<'_, T> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		match self.0 {
+			Composite::Named(vals) => {
+				let mut map = serializer.serialize_map(Some(vals.len()))?;
+				for (key, val) in vals {
+					map.serialize_entry(key, &WithContext(val))?;
+				}
+				map.end()
+			}
+			Composite::Unnamed(vals) => {
+				let mut seq = serializer.serialize_seq(Some(vals.len()))?;
+				for val in vals {
+					seq.serialize_element(&WithContext(val))?;
+				}
+				seq.end()
+			}
+		}
+	}
+}
+
 #[cfg(test)]
 mod test {
 
@@ -187,4 +260,20 @@ mod test {
 			}),
 		)
 	}
+
+	#[test]
+	fn serialize_with_context() {
+		let value = Value::variant("Foo".into(), Composite::Unnamed(vec![Value::bool(true)])).map_context(|_| 1u8);
+		let val = serde_json::to_value(value.serialize_with_context()).expect("can serialize to serde_json::Value");
+		assert_eq!(
+			val,
+			json!({
+				"value": {
+					"name": "Foo",
+					"values": [{ "value": true, "context": 1 }]
+				},
+				"context": 1
+			})
+		);
+	}
 }
@@ -0,0 +1,177 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{Composite, Value, ValueDef, Variant};
+use crate::metadata::primitive_name;
+use crate::{Type, TypeId};
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef};
+use serde::Serialize;
+
+/// Information about the type of a [`Value`], resolved from the type registry that it was decoded with.
+/// A [`Value<TypeId>`] can be given this context using [`Value::with_type_info()`], so that it can be handed
+/// on (or serialized, see [`Value::serialize_with_context()`]) without also needing the metadata to make sense of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeInfo {
+	/// The ID of the type in the registry.
+	pub id: TypeId,
+	/// The path of the type, for example `sp_core::crypto::AccountId32`. Primitives, sequences,
+	/// arrays and tuples don't have a path.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub path: Option<String>,
+	/// The name given to the type where it is used as a field, for example `T::Balance`.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub type_name: Option<String>,
+	/// The generic parameters of the type.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub params: Vec<TypeParam>,
+}
+
+/// A generic parameter of some type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeParam {
+	/// The name of the parameter, for example `T`.
+	pub name: String,
+	/// The ID of the type that the parameter was given, if any.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub ty: Option<TypeId>,
+	/// A readable name for the type that the parameter was given, for example
+	/// `Vec<u8>` or `sp_core::crypto::AccountId32`.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub ty_name: Option<String>,
+}
+
+impl TypeInfo {
+	fn new(id: TypeId, type_name: Option<String>, types: &PortableRegistry) -> TypeInfo {
+		let ty = match types.resolve(id.id()) {
+			Some(ty) => ty,
+			None => return TypeInfo { id, path: None, type_name, params: Vec::new() },
+		};
+		let params = ty
+			.type_params()
+			.iter()
+			.map(|param| TypeParam {
+				name: param.name().clone(),
+				ty: param.ty().map(TypeId::from),
+				ty_name: param.ty().map(|ty| readable_name(ty.id(), types)),
+			})
+			.collect();
+		TypeInfo { id, path: type_path(ty), type_name, params }
+	}
+}
+
+impl Value<TypeId> {
+	/// Resolve the [`TypeId`] that each part of this value was decoded with into a [`TypeInfo`], using the
+	/// type registry that it was decoded with. Values whose types can't be found are given an otherwise empty
+	/// [`TypeInfo`].
+	pub fn with_type_info(self, types: &PortableRegistry) -> Value<TypeInfo> {
+		self.with_type_info_and_name(None, types)
+	}
+
+	/// Like [`Value::with_type_info()`], but also give the top level value a `type_name`. This is useful when
+	/// the value is a call argument or an event field, whose type names are found alongside the call or event
+	/// rather than in the type of the value itself.
+	pub fn with_type_info_and_name(self, type_name: Option<String>, types: &PortableRegistry) -> Value<TypeInfo> {
+		let fields = fields_of(self.context, &self.value, types);
+		let type_name_at = |idx: usize| fields.and_then(|f| f.get(idx)).and_then(|f| f.type_name().cloned());
+
+		let value = match self.value {
+			ValueDef::Composite(composite) => {
+				ValueDef::Composite(composite_with_type_info(composite, type_name_at, types))
+			}
+			ValueDef::Variant(Variant { name, values }) => {
+				ValueDef::Variant(Variant { name, values: composite_with_type_info(values, type_name_at, types) })
+			}
+			ValueDef::BitSequence(bits) => ValueDef::BitSequence(bits),
+			ValueDef::Primitive(prim) => ValueDef::Primitive(prim),
+		};
+		Value { value, context: TypeInfo::new(self.context, type_name, types) }
+	}
+}
+
+fn composite_with_type_info(
+	composite: Composite<TypeId>,
+	type_name_at: impl Fn(usize) -> Option<String>,
+	types: &PortableRegistry,
+) -> Composite<TypeInfo> {
+	match composite {
+		Composite::Named(vals) => Composite::Named(
+			vals.into_iter()
+				.enumerate()
+				.map(|(idx, (name, val))| (name, val.with_type_info_and_name(type_name_at(idx), types)))
+				.collect(),
+		),
+		Composite::Unnamed(vals) => Composite::Unnamed(
+			vals.into_iter()
+				.enumerate()
+				.map(|(idx, val)| val.with_type_info_and_name(type_name_at(idx), types))
+				.collect(),
+		),
+	}
+}
+
+/// The fields that the values nested inside some composite or variant value line up with, if any.
+fn fields_of<'a>(
+	id: TypeId,
+	value: &ValueDef<TypeId>,
+	types: &'a PortableRegistry,
+) -> Option<&'a [Field<PortableForm>]> {
+	let mut ty = types.resolve(id.id())?;
+	// Compact encoded composites are decoded with the ID of the compact type, so look through it.
+	while let TypeDef::Compact(inner) = ty.type_def() {
+		ty = types.resolve(inner.type_param().id())?;
+	}
+	match (ty.type_def(), value) {
+		(TypeDef::Composite(composite), ValueDef::Composite(_)) => Some(composite.fields()),
+		(TypeDef::Variant(variant), ValueDef::Variant(value)) => {
+			variant.variants().iter().find(|v| v.name() == &value.name).map(|v| v.fields())
+		}
+		_ => None,
+	}
+}
+
+fn type_path(ty: &Type) -> Option<String> {
+	if ty.path().is_empty() {
+		None
+	} else {
+		Some(ty.path().segments().join("::"))
+	}
+}
+
+/// A Rust-like name for the type with the given ID, for example `Option<Vec<u8>>`.
+fn readable_name(id: u32, types: &PortableRegistry) -> String {
+	let ty = match types.resolve(id) {
+		Some(ty) => ty,
+		None => return format!("<unknown type {}>", id),
+	};
+	if let Some(path) = type_path(ty) {
+		let params: Vec<_> =
+			ty.type_params().iter().filter_map(|p| p.ty()).map(|p| readable_name(p.id(), types)).collect();
+		return if params.is_empty() { path } else { format!("{}<{}>", path, params.join(", ")) };
+	}
+	match ty.type_def() {
+		TypeDef::Sequence(seq) => format!("Vec<{}>", readable_name(seq.type_param().id(), types)),
+		TypeDef::Array(arr) => format!("[{}; {}]", readable_name(arr.type_param().id(), types), arr.len()),
+		TypeDef::Tuple(tuple) => {
+			let fields: Vec<_> = tuple.fields().iter().map(|f| readable_name(f.id(), types)).collect();
+			format!("({})", fields.join(", "))
+		}
+		TypeDef::Compact(compact) => format!("Compact<{}>", readable_name(compact.type_param().id(), types)),
+		TypeDef::BitSequence(_) => "BitVec".to_string(),
+		TypeDef::Primitive(prim) => primitive_name(prim).to_string(),
+		// Composites and variants without a path aren't something we expect to see.
+		TypeDef::Composite(_) | TypeDef::Variant(_) => format!("<anonymous type {}>", id),
+	}
+}
@@ -0,0 +1,33 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use desub_current::Metadata;
+use frame_metadata::{
+	v14::{ExtrinsicMetadata, PalletMetadata, RuntimeMetadataV14},
+	RuntimeMetadata,
+};
+use scale_info::meta_type;
+
+/// Builds metadata with just the pallet given.
+pub fn metadata_with_pallet(pallet: PalletMetadata) -> Metadata {
+	let extrinsic = ExtrinsicMetadata { ty: meta_type::<()>(), version: 4, signed_extensions: vec![] };
+	Metadata::from_runtime_metadata(RuntimeMetadata::V14(RuntimeMetadataV14::new(
+		vec![pallet],
+		extrinsic,
+		meta_type::<()>(),
+	)))
+	.expect("valid metadata")
+}
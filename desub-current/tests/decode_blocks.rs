@@ -0,0 +1,136 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::{Compact, Encode};
+use desub_current::{
+	decoder::{self, DecodeError, DigestItem, Header},
+	Metadata, Value,
+};
+use sp_runtime::{generic, traits::BlakeTwo256};
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata")
+}
+
+fn to_bytes(hex_str: &str) -> Vec<u8> {
+	let hex_str = hex_str.strip_prefix("0x").expect("0x should prefix hex encoded bytes");
+	hex::decode(hex_str).expect("valid bytes from hex")
+}
+
+// Balances.transfer (amount: 12345); see the decode_extrinsics tests.
+const BALANCE_TRANSFER_SIGNED: &str = "0x31028400d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d016ada9b477ef454972200e098f1186d4a2aeee776f1f6a68609797f5ba052906ad2427bdca865442158d118e2dfc82226077e4dfdff975d005685bab66eefa38a150200000500001cbd2d43530a44705ad088af313e18f80b53ef16b36177cd4b77b846f2a5f07ce5c0";
+
+// Auctions.bid unsigned; see the decode_extrinsics tests.
+const AUCTIONS_BID_UNSIGNED: &str = "0x2004480104080c1014";
+
+fn header_bytes() -> Vec<u8> {
+	let header = generic::Header::<u32, BlakeTwo256> {
+		parent_hash: [1; 32].into(),
+		number: 1000,
+		state_root: [2; 32].into(),
+		extrinsics_root: [3; 32].into(),
+		digest: generic::Digest {
+			logs: vec![
+				generic::DigestItem::PreRuntime(*b"BABE", vec![1, 2, 3]),
+				generic::DigestItem::Consensus(*b"FRNK", vec![4, 5]),
+				generic::DigestItem::Other(vec![6]),
+				generic::DigestItem::Seal(*b"BABE", vec![7; 64]),
+			],
+		},
+	};
+	header.encode()
+}
+
+fn block_bytes() -> Vec<u8> {
+	let mut bytes = header_bytes();
+	Compact(2u32).encode_to(&mut bytes);
+	bytes.extend(to_bytes(BALANCE_TRANSFER_SIGNED));
+	bytes.extend(to_bytes(AUCTIONS_BID_UNSIGNED));
+	bytes
+}
+
+#[test]
+fn decode_header() {
+	let bytes = header_bytes();
+	let cursor = &mut &*bytes;
+	let header = decoder::decode_header(cursor).expect("can decode header");
+
+	assert!(cursor.is_empty(), "No more bytes expected");
+	assert_eq!(
+		header,
+		Header {
+			parent_hash: [1; 32],
+			number: 1000,
+			state_root: [2; 32],
+			extrinsics_root: [3; 32],
+			digest: vec![
+				DigestItem::PreRuntime(*b"BABE", vec![1, 2, 3]),
+				DigestItem::Consensus(*b"FRNK", vec![4, 5]),
+				DigestItem::Other(vec![6]),
+				DigestItem::Seal(*b"BABE", vec![7; 64]),
+			]
+		}
+	);
+}
+
+#[test]
+fn decode_block() {
+	let meta = metadata();
+
+	let bytes = block_bytes();
+	let cursor = &mut &*bytes;
+	let block = decoder::decode_block(&meta, cursor).expect("can decode block");
+
+	assert!(cursor.is_empty(), "No more bytes expected");
+	assert_eq!(block.header.number, 1000);
+	assert_eq!(block.extrinsics.len(), 2);
+	assert_eq!(block.extrinsics[0].call_data.ty.name(), "transfer");
+	assert_eq!(block.extrinsics[0].call_data.arguments[1].clone().without_context(), Value::u128(12345));
+	assert_eq!(block.extrinsics[1].call_data.ty.name(), "bid");
+}
+
+#[test]
+fn decode_signed_block() {
+	let meta = metadata();
+
+	let mut bytes = block_bytes();
+	Some(vec![(*b"FRNK", vec![8u8, 9])]).encode_to(&mut bytes);
+	let cursor = &mut &*bytes;
+	let signed_block = decoder::decode_signed_block(&meta, cursor).expect("can decode signed block");
+
+	assert!(cursor.is_empty(), "No more bytes expected");
+	assert_eq!(signed_block.block.extrinsics.len(), 2);
+	assert_eq!(signed_block.justifications, Some(vec![(*b"FRNK", vec![8, 9])]));
+
+	// No justifications:
+	let mut bytes = block_bytes();
+	bytes.push(0);
+	let signed_block = decoder::decode_signed_block(&meta, &mut &*bytes).expect("can decode signed block");
+	assert_eq!(signed_block.justifications, None);
+}
+
+#[test]
+fn unknown_digest_item_is_an_error() {
+	let mut bytes = header_bytes();
+	// Replace the digest (after the hashes and compact block number) with a single item of an unknown kind:
+	bytes.truncate(32 + 2 + 32 + 32);
+	bytes.extend([4, 3]);
+
+	let err = decoder::decode_header(&mut &*bytes).unwrap_err();
+	assert!(matches!(err, DecodeError::CannotDecodeDigestItem(3)));
+}
@@ -0,0 +1,160 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::Encode;
+use desub_current::decoder::{
+	self, AllowedSlots, AuraConsensusLog, BabeConsensusLog, BabePreDigest, BeefyConsensusLog, ConsensusDigest,
+	DecodeError, DigestItem, GrandpaConsensusLog, GrandpaScheduledChange, Header, VrfSignature,
+};
+
+fn header(digest: Vec<DigestItem>) -> Header {
+	Header { parent_hash: [0; 32], number: 1, state_root: [0; 32], extrinsics_root: [0; 32], digest }
+}
+
+fn decode(item: DigestItem) -> ConsensusDigest {
+	decoder::decode_consensus_digest(&item).expect("can decode digest").expect("digest is known")
+}
+
+#[test]
+fn babe_pre_digests() {
+	let primary = (1u8, 7u32, 1000u64, [1u8; 32], [2u8; 64]).encode();
+	assert_eq!(
+		decode(DigestItem::PreRuntime(*b"BABE", primary)),
+		ConsensusDigest::BabePreDigest(BabePreDigest::Primary {
+			authority_index: 7,
+			slot: 1000,
+			vrf: VrfSignature { output: [1; 32], proof: vec![2; 64] }
+		})
+	);
+
+	let secondary_plain = (2u8, 7u32, 1000u64).encode();
+	assert_eq!(
+		decode(DigestItem::PreRuntime(*b"BABE", secondary_plain)),
+		ConsensusDigest::BabePreDigest(BabePreDigest::SecondaryPlain { authority_index: 7, slot: 1000 })
+	);
+
+	let secondary_vrf = (3u8, 7u32, 1000u64, [1u8; 32], [2u8; 64]).encode();
+	let digest = decode(DigestItem::PreRuntime(*b"BABE", secondary_vrf));
+	assert!(matches!(
+		digest,
+		ConsensusDigest::BabePreDigest(ref d @ BabePreDigest::SecondaryVrf { .. }) if d.authority_index() == 7 && d.slot() == 1000
+	));
+}
+
+#[test]
+fn babe_consensus_logs() {
+	let next_epoch = (1u8, vec![([1u8; 32], 1u64), ([2u8; 32], 1u64)], [3u8; 32]).encode();
+	assert_eq!(
+		decode(DigestItem::Consensus(*b"BABE", next_epoch)),
+		ConsensusDigest::Babe(BabeConsensusLog::NextEpochData {
+			authorities: vec![([1; 32], 1), ([2; 32], 1)],
+			randomness: [3; 32]
+		})
+	);
+
+	let next_config = (3u8, 1u8, (1u64, 4u64), 2u8).encode();
+	assert_eq!(
+		decode(DigestItem::Consensus(*b"BABE", next_config)),
+		ConsensusDigest::Babe(BabeConsensusLog::NextConfigData {
+			c: (1, 4),
+			allowed_slots: AllowedSlots::PrimaryAndSecondaryVrfSlots
+		})
+	);
+}
+
+#[test]
+fn aura_digests() {
+	assert_eq!(decode(DigestItem::PreRuntime(*b"aura", 42u64.encode())), ConsensusDigest::AuraPreDigest { slot: 42 });
+	assert_eq!(
+		decode(DigestItem::Consensus(*b"aura", (1u8, vec![[1u8; 32]]).encode())),
+		ConsensusDigest::Aura(AuraConsensusLog::AuthoritiesChange(vec![[1; 32]]))
+	);
+}
+
+#[test]
+fn grandpa_consensus_logs() {
+	let change = (vec![([1u8; 32], 1u64)], 5u32);
+	let expected_change = GrandpaScheduledChange { next_authorities: vec![([1; 32], 1)], delay: 5 };
+
+	assert_eq!(
+		decode(DigestItem::Consensus(*b"FRNK", (1u8, &change).encode())),
+		ConsensusDigest::Grandpa(GrandpaConsensusLog::ScheduledChange(expected_change.clone()))
+	);
+	assert_eq!(
+		decode(DigestItem::Consensus(*b"FRNK", (2u8, 100u32, &change).encode())),
+		ConsensusDigest::Grandpa(GrandpaConsensusLog::ForcedChange(100, expected_change))
+	);
+	assert_eq!(
+		decode(DigestItem::Consensus(*b"FRNK", (3u8, 2u64).encode())),
+		ConsensusDigest::Grandpa(GrandpaConsensusLog::OnDisabled(2))
+	);
+}
+
+#[test]
+fn beefy_consensus_logs() {
+	let validators = vec![[1u8; 33], [2u8; 33]];
+	assert_eq!(
+		decode(DigestItem::Consensus(*b"BEEF", (1u8, validators, 9u64).encode())),
+		ConsensusDigest::Beefy(BeefyConsensusLog::AuthoritiesChange {
+			validators: vec![vec![1; 33], vec![2; 33]],
+			id: 9
+		})
+	);
+	assert_eq!(
+		decode(DigestItem::Consensus(*b"BEEF", (3u8, [4u8; 32]).encode())),
+		ConsensusDigest::Beefy(BeefyConsensusLog::MmrRoot([4; 32]))
+	);
+}
+
+#[test]
+fn unknown_digests_are_ignored() {
+	let items = [
+		DigestItem::PreRuntime(*b"wibl", vec![1, 2, 3]),
+		DigestItem::Seal(*b"BABE", vec![0; 64]),
+		DigestItem::Other(vec![1]),
+		DigestItem::RuntimeEnvironmentUpdated,
+	];
+	for item in &items {
+		assert_eq!(decoder::decode_consensus_digest(item).unwrap(), None);
+	}
+}
+
+#[test]
+fn invalid_consensus_log_is_an_error() {
+	let err = decoder::decode_consensus_digest(&DigestItem::Consensus(*b"FRNK", vec![9])).unwrap_err();
+	assert!(matches!(err, DecodeError::CannotDecodeConsensusDigest { engine: "GRANDPA", index: 9 }));
+}
+
+#[test]
+fn block_author() {
+	let authorities = ["alice", "bob", "charlie"];
+
+	let babe = header(vec![
+		DigestItem::PreRuntime(*b"BABE", (2u8, 1u32, 1000u64).encode()),
+		DigestItem::Seal(*b"BABE", vec![0; 64]),
+	]);
+	assert_eq!(decoder::block_author(&babe, &authorities).unwrap(), Some(&"bob"));
+
+	// Aura authors take turns, one per slot:
+	let aura = header(vec![DigestItem::PreRuntime(*b"aura", 1000u64.encode())]);
+	assert_eq!(decoder::block_author(&aura, &authorities).unwrap(), Some(&"bob"));
+
+	let out_of_range = header(vec![DigestItem::PreRuntime(*b"BABE", (2u8, 5u32, 1000u64).encode())]);
+	assert_eq!(decoder::block_author(&out_of_range, &authorities).unwrap(), None);
+
+	let no_pre_digest = header(vec![DigestItem::Other(vec![1])]);
+	assert_eq!(decoder::block_author(&no_pre_digest, &authorities).unwrap(), None);
+}
@@ -0,0 +1,161 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::{Compact, Encode};
+use desub_current::{
+	decoder::{self, AccountFormat, DecodeError, Phase},
+	Metadata, Value,
+};
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata")
+}
+
+fn account_id_to_value<A: AsRef<[u8]>>(account_id_bytes: A) -> Value<()> {
+	Value::unnamed_composite(vec![Value::unnamed_composite(
+		account_id_bytes.as_ref().iter().map(|&b| Value::u8(b)).collect(),
+	)])
+}
+
+fn alice() -> [u8; 32] {
+	sp_keyring::AccountKeyring::Alice.to_raw_public()
+}
+
+fn bob() -> [u8; 32] {
+	sp_keyring::AccountKeyring::Bob.to_raw_public()
+}
+
+// Encode an event record by hand. `event` is expected to contain the pallet index,
+// event index and encoded event fields.
+fn event_record(phase: Vec<u8>, event: Vec<u8>, topics: Vec<[u8; 32]>) -> Vec<u8> {
+	let mut bytes = phase;
+	bytes.extend(event);
+	bytes.extend(topics.encode());
+	bytes
+}
+
+fn events(records: Vec<Vec<u8>>) -> Vec<u8> {
+	let mut bytes = Compact(records.len() as u32).encode();
+	for record in records {
+		bytes.extend(record);
+	}
+	bytes
+}
+
+#[test]
+fn decode_no_events() {
+	let meta = metadata();
+	let bytes = events(vec![]);
+	let cursor = &mut &*bytes;
+
+	let events = decoder::decode_events(&meta, cursor).expect("can decode events");
+
+	assert!(cursor.is_empty(), "No more bytes expected");
+	assert!(events.is_empty());
+}
+
+#[test]
+fn decode_events_in_each_phase() {
+	let meta = metadata();
+
+	// System.NewAccount(alice)
+	let mut new_account = vec![0u8, 3];
+	new_account.extend(alice());
+
+	// Balances.Transfer(alice, bob, 12345)
+	let mut transfer = vec![5u8, 2];
+	transfer.extend(alice());
+	transfer.extend(bob());
+	transfer.extend(12345u128.encode());
+
+	let bytes = events(vec![
+		event_record(vec![2], new_account.clone(), vec![]),
+		event_record((0u8, 1u32).encode(), transfer, vec![[1; 32], [2; 32]]),
+		event_record(vec![1], new_account, vec![]),
+	]);
+	let cursor = &mut &*bytes;
+
+	let events = decoder::decode_events(&meta, cursor).expect("can decode events");
+	assert!(cursor.is_empty(), "No more bytes expected");
+	assert_eq!(events.len(), 3);
+
+	assert_eq!(events[0].phase, Phase::Initialization);
+	assert_eq!(events[0].event.pallet_name, "System");
+	assert_eq!(&*events[0].event.ty.name(), "NewAccount");
+	assert_eq!(events[0].event.arguments.len(), 1);
+	assert_eq!(events[0].event.arguments[0].clone().without_context(), account_id_to_value(alice()));
+	assert!(events[0].topics.is_empty());
+
+	assert_eq!(events[1].phase, Phase::ApplyExtrinsic(1));
+	assert_eq!(events[1].event.pallet_name, "Balances");
+	assert_eq!(&*events[1].event.ty.name(), "Transfer");
+	assert_eq!(events[1].event.arguments.len(), 3);
+	assert_eq!(events[1].event.arguments[0].clone().without_context(), account_id_to_value(alice()));
+	assert_eq!(events[1].event.arguments[1].clone().without_context(), account_id_to_value(bob()));
+	assert_eq!(events[1].event.arguments[2].clone().without_context(), Value::u128(12345));
+	assert_eq!(events[1].topics, vec![[1; 32], [2; 32]]);
+
+	assert_eq!(events[2].phase, Phase::Finalization);
+	assert_eq!(&*events[2].event.ty.name(), "NewAccount");
+}
+
+#[test]
+fn event_account_ids_can_be_rendered_as_ss58() {
+	let meta = metadata();
+
+	// Balances.Transfer(alice, bob, 12345)
+	let mut transfer = vec![5u8, 2];
+	transfer.extend(alice());
+	transfer.extend(bob());
+	transfer.extend(12345u128.encode());
+
+	let bytes = events(vec![event_record(vec![2], transfer, vec![])]);
+	let events = decoder::decode_events(&meta, &mut &*bytes).expect("can decode events");
+
+	let format = AccountFormat::new(42);
+	let event = format.format_event(events[0].clone(), &meta);
+	let args: Vec<_> = event.event.arguments.into_iter().map(|a| a.without_context()).collect();
+	assert_eq!(
+		args,
+		vec![
+			Value::str("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".into()),
+			Value::str("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty".into()),
+			Value::u128(12345),
+		]
+	);
+}
+
+#[test]
+fn unknown_event_is_an_error() {
+	let meta = metadata();
+
+	// Pallet 0 (System) has no event at index 200:
+	let bytes = events(vec![event_record(vec![1], vec![0, 200], vec![])]);
+
+	let err = decoder::decode_events(&meta, &mut &*bytes).unwrap_err();
+	assert!(matches!(err, DecodeError::CannotFindEvent(0, 200)));
+}
+
+#[test]
+fn unknown_phase_is_an_error() {
+	let meta = metadata();
+	let bytes = events(vec![event_record(vec![3], vec![0, 3], vec![])]);
+
+	let err = decoder::decode_events(&meta, &mut &*bytes).unwrap_err();
+	assert!(matches!(err, DecodeError::CannotDecodePhase(3)));
+}
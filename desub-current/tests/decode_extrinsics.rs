@@ -15,9 +15,13 @@
 // along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
 
 use desub_current::{
-	decoder::{self, SignedExtensionWithAdditional},
-	value, Metadata, Value, ValueDef,
+	decoder::{
+		self, AccountFormat, Address, DecodeError, DecodeValueErrorKind, ExtrinsicKind, SignedExtensionWithAdditional,
+	},
+	value::{self, SerializeProfile, WithProfile},
+	Metadata, Value, ValueDef,
 };
+use sp_core::crypto::Ss58Codec;
 
 static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
 
@@ -84,10 +88,18 @@ fn balance_transfer_signed() {
 	let ext = decoder::decode_extrinsic(&meta, ext_bytes).expect("can decode extrinsic");
 
 	assert!(ext_bytes.is_empty(), "No more bytes expected");
+	assert_eq!(ext.version, 4);
+	assert_eq!(ext.kind, ExtrinsicKind::Signed);
 	assert_eq!(ext.call_data.pallet_name, "Balances");
 	assert_eq!(&*ext.call_data.ty.name(), "transfer");
 	assert_eq!(ext.call_data.arguments.len(), 2);
 	assert_eq!(ext.call_data.arguments[1].clone().without_context(), Value::u128(12345));
+
+	// Polkadot uses `()` as the account index type of its MultiAddress, so we decode the address into a Value:
+	let alice = sp_keyring::AccountKeyring::Alice.to_account_id();
+	let address = ext.signature.unwrap().address;
+	assert!(matches!(address, Address::Value(_)));
+	assert_eq!(address.account_id(), Some(alice));
 }
 
 #[test]
@@ -117,6 +129,8 @@ fn auctions_bid_unsigned() {
 	let ext = decoder::decode_unwrapped_extrinsic(&meta, ext_bytes).expect("can decode extrinsic");
 
 	assert!(ext_bytes.is_empty(), "No more bytes expected");
+	assert_eq!(ext.version, 4);
+	assert_eq!(ext.kind, ExtrinsicKind::Bare);
 	assert_eq!(ext.call_data.pallet_name, "Auctions");
 	assert_eq!(&*ext.call_data.ty.name(), "bid");
 	assert_eq!(ext.call_data.arguments.len(), 5);
@@ -127,6 +141,105 @@ fn auctions_bid_unsigned() {
 	);
 }
 
+// V5 bare extrinsics look just like V4 unsigned ones, but with a different version.
+#[test]
+fn auctions_bid_v5_bare() {
+	let meta = metadata();
+
+	// Auctions.bid (Args: (1,), 2, 3, 4, 5, all compact encoded).
+	let ext_bytes = &mut &*to_bytes("0x05480104080c1014");
+	let ext = decoder::decode_unwrapped_extrinsic(&meta, ext_bytes).expect("can decode extrinsic");
+
+	assert!(ext_bytes.is_empty(), "No more bytes expected");
+	assert_eq!(ext.version, 5);
+	assert_eq!(ext.kind, ExtrinsicKind::Bare);
+	assert_eq!(ext.signature, None);
+	assert_eq!(ext.call_data.pallet_name, "Auctions");
+	assert_eq!(&*ext.call_data.ty.name(), "bid");
+}
+
+// V5 general extrinsics have an extension version and extensions, but no signature.
+#[test]
+fn auctions_bid_v5_general() {
+	let meta = metadata();
+
+	// Auctions.bid (Args: (1,), 2, 3, 4, 5, all compact encoded), with an immortal era,
+	// a nonce of 0 and a tip of 0.
+	let ext_bytes = &mut &*to_bytes("0x4500000000480104080c1014");
+	let ext = decoder::decode_unwrapped_extrinsic(&meta, ext_bytes).expect("can decode extrinsic");
+
+	assert!(ext_bytes.is_empty(), "No more bytes expected");
+	assert_eq!(ext.version, 5);
+	assert_eq!(ext.signature, None);
+	assert_eq!(ext.call_data.pallet_name, "Auctions");
+	assert_eq!(&*ext.call_data.ty.name(), "bid");
+
+	let (extension_version, extensions) = match ext.kind {
+		ExtrinsicKind::General { extension_version, extensions } => (extension_version, extensions),
+		kind => panic!("Expected a general extrinsic, got {:?}", kind),
+	};
+	assert_eq!(extension_version, 0);
+	let names: Vec<_> = extensions.iter().map(|(name, _)| &**name).collect();
+	assert_eq!(
+		names,
+		vec![
+			"CheckSpecVersion",
+			"CheckTxVersion",
+			"CheckGenesis",
+			"CheckMortality",
+			"CheckNonce",
+			"CheckWeight",
+			"ChargeTransactionPayment",
+			"PrevalidateAttests"
+		]
+	);
+	assert_eq!(
+		extensions[3].1.clone().without_context(),
+		singleton_value(Value::variant("Immortal".to_string(), value::Composite::Unnamed(vec![])))
+	);
+}
+
+// The version and kind are left out of serialized V4 extrinsics, which serialize as they did before V5 existed.
+#[test]
+fn version_and_kind_are_only_serialized_for_v5() {
+	let meta = metadata();
+
+	let v4 = decoder::decode_unwrapped_extrinsic(&meta, &mut &*to_bytes("0x04480104080c1014")).unwrap();
+	let json = serde_json::to_value(&v4).unwrap();
+	let keys: Vec<_> = json.as_object().unwrap().keys().map(|k| &**k).collect();
+	assert_eq!(keys, vec!["call_data", "signature"]);
+
+	let v5 = decoder::decode_unwrapped_extrinsic(&meta, &mut &*to_bytes("0x4500000000480104080c1014")).unwrap();
+	let json = serde_json::to_value(&v5).unwrap();
+	assert_eq!(json["version"], 5);
+	assert_eq!(json["kind"]["General"]["extension_version"], 0);
+}
+
+#[test]
+fn unsupported_extrinsic_kind_is_an_error() {
+	let meta = metadata();
+
+	// General extrinsics don't exist in V4:
+	let err = decoder::decode_unwrapped_extrinsic(&meta, &mut &*to_bytes("0x4400000000480104080c1014")).unwrap_err();
+	assert!(matches!(err, DecodeError::CannotDecodeExtrinsicKind { version: 4, kind: 0b0100_0000 }));
+
+	// Signed extrinsics don't exist in V5:
+	let err = decoder::decode_unwrapped_extrinsic(&meta, &mut &*to_bytes("0x85480104080c1014")).unwrap_err();
+	assert!(matches!(err, DecodeError::CannotDecodeExtrinsicKind { version: 5, kind: 0b1000_0000 }));
+
+	// And the top two bits can't both be set:
+	let err = decoder::decode_unwrapped_extrinsic(&meta, &mut &*to_bytes("0xc5480104080c1014")).unwrap_err();
+	assert!(matches!(err, DecodeError::CannotDecodeExtrinsicKind { version: 5, kind: 0b1100_0000 }));
+}
+
+#[test]
+fn unsupported_extrinsic_version_is_an_error() {
+	let meta = metadata();
+
+	let err = decoder::decode_unwrapped_extrinsic(&meta, &mut &*to_bytes("0x06480104080c1014")).unwrap_err();
+	assert!(matches!(err, DecodeError::CannotDecodeExtrinsicVersion(6)));
+}
+
 #[test]
 fn auctions_bid_unsigned_excess_bytes_allowed() {
 	let meta = metadata();
@@ -290,3 +403,185 @@ fn can_decode_signer_payload() {
 		assert_eq!(additional.without_context(), expected_additional);
 	}
 }
+
+#[test]
+fn decode_extrinsics_resilient_carries_on_after_failures() {
+	let meta = metadata();
+
+	// Auctions.bid, then an unknown call, then an extrinsic with excess bytes, then Auctions.bid again.
+	let ext_bytes = to_bytes("0x102004480104080c10140c04ff002404480104080c1014ff2004480104080c1014");
+	let cursor = &mut &*ext_bytes;
+	let extrinsics = decoder::decode_extrinsics_resilient(&meta, cursor).expect("can decode extrinsics");
+
+	assert_eq!(cursor.len(), 0);
+	assert_eq!(extrinsics.len(), 4);
+	assert_eq!(extrinsics[0].as_ref().unwrap().call_data.ty.name(), "bid");
+	assert_eq!(extrinsics[3].as_ref().unwrap().call_data.ty.name(), "bid");
+
+	let failure = extrinsics[1].as_ref().unwrap_err();
+	assert_eq!((failure.index, &*failure.bytes), (1, &[0x04, 0xff, 0x00][..]));
+	assert!(matches!(failure.error, DecodeError::CannotFindCall(0xff, 0x00)));
+
+	let failure = extrinsics[2].as_ref().unwrap_err();
+	assert_eq!(failure.index, 2);
+	assert!(matches!(failure.error, DecodeError::ExcessBytes(1)));
+}
+
+#[test]
+fn decode_extrinsics_resilient_stops_at_bad_length() {
+	let meta = metadata();
+
+	// Auctions.bid, then a length prefix claiming more bytes than there are.
+	let ext_bytes = to_bytes("0x082004480104080c1014ff0401");
+	let extrinsics = decoder::decode_extrinsics_resilient(&meta, &mut &*ext_bytes).expect("can decode extrinsics");
+
+	assert_eq!(extrinsics.len(), 2);
+	assert!(extrinsics[0].is_ok());
+
+	let failure = extrinsics[1].as_ref().unwrap_err();
+	assert_eq!((failure.index, &*failure.bytes), (1, &[0xff, 0x04, 0x01][..]));
+	assert!(matches!(failure.error, DecodeError::UnexpectedExtrinsicsShape(..)));
+}
+
+// The count of extrinsics is read from the input, and shouldn't be trusted.
+#[test]
+fn decode_extrinsics_with_huge_count() {
+	let meta = metadata();
+
+	// A count of u32::MAX, but only one Auctions.bid extrinsic.
+	let ext_bytes = to_bytes("0x03ffffffff2004480104080c1014");
+
+	let extrinsics = decoder::decode_extrinsics_resilient(&meta, &mut &*ext_bytes).expect("can decode extrinsics");
+	assert_eq!(extrinsics.len(), 1);
+	assert!(extrinsics[0].is_ok());
+
+	let extrinsics = decoder::decode_extrinsics(&meta, &mut &*ext_bytes).expect("can decode extrinsics");
+	assert_eq!(extrinsics.len(), 1);
+}
+
+#[test]
+fn value_decode_errors_are_located() {
+	let meta = metadata();
+
+	// Balances.transfer, but the compact encoded value is missing its second byte:
+	let call = "050000d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27de5c0";
+	let truncated = &call[..call.len() - 2];
+
+	let err = decoder::decode_call_data(&meta, &mut &*to_bytes(&format!("0x{}", truncated))).unwrap_err();
+	let err = match err {
+		DecodeError::DecodeValueError(e) => e,
+		e => panic!("expected a value decoding error, got {:?}", e),
+	};
+	assert_eq!(err.path(), &["Balances", "transfer", "value"]);
+	// No named type was being decoded, so the type is that of the pallet's calls:
+	assert_eq!(err.type_path(), Some("pallet_balances::pallet::Call"));
+	// pallet and call index (2), MultiAddress::Id (1 + 32):
+	assert_eq!(err.offset(), 35);
+	assert!(matches!(err.kind(), DecodeValueErrorKind::CodecError(_)));
+
+	// The same call nested in a Utility.batch, after a valid one. The offset is relative to the
+	// start of the outer call data, and the type is that of the innermost named type we were decoding.
+	let batch = format!("0x1a0008{}{}", call, truncated);
+	let err = decoder::decode_call_data(&meta, &mut &*to_bytes(&batch)).unwrap_err();
+	assert_eq!(
+		err.to_string(),
+		"Failed to decode type: field `Utility.batch.calls[1].Balances.transfer.value` \
+		(type `pallet_balances::pallet::Call`, at byte 75): Not enough data to fill buffer"
+	);
+}
+
+#[test]
+fn query_decoded_call_arguments() {
+	let meta = metadata();
+	let alice = sp_keyring::AccountKeyring::Alice.to_account_id();
+
+	// Utility.batch containing two Balances.transfer calls (dest: MultiAddress::Id(Alice), value: 12345)
+	let call = "050000d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27de5c0";
+	let batch = decoder::decode_call_data(&meta, &mut &*to_bytes(&format!("0x1a0008{}{}", call, call))).unwrap();
+	let calls = &batch.arguments[0];
+
+	assert_eq!(calls.at("[1].value").and_then(Value::as_u128), Some(12345));
+	assert_eq!(calls.at("[0].Balances.transfer.dest.Id").and_then(Value::as_account_id), Some(alice.clone()));
+	assert_eq!(calls.at("[0].dest").and_then(Value::as_variant).map(|v| v.name.as_str()), Some("Id"));
+	assert_eq!(calls.at("[0].dest.Index"), None);
+
+	let dests: Vec<_> = calls.find_all("..dest").into_iter().filter_map(Value::as_account_id).collect();
+	assert_eq!(dests, vec![alice.clone(), alice]);
+}
+
+#[test]
+fn call_arguments_with_type_info() {
+	let meta = metadata();
+
+	// Balances.transfer (dest: MultiAddress::Id(Alice), value: 12345)
+	let call = to_bytes("0x050000d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27de5c0");
+	let call = decoder::decode_call_data(&meta, &mut &*call).expect("can decode call data");
+	let args = call.arguments_with_type_info(&meta);
+
+	let dest = &args[0].context;
+	assert_eq!(dest.path.as_deref(), Some("sp_runtime::multiaddress::MultiAddress"));
+	assert_eq!(dest.type_name.as_deref(), Some("<T::Lookup as StaticLookup>::Source"));
+	assert_eq!(dest.params[0].name, "AccountId");
+	assert_eq!(dest.params[0].ty_name.as_deref(), Some("sp_core::crypto::AccountId32"));
+	assert_eq!(dest.params[1].ty_name.as_deref(), Some("()"));
+
+	// Nested values are given the type names of the fields they are found in:
+	let account = match &args[0].value {
+		ValueDef::Variant(value::Variant { values: value::Composite::Unnamed(vals), .. }) => &vals[0].context,
+		v => panic!("expected MultiAddress::Id, got {:?}", v),
+	};
+	assert_eq!(account.path.as_deref(), Some("sp_core::crypto::AccountId32"));
+	assert_eq!(account.type_name.as_deref(), Some("AccountId"));
+
+	let value = serde_json::to_value(args[1].serialize_with_context()).expect("can serialize");
+	assert_eq!(value, serde_json::json!({ "value": 12345, "context": { "id": 51, "type_name": "T::Balance" } }));
+}
+
+#[test]
+fn account_ids_can_be_rendered_as_ss58() {
+	let meta = metadata();
+
+	// Balances.transfer (amount: 12345), signed by and sent to Alice.
+	let ext_bytes = &mut &*to_bytes("0x31028400d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d016ada9b477ef454972200e098f1186d4a2aeee776f1f6a68609797f5ba052906ad2427bdca865442158d118e2dfc82226077e4dfdff975d005685bab66eefa38a150200000500001cbd2d43530a44705ad088af313e18f80b53ef16b36177cd4b77b846f2a5f07ce5c0");
+	let ext = decoder::decode_extrinsic(&meta, ext_bytes).expect("can decode extrinsic");
+
+	// Polkadot uses an SS58 prefix of 0:
+	let format = AccountFormat::from_metadata(&meta);
+	assert_eq!(format.ss58_prefix(), 0);
+	let ext = format.format_extrinsic(ext, &meta);
+
+	let ferdie = sp_keyring::AccountKeyring::Ferdie.to_account_id().to_ss58check_with_version(0u16.into());
+	let alice = sp_keyring::AccountKeyring::Alice.to_account_id().to_ss58check_with_version(0u16.into());
+	let id = |address: String| Value::variant("Id".into(), value::Composite::Unnamed(vec![Value::str(address)]));
+
+	assert_eq!(ext.call_data.arguments[0].clone().without_context(), id(ferdie));
+	match ext.signature.unwrap().address {
+		Address::Value(address) => assert_eq!(address.without_context(), id(alice)),
+		address => panic!("expected the address to be a Value, got {:?}", address),
+	}
+}
+
+#[test]
+fn extrinsics_can_be_serialized_with_a_profile() {
+	let meta = metadata();
+
+	// Balances.transfer (dest: MultiAddress::Id(Ferdie), value: 12345)
+	let call = to_bytes("0x0500001cbd2d43530a44705ad088af313e18f80b53ef16b36177cd4b77b846f2a5f07ce5c0");
+	let call = decoder::decode_call_data(&meta, &mut &*call).expect("can decode call data");
+
+	let json = serde_json::to_value(WithProfile::new(&call, SerializeProfile::human_readable(), meta.types())).unwrap();
+	assert_eq!(
+		json["arguments"],
+		serde_json::json!([
+			// The AccountId32 is a newtype around the bytes:
+			{ "name": "Id", "values": [["0x1cbd2d43530a44705ad088af313e18f80b53ef16b36177cd4b77b846f2a5f07c"]] },
+			"12345"
+		])
+	);
+
+	// System.remark(remark: [1]): a Vec<u8> is serialized as hex, whatever its length.
+	let call = to_bytes("0x00010401");
+	let call = decoder::decode_call_data(&meta, &mut &*call).expect("can decode call data");
+	let json = serde_json::to_value(WithProfile::new(&call, SerializeProfile::human_readable(), meta.types())).unwrap();
+	assert_eq!(json["arguments"], serde_json::json!(["0x01"]));
+}
@@ -0,0 +1,146 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use desub_current::{
+	decoder::{self, ModuleErrorDecodeError},
+	value::{Composite, Value},
+	Metadata,
+};
+use frame_metadata::v14::{PalletErrorMetadata, PalletMetadata};
+use scale_info::{meta_type, MetaType};
+
+mod common;
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata")
+}
+
+fn to_bytes(hex_str: &str) -> Vec<u8> {
+	let hex_str = hex_str.strip_prefix("0x").expect("0x should prefix hex encoded bytes");
+	hex::decode(hex_str).expect("valid bytes from hex")
+}
+
+#[test]
+fn module_error_from_u8() {
+	let meta = metadata();
+
+	let error = decoder::decode_module_error(&meta, 5, &[2]).expect("can find error");
+	assert_eq!(error.pallet_name, "Balances");
+	assert_eq!(error.ty.name(), "InsufficientBalance");
+	assert!(error.ty.fields().is_empty());
+	assert!(!error.ty.docs().is_empty());
+}
+
+#[test]
+fn module_error_from_u8_array() {
+	let meta = metadata();
+
+	let error = decoder::decode_module_error(&meta, 5, &[2, 0, 0, 0]).expect("can find error");
+	assert_eq!(error.pallet_name, "Balances");
+	assert_eq!(error.ty.name(), "InsufficientBalance");
+}
+
+#[test]
+fn module_error_from_extrinsic_failed_event() {
+	let meta = metadata();
+
+	// System.ExtrinsicFailed(DispatchError::Module { index: 5, error: 2 }, DispatchInfo { .. })
+	let events_bytes = to_bytes("0x04000100000000010305021027000000000000000000");
+	let events = decoder::decode_events(&meta, &mut &*events_bytes).expect("can decode events");
+	assert_eq!(events[0].event.ty.name(), "ExtrinsicFailed");
+
+	let error = decoder::decode_dispatch_error(&meta, &events[0].event.arguments[0]).expect("can find error");
+	assert_eq!(error.pallet_name, "Balances");
+	assert_eq!(error.ty.name(), "InsufficientBalance");
+}
+
+// Newer runtimes wrap the index and error in a `ModuleError` struct, and the error is a `[u8; 4]`.
+#[test]
+fn module_error_from_newer_dispatch_error() {
+	let meta = metadata();
+
+	let dispatch_error = Value::variant(
+		"Module".into(),
+		Composite::Unnamed(vec![Value::named_composite(vec![
+			("index".into(), Value::u8(5)),
+			("error".into(), Value::unnamed_composite(vec![Value::u8(2), Value::u8(0), Value::u8(0), Value::u8(0)])),
+		])]),
+	);
+
+	let error = decoder::decode_dispatch_error(&meta, &dispatch_error).expect("can find error");
+	assert_eq!(error.pallet_name, "Balances");
+	assert_eq!(error.ty.name(), "InsufficientBalance");
+}
+
+#[test]
+fn invalid_module_errors() {
+	let meta = metadata();
+
+	assert_eq!(
+		decoder::decode_module_error(&meta, 5, &[2, 0]).unwrap_err(),
+		ModuleErrorDecodeError::InvalidErrorLength(2)
+	);
+	assert_eq!(
+		decoder::decode_module_error(&meta, 5, &[200]).unwrap_err(),
+		ModuleErrorDecodeError::CannotFindError(5, 200)
+	);
+	assert_eq!(
+		decoder::decode_dispatch_error(&meta, &Value::variant("BadOrigin".into(), Composite::Unnamed(vec![])))
+			.unwrap_err(),
+		ModuleErrorDecodeError::NotModuleError
+	);
+}
+
+// Builds metadata with a single pallet, `Test`, whose errors are the enum given.
+fn metadata_with_errors(errors: MetaType) -> Metadata {
+	common::metadata_with_pallet(PalletMetadata {
+		name: "Test",
+		storage: None,
+		calls: None,
+		event: None,
+		constants: vec![],
+		error: Some(PalletErrorMetadata { ty: errors }),
+		index: 0,
+	})
+}
+
+#[allow(dead_code)]
+#[derive(scale_info::TypeInfo)]
+enum TestError {
+	NoFields,
+	WithFields(u8, bool),
+}
+
+// The bytes after the error index in the `[u8; 4]` form are decoded according to the fields of the error.
+#[test]
+fn module_error_fields_are_decoded() {
+	let meta = metadata_with_errors(meta_type::<TestError>());
+
+	let error = decoder::decode_module_error(&meta, 0, &[1, 7, 1, 0]).expect("can find error");
+	assert_eq!(error.ty.name(), "WithFields");
+	let arguments: Vec<_> = error.arguments.into_iter().map(|v| v.without_context()).collect();
+	assert_eq!(arguments, vec![Value::u8(7), Value::bool(true)]);
+
+	let error = decoder::decode_module_error(&meta, 0, &[0, 0, 0, 0]).expect("can find error");
+	assert_eq!(error.ty.name(), "NoFields");
+	assert!(error.arguments.is_empty());
+
+	// There are no bytes for the fields in the single byte form:
+	let err = decoder::decode_module_error(&meta, 0, &[1]).unwrap_err();
+	assert!(matches!(err, ModuleErrorDecodeError::CannotDecodeErrorFields(_)));
+}
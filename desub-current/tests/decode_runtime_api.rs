@@ -0,0 +1,121 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::Encode;
+use desub_current::{
+	decoder::{self, DecodeError, RuntimeApiDefinitions, RuntimeApiMethod},
+	Metadata, Value,
+};
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata")
+}
+
+// Define `TransactionPaymentApi_query_info` using types found in the polkadot metadata.
+fn definitions(meta: &Metadata) -> RuntimeApiDefinitions {
+	let field_type = |pallet: &str, call: &str, idx: usize| {
+		meta.pallet(pallet).unwrap().call(call).unwrap().fields().nth(idx).unwrap().ty()
+	};
+	let vec_u8 = field_type("System", "remark", 0);
+	let u32 = field_type("Scheduler", "cancel", 1);
+	let dispatch_info = meta.pallet("System").unwrap().event("ExtrinsicSuccess").unwrap().fields().next().unwrap().ty();
+
+	let mut definitions = RuntimeApiDefinitions::new();
+	definitions.insert(
+		"TransactionPaymentApi_query_info",
+		RuntimeApiMethod { inputs: vec![("uxt".into(), vec_u8), ("len".into(), u32)], output: dispatch_info },
+	);
+	definitions
+}
+
+#[test]
+fn decode_call_arguments() {
+	let meta = metadata();
+	let definitions = definitions(&meta);
+
+	let args_bytes = (vec![1u8, 2, 3], 256u32).encode();
+	let args_cursor = &mut &*args_bytes;
+	let call = decoder::decode_runtime_api_call(&meta, &definitions, "TransactionPaymentApi_query_info", args_cursor)
+		.expect("can decode call");
+
+	assert!(args_cursor.is_empty(), "No more bytes expected");
+	assert_eq!(call.method, "TransactionPaymentApi_query_info");
+	let arguments: Vec<_> = call.arguments.into_iter().map(|(name, value)| (name, value.without_context())).collect();
+	assert_eq!(
+		arguments,
+		vec![
+			("uxt".into(), Value::unnamed_composite(vec![Value::u8(1), Value::u8(2), Value::u8(3)])),
+			("len".into(), Value::u32(256)),
+		]
+	);
+}
+
+#[test]
+fn decode_result() {
+	let meta = metadata();
+	let definitions = definitions(&meta);
+
+	// DispatchInfo { weight: 1000, class: Normal, pays_fee: Yes }
+	let result_bytes = (1000u64, 0u8, 0u8).encode();
+	let result_cursor = &mut &*result_bytes;
+	let result =
+		decoder::decode_runtime_api_result(&meta, &definitions, "TransactionPaymentApi_query_info", result_cursor)
+			.expect("can decode result");
+
+	assert!(result_cursor.is_empty(), "No more bytes expected");
+	assert_eq!(
+		result.without_context(),
+		Value::named_composite(vec![
+			("weight".into(), Value::u64(1000)),
+			("class".into(), Value::variant("Normal".into(), desub_current::value::Composite::Unnamed(vec![]))),
+			("pays_fee".into(), Value::variant("Yes".into(), desub_current::value::Composite::Unnamed(vec![]))),
+		])
+	);
+}
+
+#[test]
+fn unknown_method_is_an_error() {
+	let meta = metadata();
+	let definitions = definitions(&meta);
+
+	let err = decoder::decode_runtime_api_result(&meta, &definitions, "Core_version", &mut &[][..]).unwrap_err();
+	assert!(matches!(err, DecodeError::CannotFindRuntimeApiMethod(name) if name == "Core_version"));
+}
+
+// There are no runtime APIs in V14 metadata to fill definitions from.
+#[test]
+fn no_definitions_from_v14_metadata() {
+	let meta = metadata();
+	assert_eq!(RuntimeApiDefinitions::from_metadata(&meta), RuntimeApiDefinitions::new());
+}
+
+#[test]
+fn excess_result_bytes_are_an_error() {
+	let meta = metadata();
+	let definitions = definitions(&meta);
+
+	let result_bytes = (1000u64, 0u8, 0u8, 1u8).encode();
+	let err = decoder::decode_runtime_api_result(
+		&meta,
+		&definitions,
+		"TransactionPaymentApi_query_info",
+		&mut &*result_bytes,
+	)
+	.unwrap_err();
+	assert!(matches!(err, DecodeError::ExcessBytes(1)));
+}
@@ -16,7 +16,7 @@
 
 use codec::Encode;
 use desub_current::{
-	decoder::{self, StorageHasher},
+	decoder::{self, AccountFormat, StorageHasher},
 	Metadata, Value,
 };
 
@@ -147,6 +147,25 @@ fn balances_account() {
 	assert_hasher_eq!(keys[0].hasher, StorageHasher::Blake2_128Concat, bobs_value);
 }
 
+#[test]
+fn balances_account_key_rendered_as_ss58() {
+	let meta = metadata();
+	let storage = decoder::decode_storage(&meta);
+
+	// Balances.Account(BOB: AccountId32): PalletBalancesAccountData
+	bytes!(storage_key = "0xc2261276cc9d1f8598ea4b6a74b15c2fb99d880ec681799c0cf30e8886371da94f9aea1afa791265fae359272badc1cf8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48");
+
+	let entry = storage.decode_key(&meta, storage_key).expect("can decode storage");
+	let entry = AccountFormat::new(42).format_storage_entry(entry, &meta);
+
+	let keys = entry.details.map_keys();
+	assert_hasher_eq!(
+		keys[0].hasher,
+		StorageHasher::Blake2_128Concat,
+		Value::str("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty".into())
+	);
+}
+
 // A map storage entry keyed by a tuple of 2 Twox64Concat values.
 #[test]
 fn imonline_authoredblocks() {
@@ -176,3 +195,65 @@ fn imonline_authoredblocks() {
 	let val = decoder::decode_value_by_id(&meta, &entry.ty, &mut &*bytes).unwrap();
 	assert_eq!(val.without_context(), Value::u32(5678));
 }
+
+// We can decode a storage key and the value at that location in one go.
+#[test]
+fn imonline_authoredblocks_key_and_value() {
+	let meta = metadata();
+	let storage = decoder::decode_storage(&meta);
+
+	// ImOnline.AuthoredBlocks(1234: u32, BOB:AccountId32): u32
+	bytes!(storage_key = "0x2b06af9719ac64d755623cda8ddd9b94b1c371ded9e9c565e89ba783c4d5f5f9548491cbfe725727d2040000a647e755c30521d38eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48");
+
+	let value_bytes = 5678u32.encode();
+	let entry_with_value =
+		storage.decode_key_value(&meta, storage_key, Some(&value_bytes)).expect("can decode storage");
+	assert!(storage_key.is_empty(), "No more bytes expected");
+	assert_eq!(entry_with_value.entry.prefix, "ImOnline");
+	assert_eq!(entry_with_value.entry.name, "AuthoredBlocks");
+	assert_eq!(entry_with_value.entry.details.map_keys().len(), 2);
+	assert_eq!(entry_with_value.value.map(|v| v.without_context()), Some(Value::u32(5678)));
+}
+
+// If no value is provided for a `ValueQuery` storage entry, we decode the default value instead.
+#[test]
+fn timestamp_now_default_value() {
+	let meta = metadata();
+	let storage = decoder::decode_storage(&meta);
+
+	// Timestamp.Now(): u64
+	bytes!(storage_key = "0xf0c365c3cf59d671eb72da0e7a4113c49f1f0515f462cdcf84e0f1d6045dfcbb");
+
+	let entry_with_value = storage.decode_key_value(&meta, storage_key, None).expect("can decode storage");
+	assert_eq!(entry_with_value.entry.name, "Now");
+	assert_eq!(entry_with_value.value.map(|v| v.without_context()), Some(Value::u64(0)));
+}
+
+// If no value is provided for an `OptionQuery` storage entry, there is no value.
+#[test]
+fn democracy_blacklist_no_value() {
+	let meta = metadata();
+	let storage = decoder::decode_storage(&meta);
+
+	// Democracy.Blacklist([1u8; 32]: H256): ..
+	bytes!(storage_key = "0xf2794c22e353e9a839f12faab03a911bb7612c99e31defd01cd5a28e9967e2080101010101010101010101010101010101010101010101010101010101010101");
+
+	let entry_with_value = storage.decode_key_value(&meta, storage_key, None).expect("can decode storage");
+	assert_eq!(entry_with_value.entry.name, "Blacklist");
+	assert_eq!(entry_with_value.value, None);
+}
+
+// Values should be entirely consumed when decoding them.
+#[test]
+fn excess_value_bytes_is_an_error() {
+	let meta = metadata();
+	let storage = decoder::decode_storage(&meta);
+
+	// Timestamp.Now(): u64
+	bytes!(storage_key = "0xf0c365c3cf59d671eb72da0e7a4113c49f1f0515f462cdcf84e0f1d6045dfcbb");
+
+	let mut value_bytes = 123u64.encode();
+	value_bytes.push(1);
+	let err = storage.decode_key_value(&meta, storage_key, Some(&value_bytes)).unwrap_err();
+	assert_eq!(err, decoder::StorageDecodeError::ExcessValueBytes(1));
+}
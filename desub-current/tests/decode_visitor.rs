@@ -0,0 +1,207 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::Encode;
+use desub_current::{
+	decoder::{self, DecodeValueError, Visitor},
+	value::Primitive,
+	Metadata, TypeId,
+};
+use scale_info::{form::PortableForm, Field, Variant};
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata")
+}
+
+fn alice() -> [u8; 32] {
+	sp_keyring::AccountKeyring::Alice.to_raw_public()
+}
+
+fn bob() -> [u8; 32] {
+	sp_keyring::AccountKeyring::Bob.to_raw_public()
+}
+
+// The outer `polkadot_runtime::Event` enum.
+const EVENT_TY: u32 = 20;
+
+// Balances.Transfer(alice, bob, 12345), wrapped in the outer event enum.
+fn transfer_event() -> Vec<u8> {
+	let mut bytes = vec![5u8, 2];
+	bytes.extend(alice());
+	bytes.extend(bob());
+	bytes.extend(12345u128.encode());
+	bytes
+}
+
+// Collects the bytes of every `AccountId32` that it comes across.
+struct AccountIdCollector<'m> {
+	metadata: &'m Metadata,
+	account_ids: Vec<[u8; 32]>,
+	// The bytes of the account ID we're currently inside, if any.
+	current: Option<Vec<u8>>,
+}
+
+impl<'m> AccountIdCollector<'m> {
+	fn is_account_id(&self, ty: TypeId) -> bool {
+		let ty = self.metadata.resolve(ty).expect("type exists");
+		ty.path().segments().last().map(|s| &**s) == Some("AccountId32")
+	}
+}
+
+impl<'m> Visitor for AccountIdCollector<'m> {
+	type Error = DecodeValueError;
+
+	fn visit_composite_start(&mut self, ty: TypeId, _fields: &[Field<PortableForm>]) -> Result<(), Self::Error> {
+		if self.is_account_id(ty) {
+			self.current = Some(Vec::with_capacity(32));
+		}
+		Ok(())
+	}
+
+	fn visit_primitive(&mut self, _ty: TypeId, value: Primitive) -> Result<(), Self::Error> {
+		if let (Some(bytes), Primitive::U8(b)) = (&mut self.current, value) {
+			bytes.push(b);
+		}
+		Ok(())
+	}
+
+	fn visit_composite_end(&mut self, ty: TypeId) -> Result<(), Self::Error> {
+		if self.is_account_id(ty) {
+			let bytes = self.current.take().expect("started account ID");
+			self.account_ids.push(bytes.try_into().expect("32 bytes"));
+		}
+		Ok(())
+	}
+}
+
+#[test]
+fn collect_account_ids() {
+	let meta = metadata();
+	let bytes = transfer_event();
+	let cursor = &mut &*bytes;
+
+	let mut visitor = AccountIdCollector { metadata: &meta, account_ids: vec![], current: None };
+	decoder::decode_with_visitor(&meta, EVENT_TY, cursor, &mut visitor).expect("can decode event");
+
+	assert!(cursor.is_empty(), "No more bytes expected");
+	assert_eq!(visitor.account_ids, vec![alice(), bob()]);
+}
+
+// Records a line for each part of the value that it visits.
+#[derive(Default)]
+struct Tracer(Vec<String>);
+
+impl Visitor for Tracer {
+	type Error = DecodeValueError;
+
+	fn visit_primitive(&mut self, _ty: TypeId, value: Primitive) -> Result<(), Self::Error> {
+		self.0.push(format!("{:?}", value));
+		Ok(())
+	}
+	fn visit_variant_start(&mut self, _ty: TypeId, variant: &Variant<PortableForm>) -> Result<(), Self::Error> {
+		self.0.push(format!("variant {}", variant.name()));
+		Ok(())
+	}
+	fn visit_variant_end(&mut self, _ty: TypeId) -> Result<(), Self::Error> {
+		self.0.push("end variant".into());
+		Ok(())
+	}
+	fn visit_sequence_start(&mut self, _ty: TypeId, len: usize) -> Result<(), Self::Error> {
+		self.0.push(format!("sequence {}", len));
+		Ok(())
+	}
+	fn visit_sequence_end(&mut self, _ty: TypeId) -> Result<(), Self::Error> {
+		self.0.push("end sequence".into());
+		Ok(())
+	}
+}
+
+#[test]
+fn visits_values_in_order() {
+	let meta = metadata();
+
+	let bytes = transfer_event();
+	let mut tracer = Tracer::default();
+	decoder::decode_with_visitor(&meta, EVENT_TY, &mut &*bytes, &mut tracer).expect("can decode event");
+
+	let mut expected = vec!["variant Balances".to_string(), "variant Transfer".to_string()];
+	for account in [alice(), bob()] {
+		expected.push("sequence 32".into());
+		expected.extend(account.iter().map(|b| format!("U8({})", b)));
+		expected.push("end sequence".into());
+	}
+	expected.extend(["U128(12345)".to_string(), "end variant".into(), "end variant".into()]);
+
+	assert_eq!(tracer.0, expected);
+}
+
+// A visitor can bail out early with its own error.
+#[test]
+fn visitor_errors_are_returned() {
+	#[derive(Debug, PartialEq)]
+	enum Error {
+		Decode,
+		FoundU128,
+	}
+	impl From<DecodeValueError> for Error {
+		fn from(_: DecodeValueError) -> Self {
+			Error::Decode
+		}
+	}
+
+	struct FailOnU128;
+	impl Visitor for FailOnU128 {
+		type Error = Error;
+		fn visit_primitive(&mut self, _ty: TypeId, value: Primitive) -> Result<(), Self::Error> {
+			match value {
+				Primitive::U128(_) => Err(Error::FoundU128),
+				_ => Ok(()),
+			}
+		}
+	}
+
+	let meta = metadata();
+	let bytes = transfer_event();
+	let err = decoder::decode_with_visitor(&meta, EVENT_TY, &mut &*bytes, &mut FailOnU128).unwrap_err();
+	assert_eq!(err, Error::FoundU128);
+
+	// Decode errors are converted into the visitor's error type:
+	let err = decoder::decode_with_visitor(&meta, EVENT_TY, &mut &bytes[..40], &mut FailOnU128).unwrap_err();
+	assert_eq!(err, Error::Decode);
+}
+
+// Strings are borrowed from the input bytes and handed to `visit_str`.
+#[test]
+fn strings_are_borrowed() {
+	struct StrVisitor<'a>(&'a mut Vec<String>);
+	impl<'a> Visitor for StrVisitor<'a> {
+		type Error = DecodeValueError;
+		fn visit_str(&mut self, _ty: TypeId, value: &str) -> Result<(), Self::Error> {
+			self.0.push(value.to_uppercase());
+			Ok(())
+		}
+	}
+
+	let meta = metadata();
+	// RuntimeVersion starts with the spec and impl names:
+	let bytes = ("polkadot", "parity-polkadot", 0u32, 9110u32, 0u32, Vec::<([u8; 8], u32)>::new(), 5u32).encode();
+
+	let mut strings = vec![];
+	decoder::decode_with_visitor(&meta, 121u32, &mut &*bytes, &mut StrVisitor(&mut strings)).expect("can decode");
+	assert_eq!(strings, vec!["POLKADOT", "PARITY-POLKADOT"]);
+}
@@ -0,0 +1,268 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use desub_current::{
+	decoder::{self, Address, Signature},
+	encoder::{self, ExtrinsicEncodeError},
+	value, Metadata, Value,
+};
+use frame_metadata::{
+	v14::{ExtrinsicMetadata, PalletCallMetadata, PalletMetadata, RuntimeMetadataV14, SignedExtensionMetadata},
+	RuntimeMetadata,
+};
+use scale_info::{meta_type, TypeInfo};
+use std::marker::PhantomData;
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata")
+}
+
+fn to_bytes(hex_str: &str) -> Vec<u8> {
+	let hex_str = hex_str.strip_prefix("0x").expect("0x should prefix hex encoded bytes");
+	hex::decode(hex_str).expect("valid bytes from hex")
+}
+
+fn account_id_to_value<A: AsRef<[u8]>>(account_id_bytes: A) -> Value<()> {
+	Value::unnamed_composite(vec![Value::unnamed_composite(
+		account_id_bytes.as_ref().iter().map(|&b| Value::u8(b)).collect(),
+	)])
+}
+
+// Signed Balances.transfer (Alice -> Bob, amount: 12345); see the decode_extrinsics tests.
+const BALANCE_TRANSFER_SIGNED: &str = "0x31028400d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d016ada9b477ef454972200e098f1186d4a2aeee776f1f6a68609797f5ba052906ad2427bdca865442158d118e2dfc82226077e4dfdff975d005685bab66eefa38a150200000500001cbd2d43530a44705ad088af313e18f80b53ef16b36177cd4b77b846f2a5f07ce5c0";
+
+#[test]
+fn auctions_bid_unsigned() {
+	let meta = metadata();
+
+	// Auctions.bid (Args: (1,), 2, 3, 4, 5, all compact encoded).
+	let arguments = vec![
+		Value::unnamed_composite(vec![Value::u32(1)]),
+		Value::u32(2),
+		Value::u32(3),
+		Value::u32(4),
+		Value::u128(5),
+	];
+	let builder = encoder::build_extrinsic(&meta, "Auctions", "bid", &arguments).unwrap();
+
+	assert_eq!(builder.call_data(), &*to_bytes("0x480104080c1014"));
+	assert_eq!(builder.unsigned(), to_bytes("0x2004480104080c1014"));
+}
+
+#[test]
+fn balance_transfer_call_data() {
+	let meta = metadata();
+	let bob = account_id_to_value(sp_keyring::AccountKeyring::Bob.to_account_id());
+
+	// Balances.transfer (Args: MultiAddress::Id(Bob), 12345).
+	let arguments = vec![Value::variant("Id".into(), value::Composite::Unnamed(vec![bob])), Value::u128(12345)];
+	let builder = encoder::build_extrinsic(&meta, "Balances", "transfer", &arguments).unwrap();
+
+	assert_eq!(
+		builder.call_data(),
+		&*to_bytes("0x0500008eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48e5c0")
+	);
+}
+
+// Decoding and then re-encoding a signed extrinsic should give back the original bytes.
+#[test]
+fn balance_transfer_signed_roundtrip() {
+	let meta = metadata();
+
+	let ext_bytes = to_bytes(BALANCE_TRANSFER_SIGNED);
+	let ext = decoder::decode_extrinsic(&meta, &mut &*ext_bytes).expect("can decode extrinsic");
+	let signature = ext.signature.expect("extrinsic is signed");
+	let extensions: Vec<_> = signature.extensions.into_iter().map(|(_, value)| value).collect();
+
+	let builder = encoder::build_extrinsic_from_call_data(&meta, &ext.call_data).unwrap();
+	let encoded = builder.signed(&signature.address, &signature.signature, &extensions).unwrap();
+
+	assert_eq!(encoded, ext_bytes);
+}
+
+// Decoding and then re-encoding a signer payload should give back the original bytes.
+#[test]
+fn signer_payload_roundtrip() {
+	let meta = metadata();
+
+	// Staking.chill, along with signed extensions and additional data.
+	let payload_bytes = to_bytes("0x0706b9340000962300000800000091b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c31c81d421f68281950ad2901291603b5e49fc5c872f129e75433f4b55f07ca072");
+	let payload = decoder::decode_signer_payload(&meta, &mut &*payload_bytes).expect("can decode signer payload");
+	let (extensions, additional): (Vec<_>, Vec<_>) =
+		payload.extensions.into_iter().map(|(_, ext)| (ext.extension, ext.additional)).unzip();
+
+	let builder = encoder::build_extrinsic(&meta, "Staking", "chill", &payload.call_data.arguments).unwrap();
+	let encoded = builder.signer_payload(&extensions, &additional).unwrap();
+
+	assert_eq!(encoded, payload_bytes);
+}
+
+#[test]
+fn unknown_call_is_an_error() {
+	let meta = metadata();
+
+	let err = encoder::build_extrinsic::<()>(&meta, "Balances", "wibble", &[]).err().unwrap();
+	assert_eq!(err, ExtrinsicEncodeError::CallNotFound { pallet: "Balances".into(), call: "wibble".into() });
+}
+
+#[test]
+fn wrong_number_of_arguments_is_an_error() {
+	let meta = metadata();
+
+	let err = encoder::build_extrinsic(&meta, "Balances", "transfer", &[Value::u128(1)]).err().unwrap();
+	assert_eq!(err, ExtrinsicEncodeError::WrongNumberOfArguments { expected: 2, got: 1 });
+}
+
+#[test]
+fn argument_of_wrong_shape_is_an_error() {
+	let meta = metadata();
+
+	let err = encoder::build_extrinsic(&meta, "Balances", "transfer", &[Value::u32(1), Value::u128(1)]).err().unwrap();
+	assert_eq!(err.to_string(), "Couldn't encode the value provided for argument 'dest': expected variant, got u32");
+}
+
+#[test]
+fn balance_transfer_call_data_from_json() {
+	let meta = metadata();
+	let bob = sp_keyring::AccountKeyring::Bob.to_account_id();
+
+	// Balances.transfer (Args: MultiAddress::Id(Bob), 12345), given the way polkadot-js would.
+	let json = serde_json::json!({ "dest": { "id": bob.to_string() }, "value": "12345" });
+	let arguments = encoder::call_arguments_from_json(&meta, "Balances", "transfer", &json).unwrap();
+	let builder = encoder::build_extrinsic(&meta, "Balances", "transfer", &arguments).unwrap();
+
+	assert_eq!(
+		builder.call_data(),
+		&*to_bytes("0x0500008eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48e5c0")
+	);
+}
+
+#[test]
+fn utility_batch_call_data_from_json() {
+	let meta = metadata();
+	let bob = sp_keyring::AccountKeyring::Bob.to_account_id();
+
+	// Utility.batch, containing two of the Balances.transfer calls above.
+	let transfer =
+		serde_json::json!({ "Balances": { "transfer": { "dest": { "Id": bob.to_string() }, "value": 12345 } } });
+	let json = serde_json::json!([[transfer, transfer]]);
+	let arguments = encoder::call_arguments_from_json(&meta, "Utility", "batch", &json).unwrap();
+	let builder = encoder::build_extrinsic(&meta, "Utility", "batch", &arguments).unwrap();
+
+	let transfer_bytes = "0500008eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48e5c0";
+	assert_eq!(hex::encode(builder.call_data()), format!("1a0008{transfer_bytes}{transfer_bytes}"));
+}
+
+#[test]
+fn json_argument_errors_are_located() {
+	let meta = metadata();
+
+	let json = serde_json::json!({ "dest": { "Id": "5Gr" }, "value": 1 });
+	let err = encoder::call_arguments_from_json(&meta, "Balances", "transfer", &json).unwrap_err();
+	assert_eq!(err.to_string(), "field `dest.Id` '5Gr' is not a valid SS58 address");
+
+	let json = serde_json::json!({ "dest": { "Raw": "0x00" }, "value": -1 });
+	let err = encoder::call_arguments_from_json(&meta, "Balances", "transfer", &json).unwrap_err();
+	assert_eq!(err.to_string(), "field `value` -1 is out of range for u128");
+
+	let err = encoder::call_arguments_from_json(&meta, "Balances", "wibble", &serde_json::json!({})).unwrap_err();
+	assert_eq!(err.to_string(), "couldn't find a call named 'wibble' in the pallet 'Balances'");
+}
+
+#[test]
+fn wrong_number_of_signed_extensions_is_an_error() {
+	let meta = metadata();
+
+	let ext_bytes = to_bytes(BALANCE_TRANSFER_SIGNED);
+	let ext = decoder::decode_extrinsic(&meta, &mut &*ext_bytes).expect("can decode extrinsic");
+	let signature = ext.signature.expect("extrinsic is signed");
+
+	let builder = encoder::build_extrinsic_from_call_data(&meta, &ext.call_data).unwrap();
+	let err = builder.signed::<()>(&signature.address, &signature.signature, &[]).unwrap_err();
+
+	assert_eq!(
+		err,
+		ExtrinsicEncodeError::WrongNumberOfSignedExtensions {
+			expected: meta.extrinsic().signed_extensions().len(),
+			got: 0
+		}
+	);
+}
+
+// Some chains, such as EVM compatible ones, use address and signature types other than the standard
+// Substrate `MultiAddress` and `MultiSignature`. We should follow the types given in the metadata.
+#[test]
+fn signed_with_metadata_address_and_signature_types() {
+	#[derive(TypeInfo)]
+	#[allow(dead_code)]
+	struct AccountId20([u8; 20]);
+	#[derive(TypeInfo)]
+	#[allow(dead_code)]
+	struct EthereumSignature([u8; 65]);
+	#[derive(TypeInfo)]
+	#[allow(dead_code, non_camel_case_types)]
+	enum SystemCall {
+		remark { remark: Vec<u8> },
+	}
+	#[derive(TypeInfo)]
+	struct UncheckedExtrinsic<Address, Call, Signature, Extra>(PhantomData<(Address, Call, Signature, Extra)>);
+	#[derive(TypeInfo)]
+	struct Runtime;
+
+	let meta = Metadata::from_runtime_metadata(RuntimeMetadata::V14(RuntimeMetadataV14::new(
+		vec![PalletMetadata {
+			name: "System",
+			storage: None,
+			calls: Some(PalletCallMetadata { ty: meta_type::<SystemCall>() }),
+			event: None,
+			constants: vec![],
+			error: None,
+			index: 0,
+		}],
+		ExtrinsicMetadata {
+			ty: meta_type::<UncheckedExtrinsic<AccountId20, SystemCall, EthereumSignature, ()>>(),
+			version: 4,
+			signed_extensions: vec![SignedExtensionMetadata {
+				identifier: "CheckNonce",
+				ty: meta_type::<codec::Compact<u32>>(),
+				additional_signed: meta_type::<()>(),
+			}],
+		},
+		meta_type::<Runtime>(),
+	)))
+	.expect("valid metadata");
+
+	let bytes_value = |b: u8, n: usize| Value::unnamed_composite(vec![Value::u8(b); n]);
+	let address = Value::unnamed_composite(vec![bytes_value(1, 20)]);
+	let signature = Value::unnamed_composite(vec![bytes_value(2, 65)]);
+
+	let builder = encoder::build_extrinsic(&meta, "System", "remark", &[bytes_value(3, 4)]).unwrap();
+	let encoded = builder.signed_with_values(&address, &signature, &[Value::u32(7)]).unwrap();
+
+	let ext = decoder::decode_extrinsic(&meta, &mut &*encoded).expect("can decode extrinsic");
+	let ext_signature = ext.signature.expect("extrinsic is signed");
+	assert_eq!(ext.call_data.pallet_name, "System");
+	assert_eq!(ext.call_data.ty.name(), "remark");
+	assert!(matches!(&ext_signature.address, Address::Value(v) if v.clone().without_context() == address));
+	assert!(matches!(&ext_signature.signature, Signature::Value(v) if v.clone().without_context() == signature));
+	assert_eq!(ext_signature.extensions[0].1.clone().without_context(), Value::u32(7));
+
+	// The address must match the address type given in the metadata:
+	let err = builder.signed_with_values(&Value::u32(1), &signature, &[Value::u32(7)]).unwrap_err();
+	assert!(matches!(err, ExtrinsicEncodeError::CouldNotEncodeAddress(..)));
+}
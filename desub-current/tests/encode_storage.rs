@@ -0,0 +1,221 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use desub_current::{
+	decoder::{self, StorageDecodeError, StorageHasher},
+	encoder::{self, EncodeValueErrorKind, StorageEncodeError},
+	Metadata, Value,
+};
+use frame_metadata::v14::{
+	PalletMetadata, PalletStorageMetadata, StorageEntryMetadata, StorageEntryModifier, StorageEntryType,
+	StorageHasher as FrameStorageHasher,
+};
+use scale_info::{meta_type, MetaType};
+
+mod common;
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata")
+}
+
+fn account_id_to_value<A: AsRef<[u8]>>(account_id_bytes: A) -> Value<()> {
+	Value::unnamed_composite(vec![Value::unnamed_composite(
+		account_id_bytes.as_ref().iter().map(|&b| Value::u8(b)).collect(),
+	)])
+}
+
+fn to_hex(bytes: Vec<u8>) -> String {
+	format!("0x{}", hex::encode(bytes))
+}
+
+#[test]
+fn timestamp_now() {
+	let meta = metadata();
+
+	let key = encoder::encode_storage_key::<()>(&meta, "Timestamp", "Now", &[]).unwrap();
+	assert_eq!(to_hex(key), "0xf0c365c3cf59d671eb72da0e7a4113c49f1f0515f462cdcf84e0f1d6045dfcbb");
+}
+
+#[test]
+fn democracy_blacklist() {
+	let meta = metadata();
+
+	let key = encoder::encode_storage_key(
+		&meta,
+		"Democracy",
+		"Blacklist",
+		&[Value::unnamed_composite(vec![Value::u8(1); 32])],
+	)
+	.unwrap();
+	assert_eq!(to_hex(key), "0xf2794c22e353e9a839f12faab03a911bb7612c99e31defd01cd5a28e9967e2080101010101010101010101010101010101010101010101010101010101010101");
+}
+
+#[test]
+fn system_blockhash() {
+	let meta = metadata();
+
+	let key = encoder::encode_storage_key(&meta, "System", "BlockHash", &[Value::u32(1000)]).unwrap();
+	assert_eq!(
+		to_hex(key),
+		"0x26aa394eea5630e07c48ae0c9558cef7a44704b568d21667356a5a050c118746b6ff6f7d467b87a9e8030000"
+	);
+}
+
+#[test]
+fn balances_account() {
+	let meta = metadata();
+	let bob = account_id_to_value(sp_keyring::AccountKeyring::Bob.to_account_id());
+
+	let key = encoder::encode_storage_key(&meta, "Balances", "Account", &[bob]).unwrap();
+	assert_eq!(to_hex(key), "0xc2261276cc9d1f8598ea4b6a74b15c2fb99d880ec681799c0cf30e8886371da94f9aea1afa791265fae359272badc1cf8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48");
+}
+
+#[test]
+fn imonline_authoredblocks() {
+	let meta = metadata();
+	let bob = account_id_to_value(sp_keyring::AccountKeyring::Bob.to_account_id());
+
+	let key = encoder::encode_storage_key(&meta, "ImOnline", "AuthoredBlocks", &[Value::u32(1234), bob]).unwrap();
+	assert_eq!(to_hex(key), "0x2b06af9719ac64d755623cda8ddd9b94b1c371ded9e9c565e89ba783c4d5f5f9548491cbfe725727d2040000a647e755c30521d38eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48");
+}
+
+// Providing only some of the keys hands back a prefix of the full key.
+#[test]
+fn imonline_authoredblocks_partial_key() {
+	let meta = metadata();
+
+	let key = encoder::encode_storage_key(&meta, "ImOnline", "AuthoredBlocks", &[Value::u32(1234)]).unwrap();
+	assert_eq!(
+		to_hex(key),
+		"0x2b06af9719ac64d755623cda8ddd9b94b1c371ded9e9c565e89ba783c4d5f5f9548491cbfe725727d2040000"
+	);
+}
+
+// Encoded keys can be decoded back into the values that we started with.
+#[test]
+fn encoded_keys_can_be_decoded() {
+	let meta = metadata();
+	let storage = decoder::decode_storage(&meta);
+	let bob = account_id_to_value(sp_keyring::AccountKeyring::Bob.to_account_id());
+
+	let key =
+		encoder::encode_storage_key(&meta, "ImOnline", "AuthoredBlocks", &[Value::u32(5678), bob.clone()]).unwrap();
+	let entry = storage.decode_key(&meta, &mut &*key).unwrap();
+	let keys = entry.details.map_keys();
+
+	assert_eq!(entry.prefix, "ImOnline");
+	assert_eq!(entry.name, "AuthoredBlocks");
+	assert_eq!(keys.len(), 2);
+	assert!(
+		matches!(&keys[0].hasher, StorageHasher::Twox64Concat(v) if v.clone().without_context() == Value::u32(5678))
+	);
+	assert!(matches!(&keys[1].hasher, StorageHasher::Twox64Concat(v) if v.clone().without_context() == bob));
+}
+
+#[test]
+fn unknown_entry_is_an_error() {
+	let meta = metadata();
+
+	let err = encoder::encode_storage_key::<()>(&meta, "Timestamp", "Later", &[]).unwrap_err();
+	assert_eq!(err, StorageEncodeError::EntryNotFound { prefix: "Timestamp".into(), name: "Later".into() });
+}
+
+#[test]
+fn too_many_keys_is_an_error() {
+	let meta = metadata();
+
+	let err = encoder::encode_storage_key(&meta, "System", "BlockHash", &[Value::u32(1), Value::u32(2)]).unwrap_err();
+	assert_eq!(err, StorageEncodeError::TooManyKeys { expected: 1, got: 2 });
+}
+
+#[test]
+fn key_of_wrong_shape_is_an_error() {
+	let meta = metadata();
+
+	// The block number is a u32, so this will not fit:
+	let err = encoder::encode_storage_key(&meta, "System", "BlockHash", &[Value::u64(u64::MAX)]).unwrap_err();
+	match err {
+		StorageEncodeError::CouldNotEncodeKey { key, encode_error } => {
+			assert_eq!(key, 0);
+			assert_eq!(
+				encode_error.kind(),
+				&EncodeValueErrorKind::NumberOutOfRange { value: u64::MAX.to_string(), expected: "u32" }
+			);
+		}
+		err => panic!("Unexpected error: {:?}", err),
+	}
+}
+
+// Builds metadata with a single pallet, `Test`, containing a map with the hashers and key type given.
+fn metadata_with_map(hashers: Vec<FrameStorageHasher>, key: MetaType) -> Metadata {
+	let storage = PalletStorageMetadata {
+		prefix: "Test",
+		entries: vec![StorageEntryMetadata {
+			name: "Map",
+			modifier: StorageEntryModifier::Optional,
+			ty: StorageEntryType::Map { hashers, key, value: meta_type::<u64>() },
+			default: vec![],
+			docs: vec![],
+		}],
+	};
+	common::metadata_with_pallet(PalletMetadata {
+		name: "Test",
+		storage: Some(storage),
+		calls: None,
+		event: None,
+		constants: vec![],
+		error: None,
+		index: 0,
+	})
+}
+
+// A map with a single hasher has a single key, even if that key is a tuple.
+#[test]
+fn tuple_key_with_single_hasher() {
+	let meta = metadata_with_map(vec![FrameStorageHasher::Twox64Concat], meta_type::<(u32, u8)>());
+
+	let key = Value::unnamed_composite(vec![Value::u32(1), Value::u8(2)]);
+	let encoded = encoder::encode_storage_key(&meta, "Test", "Map", &[key]).unwrap();
+	let key_bytes = [1, 0, 0, 0, 2];
+
+	let mut expected = sp_core::twox_128(b"Test").to_vec();
+	expected.extend(sp_core::twox_128(b"Map"));
+	expected.extend(sp_core::twox_64(&key_bytes));
+	expected.extend(key_bytes);
+	assert_eq!(encoded, expected);
+}
+
+#[test]
+fn keys_not_lining_up_with_hashers_is_an_error() {
+	let meta =
+		metadata_with_map(vec![FrameStorageHasher::Twox64Concat, FrameStorageHasher::Identity], meta_type::<u32>());
+
+	let err = encoder::encode_storage_key(&meta, "Test", "Map", &[Value::u32(1)]).unwrap_err();
+	assert_eq!(err, StorageEncodeError::KeysAndHashersMismatch { keys: 1, hashers: 2 });
+}
+
+#[test]
+fn decoding_keys_not_lining_up_with_hashers_is_an_error() {
+	let meta =
+		metadata_with_map(vec![FrameStorageHasher::Twox64Concat, FrameStorageHasher::Identity], meta_type::<u32>());
+
+	let mut key = sp_core::twox_128(b"Test").to_vec();
+	key.extend(sp_core::twox_128(b"Map"));
+	let err = decoder::decode_storage(&meta).decode_key(&meta, &mut &*key).unwrap_err();
+	assert_eq!(err, StorageDecodeError::KeysAndHashersMismatch { keys: 1, hashers: 2 });
+}
@@ -0,0 +1,108 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use desub_current::Metadata;
+use frame_metadata::v14::{StorageEntryModifier, StorageHasher};
+use scale_info::TypeDef;
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata")
+}
+
+#[test]
+fn list_pallets() {
+	let meta = metadata();
+
+	let pallets: Vec<_> = meta.pallets().map(|p| (p.name(), p.index())).take(3).collect();
+	assert_eq!(pallets, vec![("System", 0), ("Scheduler", 1), ("Babe", 2)]);
+
+	assert_eq!(meta.pallet("Balances").unwrap().index(), 5);
+	assert_eq!(meta.pallet_by_index(5).unwrap().name(), "Balances");
+	assert!(meta.pallet("Wibble").is_none());
+	assert!(meta.pallet_by_index(255).is_none());
+}
+
+#[test]
+fn calls() {
+	let meta = metadata();
+	let balances = meta.pallet("Balances").unwrap();
+
+	assert!(balances.calls().any(|c| c.name() == "transfer_all"));
+
+	let transfer = balances.call("transfer").expect("call exists");
+	assert_eq!(transfer.index(), 0);
+	assert!(!transfer.docs().is_empty());
+
+	let fields: Vec<_> = transfer.fields().map(|f| (f.name(), f.type_name())).collect();
+	assert_eq!(
+		fields,
+		vec![(Some("dest"), Some("<T::Lookup as StaticLookup>::Source")), (Some("value"), Some("T::Balance"))]
+	);
+
+	// Field type IDs can be resolved:
+	let value_ty = meta.resolve(transfer.fields().nth(1).unwrap().ty()).unwrap();
+	assert!(matches!(value_ty.type_def(), TypeDef::Compact(..)));
+
+	assert!(balances.call("wibble").is_none());
+}
+
+#[test]
+fn events_and_errors() {
+	let meta = metadata();
+	let balances = meta.pallet("Balances").unwrap();
+
+	let transfer = balances.event("Transfer").expect("event exists");
+	let field_types: Vec<_> = transfer.fields().map(|f| f.type_name().unwrap()).collect();
+	assert_eq!(field_types, vec!["T::AccountId", "T::AccountId", "T::Balance"]);
+
+	let error = balances.error("InsufficientBalance").expect("error exists");
+	assert_eq!(error.index(), 2);
+	assert!(!error.docs().is_empty());
+	assert!(balances.errors().count() > 1);
+}
+
+#[test]
+fn storage_entries() {
+	let meta = metadata();
+
+	let account = meta.pallet("System").unwrap().storage_entry("Account").expect("storage entry exists");
+	assert_eq!(account.prefix(), "System");
+	assert_eq!(account.hashers(), &[StorageHasher::Blake2_128Concat]);
+	assert_eq!(account.key_types().len(), 1);
+	assert_eq!(meta.resolve(account.key_types()[0]).unwrap().path().segments(), &["sp_core", "crypto", "AccountId32"]);
+	assert_eq!(account.modifier(), &StorageEntryModifier::Default);
+	assert!(!account.default_bytes().is_empty());
+
+	let now = meta.pallet("Timestamp").unwrap().storage_entry("Now").expect("storage entry exists");
+	assert!(now.hashers().is_empty());
+	assert!(now.key_types().is_empty());
+	assert!(matches!(meta.resolve(now.value_type()).unwrap().type_def(), TypeDef::Primitive(..)));
+	assert!(!now.docs().is_empty());
+
+	// Some pallets have no storage:
+	assert_eq!(meta.pallet("Utility").unwrap().storage_entries().count(), 0);
+}
+
+#[test]
+fn constants() {
+	let meta = metadata();
+
+	let deposit = meta.pallet("Balances").unwrap().constant("ExistentialDeposit").expect("constant exists");
+	assert_eq!(deposit.pallet(), "Balances");
+	assert_eq!(meta.pallet("Balances").unwrap().constants().count(), 3);
+}
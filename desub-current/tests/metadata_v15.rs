@@ -0,0 +1,241 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::{Decode, Encode};
+use desub_current::{
+	decoder::{self, RuntimeApiDefinitions},
+	Metadata, Value,
+};
+use frame_metadata::{
+	v14::RuntimeMetadataV14,
+	v15::{
+		CustomMetadata, CustomValueMetadata, ExtrinsicMetadata, OuterEnums, PalletMetadata, RuntimeApiMetadata,
+		RuntimeApiMethodMetadata, RuntimeApiMethodParamMetadata, RuntimeMetadataV15, SignedExtensionMetadata,
+	},
+	RuntimeMetadata, RuntimeMetadataPrefixed,
+};
+use scale_info::{form::PortableForm, TypeDef, TypeDefPrimitive};
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn to_bytes(hex_str: &str) -> Vec<u8> {
+	let hex_str = hex_str.strip_prefix("0x").expect("0x should prefix hex encoded bytes");
+	hex::decode(hex_str).expect("valid bytes from hex")
+}
+
+// We don't have a V15 metadata blob to hand, so we build one from the V14 polkadot metadata,
+// adding a couple of runtime APIs and custom values.
+fn v15_metadata_bytes() -> Vec<u8> {
+	let v14: RuntimeMetadataV14 = match RuntimeMetadataPrefixed::decode(&mut &*V14_METADATA_POLKADOT_SCALE).unwrap().1 {
+		RuntimeMetadata::V14(meta) => meta,
+		_ => panic!("expected V14 metadata"),
+	};
+
+	let find_type = |f: &dyn Fn(&scale_info::Type<PortableForm>) -> bool| {
+		v14.types.types().iter().find(|t| f(t.ty())).map(|t| t.id()).expect("type exists").into()
+	};
+	let path_is = |path: &'static [&'static str]| {
+		move |ty: &scale_info::Type<PortableForm>| ty.path().segments().iter().map(|s| &**s).eq(path.iter().copied())
+	};
+	let unchecked_extrinsic = v14
+		.types
+		.types()
+		.iter()
+		.map(|t| t.ty())
+		.find(|t| t.path().segments().last().map(|s| &**s) == Some("UncheckedExtrinsic"))
+		.expect("UncheckedExtrinsic type exists");
+	let type_param = |name: &str| {
+		unchecked_extrinsic.type_params().iter().find(|p| p.name() == name).and_then(|p| p.ty()).cloned().unwrap()
+	};
+
+	let v15 = RuntimeMetadataV15 {
+		pallets: v14
+			.pallets
+			.into_iter()
+			.map(|p| PalletMetadata {
+				name: p.name,
+				storage: p.storage,
+				calls: p.calls,
+				event: p.event,
+				constants: p.constants,
+				error: p.error,
+				index: p.index,
+				docs: vec![],
+			})
+			.collect(),
+		extrinsic: ExtrinsicMetadata {
+			version: v14.extrinsic.version,
+			address_ty: type_param("Address"),
+			call_ty: type_param("Call"),
+			signature_ty: type_param("Signature"),
+			extra_ty: type_param("Extra"),
+			signed_extensions: v14
+				.extrinsic
+				.signed_extensions
+				.into_iter()
+				.map(|e| SignedExtensionMetadata {
+					identifier: e.identifier,
+					ty: e.ty,
+					additional_signed: e.additional_signed,
+				})
+				.collect(),
+		},
+		ty: v14.ty,
+		apis: vec![
+			RuntimeApiMetadata {
+				name: "Core".into(),
+				methods: vec![RuntimeApiMethodMetadata {
+					name: "version".into(),
+					inputs: vec![],
+					output: find_type(&path_is(&["sp_version", "RuntimeVersion"])),
+					docs: vec!["Returns the version of the runtime.".into()],
+				}],
+				docs: vec![],
+			},
+			RuntimeApiMetadata {
+				name: "AccountNonceApi".into(),
+				methods: vec![RuntimeApiMethodMetadata {
+					name: "account_nonce".into(),
+					inputs: vec![RuntimeApiMethodParamMetadata {
+						name: "account".into(),
+						ty: find_type(&path_is(&["sp_core", "crypto", "AccountId32"])),
+					}],
+					output: find_type(&|t| matches!(t.type_def(), TypeDef::Primitive(TypeDefPrimitive::U32))),
+					docs: vec![],
+				}],
+				docs: vec![],
+			},
+		],
+		outer_enums: OuterEnums {
+			call_enum_ty: type_param("Call"),
+			event_enum_ty: find_type(&path_is(&["polkadot_runtime", "Event"])),
+			error_enum_ty: find_type(&path_is(&["sp_runtime", "DispatchError"])),
+		},
+		custom: CustomMetadata {
+			map: [(
+				"ss58Prefix".to_string(),
+				CustomValueMetadata {
+					ty: find_type(&|t| matches!(t.type_def(), TypeDef::Primitive(TypeDefPrimitive::U16))),
+					value: 42u16.encode(),
+				},
+			)]
+			.into_iter()
+			.collect(),
+		},
+		types: v14.types,
+	};
+
+	RuntimeMetadataPrefixed::from(v15).encode()
+}
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(&v15_metadata_bytes()).expect("valid metadata")
+}
+
+#[test]
+fn can_decode_extrinsics() {
+	let meta = metadata();
+
+	// Balances.transfer (amount: 12345)
+	let ext_bytes = &mut &*to_bytes("0x31028400d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d016ada9b477ef454972200e098f1186d4a2aeee776f1f6a68609797f5ba052906ad2427bdca865442158d118e2dfc82226077e4dfdff975d005685bab66eefa38a150200000500001cbd2d43530a44705ad088af313e18f80b53ef16b36177cd4b77b846f2a5f07ce5c0");
+	let ext = decoder::decode_extrinsic(&meta, ext_bytes).expect("can decode extrinsic");
+
+	assert!(ext_bytes.is_empty(), "No more bytes expected");
+	assert_eq!(ext.call_data.pallet_name, "Balances");
+	assert_eq!(ext.call_data.ty.name(), "transfer");
+	assert_eq!(ext.signature.unwrap().address.account_id(), Some(sp_keyring::AccountKeyring::Alice.to_account_id()));
+}
+
+#[test]
+fn pallet_details_are_kept() {
+	let meta = metadata();
+
+	let existential_deposit = meta.constant("Balances", "ExistentialDeposit").expect("constant exists");
+	assert_eq!(existential_deposit.value().unwrap().without_context(), Value::u128(10_000_000_000));
+	assert_eq!(decoder::decode_module_error(&meta, 5, &[2]).unwrap().ty.name(), "InsufficientBalance");
+}
+
+#[test]
+fn runtime_apis() {
+	let meta = metadata();
+
+	let names: Vec<_> = meta.runtime_apis().iter().map(|api| &*api.name).collect();
+	assert_eq!(names, vec!["Core", "AccountNonceApi"]);
+
+	let method = meta.runtime_api_method("AccountNonceApi", "account_nonce").expect("method exists");
+	assert_eq!(method.inputs.len(), 1);
+	assert_eq!(method.inputs[0].name, "account");
+	assert!(matches!(meta.resolve(method.output).unwrap().type_def(), TypeDef::Primitive(TypeDefPrimitive::U32)));
+
+	assert!(meta.runtime_api_method("Core", "wibble").is_none());
+	assert!(meta.runtime_api_method("Wibble", "version").is_none());
+}
+
+#[test]
+fn runtime_api_definitions_from_metadata() {
+	let meta = metadata();
+	let definitions = RuntimeApiDefinitions::from_metadata(&meta);
+
+	let alice = sp_keyring::AccountKeyring::Alice.to_account_id();
+	let args_bytes = alice.encode();
+	let call =
+		decoder::decode_runtime_api_call(&meta, &definitions, "AccountNonceApi_account_nonce", &mut &*args_bytes)
+			.expect("can decode call");
+	assert_eq!(call.arguments.len(), 1);
+	assert_eq!(call.arguments[0].0, "account");
+
+	let result_bytes = 7u32.encode();
+	let result =
+		decoder::decode_runtime_api_result(&meta, &definitions, "AccountNonceApi_account_nonce", &mut &*result_bytes)
+			.expect("can decode result");
+	assert_eq!(result.without_context(), Value::u32(7));
+
+	assert!(definitions.get("Core_version").is_some());
+}
+
+#[test]
+fn outer_enums() {
+	let meta = metadata();
+
+	let outer_enums = meta.outer_enums().expect("V15 metadata has outer enums");
+	let event_enum = meta.resolve(outer_enums.event_enum_type()).unwrap();
+	assert_eq!(event_enum.path().segments(), &["polkadot_runtime", "Event"]);
+
+	// The call enum is the same type as that used in extrinsics:
+	let call_enum = meta.resolve(outer_enums.call_enum_type()).unwrap();
+	assert_eq!(call_enum.path().segments(), &["polkadot_runtime", "Call"]);
+}
+
+#[test]
+fn custom_values() {
+	let meta = metadata();
+
+	let custom = meta.custom_value("ss58Prefix").expect("custom value exists");
+	assert_eq!(custom.name(), "ss58Prefix");
+	assert_eq!(custom.value().unwrap().without_context(), Value::u16(42));
+	assert_eq!(meta.custom_values().count(), 1);
+	assert!(meta.custom_value("wibble").is_none());
+}
+
+// None of the V15 additions are available in V14 metadata.
+#[test]
+fn v14_has_no_v15_details() {
+	let meta = Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata");
+
+	assert!(meta.runtime_apis().is_empty());
+	assert!(meta.outer_enums().is_none());
+	assert_eq!(meta.custom_values().count(), 0);
+}
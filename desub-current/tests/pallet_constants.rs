@@ -0,0 +1,71 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use desub_current::{Metadata, Value};
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata")
+}
+
+#[test]
+fn balances_existential_deposit() {
+	let meta = metadata();
+
+	let constant = meta.constant("Balances", "ExistentialDeposit").expect("constant exists");
+	assert_eq!(constant.pallet(), "Balances");
+	assert_eq!(constant.name(), "ExistentialDeposit");
+	assert!(!constant.docs().is_empty());
+	assert_eq!(constant.value().unwrap().without_context(), Value::u128(10_000_000_000));
+
+	// The type ID points at the u128 type, too:
+	let ty = meta.resolve(constant.ty()).expect("type exists");
+	assert!(matches!(ty.type_def(), desub_current::scale_info::TypeDef::Primitive(..)));
+}
+
+#[test]
+fn system_ss58_prefix() {
+	let meta = metadata();
+
+	let constant = meta.constant("System", "SS58Prefix").expect("constant exists");
+	assert_eq!(constant.bytes(), &[0, 0]);
+	assert_eq!(constant.value().unwrap().without_context(), Value::u16(0));
+}
+
+#[test]
+fn list_pallet_constants() {
+	let meta = metadata();
+
+	let names: Vec<_> = meta.pallet_constants("Balances").expect("pallet exists").map(|c| c.name()).collect();
+	assert_eq!(names, vec!["ExistentialDeposit", "MaxLocks", "MaxReserves"]);
+
+	// Every constant in every pallet should decode:
+	for pallet in ["System", "Babe", "Timestamp", "Staking", "Treasury"] {
+		for constant in meta.pallet_constants(pallet).expect("pallet exists") {
+			assert!(constant.value().is_ok(), "{}.{} should decode", pallet, constant.name());
+		}
+	}
+}
+
+#[test]
+fn missing_constants() {
+	let meta = metadata();
+
+	assert!(meta.constant("Balances", "Wibble").is_none());
+	assert!(meta.constant("Wibble", "ExistentialDeposit").is_none());
+	assert!(meta.pallet_constants("Wibble").is_none());
+}
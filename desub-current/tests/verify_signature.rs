@@ -0,0 +1,217 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use desub_current::{
+	decoder::{self, ChainContext, VerifySignatureError},
+	encoder, value, Metadata, Value,
+};
+use sp_core::{blake2_256, ecdsa, ed25519, Pair};
+use sp_runtime::{traits::IdentifyAccount, AccountId32, MultiAddress, MultiSignature, MultiSigner};
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] = include_bytes!("data/v14_metadata_polkadot.scale");
+
+fn metadata() -> Metadata {
+	Metadata::from_bytes(V14_METADATA_POLKADOT_SCALE).expect("valid metadata")
+}
+
+/// A System.remark("hello") extrinsic with nonce 5 and no tip, signed by the ed25519 `//Alice` key for Polkadot
+/// (spec version 9110, transaction version 8) as an immortal transaction. The signer payload was put together
+/// by hand rather than with the encoder, so that it checks our own understanding of what gets signed.
+const SIGNED_REMARK_FIXTURE: &str = "0xb901840088dc3417d5058ec4b4503e0c12ea1a0a89be200fe98922423d4334014fa6b0ee00ff6c14eef35944f40773f52115f62069a4ce20abcf15b83a53034eb3ddce8a6cb5fd5a5bcd6ae162078f2f18fda20b0b0deec5579a7d075e732ef4c4ad85a30b00140000011468656c6c6f";
+
+fn polkadot_context() -> ChainContext {
+	let genesis_hash = hex::decode("91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3").unwrap();
+	ChainContext {
+		genesis_hash: genesis_hash.try_into().unwrap(),
+		spec_version: 9110,
+		transaction_version: 8,
+		checkpoint_hash: None,
+	}
+}
+
+fn empty_value() -> Value<()> {
+	Value::unnamed_composite(vec![])
+}
+
+fn hash_value(hash: [u8; 32]) -> Value<()> {
+	Value::unnamed_composite(hash.iter().map(|&b| Value::u8(b)).collect())
+}
+
+fn immortal() -> Value<()> {
+	Value::variant("Immortal".into(), value::Composite::Unnamed(vec![]))
+}
+
+fn mortal() -> Value<()> {
+	Value::variant("Mortal185".into(), value::Composite::Unnamed(vec![Value::u8(52)]))
+}
+
+fn context() -> ChainContext {
+	ChainContext { genesis_hash: [1; 32], spec_version: 9110, transaction_version: 8, checkpoint_hash: Some([2; 32]) }
+}
+
+/// Construct and sign a System.remark extrinsic, handing back its SCALE encoded bytes.
+fn signed_remark(
+	meta: &Metadata,
+	remark: Vec<u8>,
+	era: Value<()>,
+	context: &ChainContext,
+	address: MultiAddress<AccountId32, u32>,
+	sign: impl Fn(&[u8]) -> MultiSignature,
+) -> Vec<u8> {
+	let arguments = vec![Value::unnamed_composite(remark.into_iter().map(Value::u8).collect())];
+	let builder = encoder::build_extrinsic(meta, "System", "remark", &arguments).unwrap();
+
+	let checkpoint_hash = match &era.value {
+		desub_current::ValueDef::Variant(v) if v.name == "Immortal" => context.genesis_hash,
+		_ => context.checkpoint_hash.unwrap(),
+	};
+	let extensions = vec![
+		empty_value(),
+		empty_value(),
+		empty_value(),
+		era,
+		Value::u32(0),
+		empty_value(),
+		Value::u128(0),
+		empty_value(),
+	];
+	let additional = vec![
+		Value::u32(context.spec_version),
+		Value::u32(context.transaction_version),
+		hash_value(context.genesis_hash),
+		hash_value(checkpoint_hash),
+		empty_value(),
+		empty_value(),
+		empty_value(),
+		empty_value(),
+	];
+
+	let mut payload = builder.signer_payload(&extensions, &additional).unwrap();
+	if payload.len() > 256 {
+		payload = blake2_256(&payload).to_vec();
+	}
+	builder.signed(&address.into(), &sign(&payload).into(), &extensions).unwrap()
+}
+
+fn signed_sr25519_remark(meta: &Metadata, remark: Vec<u8>, era: Value<()>, context: &ChainContext) -> Vec<u8> {
+	let pair = sp_keyring::AccountKeyring::Alice.pair();
+	let address = MultiAddress::Id(pair.public().into());
+	signed_remark(meta, remark, era, context, address, |payload| pair.sign(payload).into())
+}
+
+fn verify(meta: &Metadata, ext_bytes: &[u8], context: &ChainContext) -> Result<bool, VerifySignatureError> {
+	let ext = decoder::decode_extrinsic(meta, &mut &*ext_bytes).expect("can decode extrinsic");
+	decoder::verify_signature(meta, &ext, context)
+}
+
+#[test]
+fn signed_extrinsic_fixture_is_valid() {
+	let meta = metadata();
+	let ext_bytes = hex::decode(SIGNED_REMARK_FIXTURE.trim_start_matches("0x")).unwrap();
+
+	assert_eq!(verify(&meta, &ext_bytes, &polkadot_context()), Ok(true));
+	assert_eq!(verify(&meta, &ext_bytes, &ChainContext { spec_version: 9111, ..polkadot_context() }), Ok(false));
+	assert_eq!(verify(&meta, &ext_bytes, &context()), Ok(false));
+}
+
+#[test]
+fn sr25519_signature_is_valid() {
+	let meta = metadata();
+	let ext_bytes = signed_sr25519_remark(&meta, b"hello".to_vec(), mortal(), &context());
+
+	assert_eq!(verify(&meta, &ext_bytes, &context()), Ok(true));
+}
+
+#[test]
+fn ed25519_signature_is_valid() {
+	let meta = metadata();
+	let pair = ed25519::Pair::from_string("//Alice", None).unwrap();
+	let address = MultiAddress::Id(AccountId32::from(pair.public().0));
+	let ext_bytes =
+		signed_remark(&meta, b"hello".to_vec(), mortal(), &context(), address, |payload| pair.sign(payload).into());
+
+	assert_eq!(verify(&meta, &ext_bytes, &context()), Ok(true));
+}
+
+#[test]
+fn ecdsa_signature_is_valid() {
+	let meta = metadata();
+	let pair = ecdsa::Pair::from_string("//Alice", None).unwrap();
+	let address = MultiAddress::Id(MultiSigner::from(pair.public()).into_account());
+	let ext_bytes =
+		signed_remark(&meta, b"hello".to_vec(), mortal(), &context(), address, |payload| pair.sign(payload).into());
+
+	assert_eq!(verify(&meta, &ext_bytes, &context()), Ok(true));
+}
+
+// Payloads over 256 bytes are hashed before being signed.
+#[test]
+fn long_payload_signature_is_valid() {
+	let meta = metadata();
+	let ext_bytes = signed_sr25519_remark(&meta, vec![1; 300], mortal(), &context());
+
+	assert_eq!(verify(&meta, &ext_bytes, &context()), Ok(true));
+}
+
+// Immortal extrinsics use the genesis hash in place of the checkpoint hash.
+#[test]
+fn immortal_signature_is_valid_without_checkpoint() {
+	let meta = metadata();
+	let context = ChainContext { checkpoint_hash: None, ..context() };
+	let ext_bytes = signed_sr25519_remark(&meta, b"hello".to_vec(), immortal(), &context);
+
+	assert_eq!(verify(&meta, &ext_bytes, &context), Ok(true));
+}
+
+#[test]
+fn mortal_signature_needs_checkpoint() {
+	let meta = metadata();
+	let ext_bytes = signed_sr25519_remark(&meta, b"hello".to_vec(), mortal(), &context());
+
+	let context = ChainContext { checkpoint_hash: None, ..context() };
+	assert_eq!(verify(&meta, &ext_bytes, &context), Err(VerifySignatureError::MissingCheckpointHash));
+}
+
+#[test]
+fn signature_for_different_chain_is_invalid() {
+	let meta = metadata();
+	let ext_bytes = signed_sr25519_remark(&meta, b"hello".to_vec(), mortal(), &context());
+
+	assert_eq!(verify(&meta, &ext_bytes, &ChainContext { genesis_hash: [3; 32], ..context() }), Ok(false));
+	assert_eq!(verify(&meta, &ext_bytes, &ChainContext { spec_version: 9111, ..context() }), Ok(false));
+	assert_eq!(verify(&meta, &ext_bytes, &ChainContext { transaction_version: 9, ..context() }), Ok(false));
+	assert_eq!(verify(&meta, &ext_bytes, &ChainContext { checkpoint_hash: Some([3; 32]), ..context() }), Ok(false));
+}
+
+#[test]
+fn signature_from_someone_else_is_invalid() {
+	let meta = metadata();
+	let alice = sp_keyring::AccountKeyring::Alice.pair();
+	let bob = sp_keyring::AccountKeyring::Bob.to_account_id();
+	let ext_bytes = signed_remark(&meta, b"hello".to_vec(), mortal(), &context(), MultiAddress::Id(bob), |payload| {
+		alice.sign(payload).into()
+	});
+
+	assert_eq!(verify(&meta, &ext_bytes, &context()), Ok(false));
+}
+
+#[test]
+fn unsigned_extrinsic_cannot_be_verified() {
+	let meta = metadata();
+	let arguments = vec![Value::u32(1), Value::u32(2), Value::u32(3), Value::u32(4), Value::u128(5)];
+	let ext_bytes = encoder::build_extrinsic(&meta, "Auctions", "bid", &arguments).unwrap().unsigned();
+	assert_eq!(verify(&meta, &ext_bytes, &context()), Err(VerifySignatureError::NotSigned));
+}
@@ -56,6 +56,9 @@ use std::{
 	sync::atomic::{AtomicUsize, Ordering},
 };
 
+/// An extrinsic which could not be decoded by [`Decoder::decode_extrinsics_resilient`].
+pub type ExtrinsicDecodeFailure = desub_common::ExtrinsicDecodeFailure<Error>;
+
 /// Decoder for substrate types
 ///
 /// hold information about the Runtime Metadata
@@ -476,6 +479,50 @@ impl Decoder {
 		Ok(ext)
 	}
 
+	/// Decode a vector of extrinsics, like [`Decoder::decode_extrinsics`], but carry on decoding after an
+	/// extrinsic fails, and return a result for each one. If the length prefix of an extrinsic is malformed,
+	/// we can't go any further, and the last failure contains all of the remaining bytes.
+	pub fn decode_extrinsics_resilient(
+		&self,
+		spec: SpecVersion,
+		data: &[u8],
+	) -> Result<Vec<Result<GenericExtrinsic, ExtrinsicDecodeFailure>>, Error> {
+		let (length, prefix) = Self::scale_length(data)?;
+		let meta = self.versions.get(&spec).ok_or(Error::MissingSpec(spec))?;
+		log::trace!("Decoding {} Total Extrinsics. CALLS: {:#?}", length, meta.modules_by_call_index);
+
+		let mut ext = Vec::new();
+		let mut state = DecodeState::new(None, None, meta, prefix, spec, data);
+		let mut cursor = prefix;
+		for index in 0..length {
+			let remaining = &data[cursor..];
+			let (len, prefix) = match Self::scale_length(remaining) {
+				Ok((len, prefix)) if prefix + len <= remaining.len() => (len, prefix),
+				Ok(_) => {
+					let error = Error::from("Extrinsic length is greater than the number of bytes remaining");
+					ext.push(Err(ExtrinsicDecodeFailure { index, bytes: remaining.to_vec(), error }));
+					break;
+				}
+				Err(error) => {
+					ext.push(Err(ExtrinsicDecodeFailure { index, bytes: remaining.to_vec(), error }));
+					break;
+				}
+			};
+			let extrinsic = &remaining[prefix..prefix + len];
+			cursor += prefix + len;
+
+			log::trace!("Extrinsic {}:{:?}", index, extrinsic);
+			state.reset(extrinsic);
+			ext.push(self.decode_extrinsic(&mut state).map_err(|error| ExtrinsicDecodeFailure {
+				index,
+				bytes: extrinsic.to_vec(),
+				error,
+			}));
+		}
+
+		Ok(ext)
+	}
+
 	/// Decode an extrinsic
 	fn decode_extrinsic(&self, state: &mut DecodeState) -> Result<GenericExtrinsic, Error> {
 		let signature = if state.interpret_version() { Some(self.decode_signature(state)?) } else { None };
@@ -1036,6 +1083,47 @@ mod tests {
 		assert_eq!(Some(&meta), _other_meta.clone())
 	}
 
+	// The number of extrinsics is read from the input, and shouldn't be trusted.
+	#[test]
+	fn should_decode_extrinsics_resiliently_with_huge_length() {
+		let mut decoder = Decoder::new(GenericTypes, Chain::Kusama);
+		decoder.register_version(0, meta_test_suite::test_metadata()).unwrap();
+
+		// A compact encoded length of u32::MAX, and then no extrinsics:
+		let data = [0x03, 0xff, 0xff, 0xff, 0xff];
+		let extrinsics = decoder.decode_extrinsics_resilient(0, &data).unwrap();
+		assert_eq!(extrinsics.len(), 1);
+		assert!(extrinsics[0].as_ref().unwrap_err().bytes.is_empty());
+	}
+
+	#[test]
+	fn should_decode_blake2_128_concat_storage_keys() {
+		let v10: &[u8] = include_bytes!("../../integration-tests/data/metadata_v10.bin");
+		let v11: &[u8] = include_bytes!("../../integration-tests/data/metadata_v11.bin");
+		let v12: &[u8] = include_bytes!("../../integration-tests/data/metadata_v12_block4643974.bin");
+
+		// Each of these storage entries has a `Blake2_128Concat` hasher.
+		let entries =
+			[(10, v10, "Utility", "Multisigs"), (11, v11, "System", "Account"), (12, v12, "System", "Account")];
+		for (spec, meta, module, entry) in entries {
+			let mut decoder = Decoder::new(GenericTypes, Chain::Kusama);
+			decoder.register_version(spec, Metadata::new(meta).unwrap()).unwrap();
+
+			let mut storage_key = sp_core::twox_128(module.as_bytes()).to_vec();
+			storage_key.extend(sp_core::twox_128(entry.as_bytes()));
+			storage_key.extend([0; 64]);
+			let storage = decoder.decode_storage(spec, (&storage_key, None::<Vec<u8>>)).unwrap();
+			let hashers = match &storage.key().extra {
+				Some(StorageKeyData::Map { hasher, .. }) => vec![hasher.clone()],
+				Some(StorageKeyData::DoubleMap { hasher, key2_hasher, .. }) => {
+					vec![hasher.clone(), key2_hasher.clone()]
+				}
+				None => Vec::new(),
+			};
+			assert!(hashers.contains(&StorageHasher::Blake2_128Concat), "{}.{}: {:?}", module, entry, hashers);
+		}
+	}
+
 	#[test]
 	fn should_get_scale_length() {
 		let encoded = vec![32, 4].encode();
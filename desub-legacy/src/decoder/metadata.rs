@@ -151,6 +151,7 @@ impl<'a> Metadata {
 
 	pub fn from_runtime_metadata(metadata: RuntimeMetadata) -> Result<Self, Error> {
 		match metadata {
+			RuntimeMetadata::V8(meta) => Ok(meta.try_into()?),
 			RuntimeMetadata::V9(meta) => Ok(meta.try_into()?),
 			RuntimeMetadata::V10(meta) => Ok(meta.try_into()?),
 			RuntimeMetadata::V11(meta) => Ok(meta.try_into()?),
@@ -317,6 +318,11 @@ impl ModuleMetadata {
 		&self.name
 	}
 
+	/// index of the module in the runtime
+	pub fn index(&self) -> u8 {
+		self.index
+	}
+
 	/// Return a storage entry by its key
 	pub fn storage(&self, key: &'static str) -> Result<&StorageMetadata, Error> {
 		self.storage.get(key).ok_or(Error::StorageNotFound(key))
@@ -327,6 +333,11 @@ impl ModuleMetadata {
 		self.events.values()
 	}
 
+	/// an iterator over all possible events for this module, along with their index in the module
+	pub fn events_with_index(&self) -> impl Iterator<Item = (u8, &ModuleEventMetadata)> {
+		self.events.iter().map(|(index, event)| (*index, event))
+	}
+
 	// TODO Transfer to Subxt
 	/// iterator over all possible calls in this module
 	pub fn calls(&self) -> impl Iterator<Item = &CallMetadata> {
@@ -367,6 +378,10 @@ impl CallMetadata {
 	pub fn name(&self) -> String {
 		self.name.clone()
 	}
+	/// encoded byte index of the call within its module
+	pub fn index(&self) -> u8 {
+		self.index
+	}
 }
 
 impl fmt::Display for CallMetadata {
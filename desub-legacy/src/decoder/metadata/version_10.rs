@@ -138,7 +138,7 @@ impl From<TempStorageHasher> for DesubStorageHasher {
 		let hasher = hasher.0;
 		match hasher {
 			StorageHasher::Blake2_128 => DesubStorageHasher::Blake2_128,
-			StorageHasher::Blake2_128Concat => DesubStorageHasher::Blake2_128,
+			StorageHasher::Blake2_128Concat => DesubStorageHasher::Blake2_128Concat,
 			StorageHasher::Blake2_256 => DesubStorageHasher::Blake2_256,
 			StorageHasher::Twox128 => DesubStorageHasher::Twox128,
 			StorageHasher::Twox256 => DesubStorageHasher::Twox256,
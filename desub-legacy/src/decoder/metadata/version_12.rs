@@ -167,7 +167,7 @@ impl From<TempStorageHasher> for DesubStorageHasher {
 		let hasher = hasher.0;
 		match hasher {
 			StorageHasherv12::Blake2_128 => DesubStorageHasher::Blake2_128,
-			StorageHasherv12::Blake2_128Concat => DesubStorageHasher::Blake2_128,
+			StorageHasherv12::Blake2_128Concat => DesubStorageHasher::Blake2_128Concat,
 			StorageHasherv12::Blake2_256 => DesubStorageHasher::Blake2_256,
 			StorageHasherv12::Twox128 => DesubStorageHasher::Twox128,
 			StorageHasherv12::Twox256 => DesubStorageHasher::Twox256,
@@ -168,7 +168,7 @@ impl From<TempStorageHasher> for DesubStorageHasher {
 		let hasher = hasher.0;
 		match hasher {
 			StorageHasherV13::Blake2_128 => DesubStorageHasher::Blake2_128,
-			StorageHasherV13::Blake2_128Concat => DesubStorageHasher::Blake2_128,
+			StorageHasherV13::Blake2_128Concat => DesubStorageHasher::Blake2_128Concat,
 			StorageHasherV13::Blake2_256 => DesubStorageHasher::Blake2_256,
 			StorageHasherV13::Twox128 => DesubStorageHasher::Twox128,
 			StorageHasherV13::Twox256 => DesubStorageHasher::Twox256,
@@ -0,0 +1,638 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compare the metadata of two runtime versions, to find out which calls, events, storage entries and
+//! signed extensions changed between them.
+//!
+//! Types in V14+ metadata are compared structurally, since type IDs aren't stable between runtime versions;
+//! a type is unchanged if it would be encoded and decoded in the same way, whatever its name or ID, or the
+//! names of its fields. Legacy (V8-V13) metadata only describes types by name, and so types are compared by
+//! name there. Types (and so the arguments of calls and events) can't be compared between legacy and V14+
+//! metadata at all, and so only the rest is compared in that case. Legacy metadata also doesn't record the
+//! order of event arguments, so only the set of argument types is compared.
+
+use desub_common::{PathSegment, ValuePath};
+use desub_current::{
+	metadata::primitive_name,
+	scale_info::{form::PortableForm, Field, TypeDef, TypeDefTuple, Variant},
+	Metadata as DesubMetadata, TypeId,
+};
+use desub_legacy::decoder::{
+	metadata::{EventArg, StorageType},
+	Metadata as LegacyDesubMetadata,
+};
+use serde::Serialize;
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt,
+};
+
+/// The differences between the metadata of two runtime versions. See [`crate::diff_metadata`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MetadataDiff {
+	/// Each of the changes found. Pallets are compared in order of name, and changes within a pallet are
+	/// given in the order calls, events, storage entries, with each sorted by name.
+	pub changes: Vec<MetadataChange>,
+}
+
+impl MetadataDiff {
+	/// Is the metadata the same in every way that we compare?
+	pub fn is_empty(&self) -> bool {
+		self.changes.is_empty()
+	}
+}
+
+impl fmt::Display for MetadataDiff {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for change in &self.changes {
+			writeln!(f, "{}", change)?;
+		}
+		Ok(())
+	}
+}
+
+/// A single change between the metadata of two runtime versions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum MetadataChange {
+	PalletAdded { pallet: String },
+	PalletRemoved { pallet: String },
+	PalletIndexChanged { pallet: String, old: u8, new: u8 },
+	Call { pallet: String, call: String, change: ItemChange },
+	Event { pallet: String, event: String, change: ItemChange },
+	Storage { pallet: String, entry: String, change: StorageChange },
+	SignedExtension { name: String, change: ItemChange },
+}
+
+impl fmt::Display for MetadataChange {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MetadataChange::PalletAdded { pallet } => write!(f, "pallet `{}` was added", pallet),
+			MetadataChange::PalletRemoved { pallet } => write!(f, "pallet `{}` was removed", pallet),
+			MetadataChange::PalletIndexChanged { pallet, old, new } => {
+				write!(f, "pallet `{}` index changed from {} to {}", pallet, old, new)
+			}
+			MetadataChange::Call { pallet, call, change } => write!(f, "call `{}.{}` {}", pallet, call, change),
+			MetadataChange::Event { pallet, event, change } => write!(f, "event `{}.{}` {}", pallet, event, change),
+			MetadataChange::Storage { pallet, entry, change } => {
+				write!(f, "storage entry `{}.{}` {}", pallet, entry, change)
+			}
+			MetadataChange::SignedExtension { name, change } => write!(f, "signed extension `{}` {}", name, change),
+		}
+	}
+}
+
+/// How a call, event or signed extension changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ItemChange {
+	Added,
+	Removed,
+	/// The index changed. For signed extensions, this is the position in the list of extensions.
+	IndexChanged {
+		old: u8,
+		new: u8,
+	},
+	/// The types of the arguments changed. For signed extensions, these are the type of the extension
+	/// followed by the type of its additional signed data.
+	TypesChanged(TypeDifference),
+}
+
+impl fmt::Display for ItemChange {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ItemChange::Added => write!(f, "was added"),
+			ItemChange::Removed => write!(f, "was removed"),
+			ItemChange::IndexChanged { old, new } => write!(f, "index changed from {} to {}", old, new),
+			ItemChange::TypesChanged(difference) => write!(f, "types changed: {}", difference),
+		}
+	}
+}
+
+/// How a storage entry changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum StorageChange {
+	Added,
+	Removed,
+	HashersChanged { old: Vec<String>, new: Vec<String> },
+	KeysChanged(TypeDifference),
+	ValueChanged(TypeDifference),
+}
+
+impl fmt::Display for StorageChange {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			StorageChange::Added => write!(f, "was added"),
+			StorageChange::Removed => write!(f, "was removed"),
+			StorageChange::HashersChanged { old, new } => {
+				write!(f, "hashers changed from [{}] to [{}]", old.join(", "), new.join(", "))
+			}
+			StorageChange::KeysChanged(difference) => write!(f, "key types changed: {}", difference),
+			StorageChange::ValueChanged(difference) => write!(f, "value type changed: {}", difference),
+		}
+	}
+}
+
+/// The first difference found between two types, and where in the type it was found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeDifference {
+	/// The path to the difference, made up of field names, variant names and indexes. This is empty
+	/// if the types differ at the top level.
+	pub path: ValuePath,
+	/// What the difference is.
+	pub reason: String,
+}
+
+impl TypeDifference {
+	fn new(reason: impl Into<String>) -> Self {
+		TypeDifference { path: ValuePath::new(), reason: reason.into() }
+	}
+
+	// Differences are found in the innermost type first, so each step back
+	// up prepends to the path.
+	fn at(mut self, segment: impl Into<PathSegment>) -> Self {
+		self.path.prepend(segment);
+		self
+	}
+}
+
+impl fmt::Display for TypeDifference {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.path.is_empty() {
+			write!(f, "{}", self.reason)
+		} else {
+			write!(f, "`{}` {}", self.path, self.reason)
+		}
+	}
+}
+
+/// Metadata for some runtime version, in whichever format it came in.
+#[derive(Clone, Copy)]
+pub(crate) enum MetadataRef<'a> {
+	Current(&'a DesubMetadata),
+	Legacy(&'a LegacyDesubMetadata),
+}
+
+/// Compare two versions of some metadata.
+pub(crate) fn diff(old: MetadataRef<'_>, new: MetadataRef<'_>) -> MetadataDiff {
+	let (old_view, new_view) = (MetadataView::new(old), MetadataView::new(new));
+	let mut diff = Diff { types: TypeComparison::new(old, new), changes: Vec::new() };
+
+	for (name, old_pallet) in &old_view.pallets {
+		match new_view.pallets.get(name) {
+			Some(new_pallet) => diff.pallets(name, old_pallet, new_pallet),
+			None => diff.changes.push(MetadataChange::PalletRemoved { pallet: name.clone() }),
+		}
+	}
+	for name in new_view.pallets.keys().filter(|name| !old_view.pallets.contains_key(*name)) {
+		diff.changes.push(MetadataChange::PalletAdded { pallet: name.clone() });
+	}
+
+	let extension = |name: &str, change| MetadataChange::SignedExtension { name: name.to_owned(), change };
+	let changes = diff.items(&old_view.signed_extensions, &new_view.signed_extensions);
+	diff.changes.extend(changes.into_iter().map(|(name, change)| extension(name, change)));
+
+	MetadataDiff { changes: diff.changes }
+}
+
+struct Diff<'a> {
+	types: TypeComparison<'a>,
+	changes: Vec<MetadataChange>,
+}
+
+impl<'a> Diff<'a> {
+	fn pallets(&mut self, name: &str, old: &PalletView, new: &PalletView) {
+		let pallet = || name.to_owned();
+		if old.index != new.index {
+			self.changes.push(MetadataChange::PalletIndexChanged { pallet: pallet(), old: old.index, new: new.index });
+		}
+		for (call, change) in self.items(&old.calls, &new.calls) {
+			self.changes.push(MetadataChange::Call { pallet: pallet(), call: call.to_owned(), change });
+		}
+		for (event, change) in self.items(&old.events, &new.events) {
+			self.changes.push(MetadataChange::Event { pallet: pallet(), event: event.to_owned(), change });
+		}
+		for (entry, change) in self.storage(&old.storage, &new.storage) {
+			self.changes.push(MetadataChange::Storage { pallet: pallet(), entry: entry.to_owned(), change });
+		}
+	}
+
+	fn items<'b>(
+		&mut self,
+		old: &'b BTreeMap<String, ItemView>,
+		new: &'b BTreeMap<String, ItemView>,
+	) -> Vec<(&'b str, ItemChange)> {
+		let mut changes = Vec::new();
+		for (name, old_item) in old {
+			let new_item = match new.get(name) {
+				Some(new_item) => new_item,
+				None => {
+					changes.push((name.as_str(), ItemChange::Removed));
+					continue;
+				}
+			};
+			if old_item.index != new_item.index {
+				changes.push((name.as_str(), ItemChange::IndexChanged { old: old_item.index, new: new_item.index }));
+			}
+			if let Some(difference) = self.types.fields(&old_item.fields, &new_item.fields) {
+				changes.push((name.as_str(), ItemChange::TypesChanged(difference)));
+			}
+		}
+		for name in new.keys().filter(|name| !old.contains_key(*name)) {
+			changes.push((name.as_str(), ItemChange::Added));
+		}
+		changes
+	}
+
+	fn storage<'b>(
+		&mut self,
+		old: &'b BTreeMap<String, StorageView>,
+		new: &'b BTreeMap<String, StorageView>,
+	) -> Vec<(&'b str, StorageChange)> {
+		let mut changes = Vec::new();
+		for (name, old_entry) in old {
+			let new_entry = match new.get(name) {
+				Some(new_entry) => new_entry,
+				None => {
+					changes.push((name.as_str(), StorageChange::Removed));
+					continue;
+				}
+			};
+			if old_entry.hashers != new_entry.hashers {
+				let (old, new) = (old_entry.hashers.clone(), new_entry.hashers.clone());
+				changes.push((name.as_str(), StorageChange::HashersChanged { old, new }));
+			}
+			if let Some(difference) = self.types.fields(&old_entry.keys, &new_entry.keys) {
+				changes.push((name.as_str(), StorageChange::KeysChanged(difference)));
+			}
+			if let Some(difference) = self.types.compare(&old_entry.value, &new_entry.value) {
+				changes.push((name.as_str(), StorageChange::ValueChanged(difference)));
+			}
+		}
+		for name in new.keys().filter(|name| !old.contains_key(*name)) {
+			changes.push((name.as_str(), StorageChange::Added));
+		}
+		changes
+	}
+}
+
+/// The parts of some metadata that we compare, in the same shape whichever version of metadata it came from.
+struct MetadataView {
+	pallets: BTreeMap<String, PalletView>,
+	signed_extensions: BTreeMap<String, ItemView>,
+}
+
+struct PalletView {
+	index: u8,
+	calls: BTreeMap<String, ItemView>,
+	events: BTreeMap<String, ItemView>,
+	storage: BTreeMap<String, StorageView>,
+}
+
+struct ItemView {
+	index: u8,
+	fields: Vec<FieldView>,
+}
+
+struct StorageView {
+	hashers: Vec<String>,
+	keys: Vec<FieldView>,
+	value: Ty,
+}
+
+struct FieldView {
+	name: Option<String>,
+	ty: Ty,
+}
+
+/// A type from either V14+ metadata, which we can look up in the type registry, or legacy metadata,
+/// where all we have is its name.
+enum Ty {
+	Current(TypeId),
+	Legacy(String),
+}
+
+impl MetadataView {
+	fn new(metadata: MetadataRef<'_>) -> Self {
+		match metadata {
+			MetadataRef::Current(metadata) => Self::from_current(metadata),
+			MetadataRef::Legacy(metadata) => Self::from_legacy(metadata),
+		}
+	}
+
+	fn from_current(metadata: &DesubMetadata) -> Self {
+		let item = |index: u8, fields: Vec<FieldView>| ItemView { index, fields };
+		let fields = |variant: desub_current::metadata::VariantMetadata| {
+			let fields =
+				variant.fields().map(|f| FieldView { name: f.name().map(Into::into), ty: Ty::Current(f.ty()) });
+			item(variant.index(), fields.collect())
+		};
+
+		let pallets = metadata
+			.pallets()
+			.map(|pallet| {
+				let calls = pallet.calls().map(|call| (call.name().to_owned(), fields(call))).collect();
+				let events = pallet.events().map(|event| (event.name().to_owned(), fields(event))).collect();
+				let storage = pallet
+					.storage_entries()
+					.map(|entry| {
+						let view = StorageView {
+							hashers: entry.hashers().iter().map(|h| format!("{:?}", h)).collect(),
+							keys: entry.key_types().into_iter().map(unnamed_current).collect(),
+							value: Ty::Current(entry.value_type()),
+						};
+						(entry.name().to_owned(), view)
+					})
+					.collect();
+				(pallet.name().to_owned(), PalletView { index: pallet.index(), calls, events, storage })
+			})
+			.collect();
+
+		let signed_extensions = metadata
+			.extrinsic()
+			.signed_extensions()
+			.iter()
+			.enumerate()
+			.map(|(index, ext)| {
+				let field = |name: &str, ty| FieldView { name: Some(name.to_owned()), ty: Ty::Current(ty) };
+				let fields =
+					vec![field("ty", (&ext.ty).into()), field("additional_signed", (&ext.additional_signed).into())];
+				(ext.identifier.clone(), item(index as u8, fields))
+			})
+			.collect();
+
+		MetadataView { pallets, signed_extensions }
+	}
+
+	fn from_legacy(metadata: &LegacyDesubMetadata) -> Self {
+		let pallets = metadata
+			.modules()
+			.map(|module| {
+				let calls = module
+					.calls()
+					.map(|call| {
+						let fields = call
+							.arguments()
+							.map(|arg| FieldView { name: Some(arg.name.clone()), ty: Ty::Legacy(arg.ty.to_string()) })
+							.collect();
+						(call.name(), ItemView { index: call.index(), fields })
+					})
+					.collect();
+				let events = module
+					.events_with_index()
+					.map(|(index, event)| {
+						// The order of event arguments isn't known, so we sort them to compare them as a set.
+						let mut arguments: Vec<_> = event.arguments().iter().map(event_arg_name).collect();
+						arguments.sort();
+						let fields = arguments.into_iter().map(unnamed_legacy).collect();
+						(event.name.clone(), ItemView { index, fields })
+					})
+					.collect();
+				let storage = module
+					.storage_keys()
+					.map(|(name, entry)| {
+						let (hashers, keys, value) = match &entry.ty {
+							StorageType::Plain(value) => (vec![], vec![], value),
+							StorageType::Map { hasher, key, value, .. } => (vec![hasher], vec![key], value),
+							StorageType::DoubleMap { hasher, key1, key2, value, key2_hasher } => {
+								(vec![hasher, key2_hasher], vec![key1, key2], value)
+							}
+							StorageType::NMap { keys, hashers, value } => {
+								(hashers.iter().collect(), keys.iter().collect(), value)
+							}
+						};
+						let view = StorageView {
+							hashers: hashers.into_iter().map(|h| format!("{:?}", h)).collect(),
+							keys: keys.into_iter().map(|k| unnamed_legacy(k.to_string())).collect(),
+							value: Ty::Legacy(value.to_string()),
+						};
+						(name.clone(), view)
+					})
+					.collect();
+				(module.name().to_owned(), PalletView { index: module.index(), calls, events, storage })
+			})
+			.collect();
+
+		let signed_extensions = metadata
+			.signed_extensions()
+			.unwrap_or_default()
+			.iter()
+			.enumerate()
+			.map(|(index, ext)| (ext.to_string(), ItemView { index: index as u8, fields: Vec::new() }))
+			.collect();
+
+		MetadataView { pallets, signed_extensions }
+	}
+}
+
+fn unnamed_current(ty: TypeId) -> FieldView {
+	FieldView { name: None, ty: Ty::Current(ty) }
+}
+
+fn unnamed_legacy(ty: String) -> FieldView {
+	FieldView { name: None, ty: Ty::Legacy(ty) }
+}
+
+fn event_arg_name(arg: &EventArg) -> String {
+	match arg {
+		EventArg::Primitive(name) => name.clone(),
+		EventArg::Vec(arg) => format!("Vec<{}>", event_arg_name(arg)),
+		EventArg::Tuple(args) => format!("({})", args.iter().map(event_arg_name).collect::<Vec<_>>().join(", ")),
+	}
+}
+
+/// Compares types from two versions of some metadata.
+struct TypeComparison<'a> {
+	old: Option<&'a DesubMetadata>,
+	new: Option<&'a DesubMetadata>,
+	/// Pairs of types that we've finished comparing.
+	done: HashMap<(TypeId, TypeId), Option<TypeDifference>>,
+	/// Pairs of types that we're in the middle of comparing, and how deep in the comparison we are.
+	in_progress: HashMap<(TypeId, TypeId), usize>,
+	/// The shallowest depth of any pair of types in progress that we've assumed are the same.
+	assumed: usize,
+}
+
+impl<'a> TypeComparison<'a> {
+	fn new(old: MetadataRef<'a>, new: MetadataRef<'a>) -> Self {
+		let types = |metadata: MetadataRef<'a>| match metadata {
+			MetadataRef::Current(metadata) => Some(metadata),
+			MetadataRef::Legacy(_) => None,
+		};
+		TypeComparison {
+			old: types(old),
+			new: types(new),
+			done: HashMap::new(),
+			in_progress: HashMap::new(),
+			assumed: usize::MAX,
+		}
+	}
+
+	/// Compare two types, returning the first difference found, if any.
+	fn compare(&mut self, old: &Ty, new: &Ty) -> Option<TypeDifference> {
+		match (old, new) {
+			(Ty::Current(old), Ty::Current(new)) => self.compare_ids(*old, *new),
+			(Ty::Legacy(old), Ty::Legacy(new)) if old != new => {
+				Some(TypeDifference::new(format!("changed from `{}` to `{}`", old, new)))
+			}
+			// Legacy types are only known by name, and so can't be compared with V14+ types.
+			_ => None,
+		}
+	}
+
+	fn fields(&mut self, old: &[FieldView], new: &[FieldView]) -> Option<TypeDifference> {
+		// Legacy arguments are unnamed and (for events) unordered, so they can't be lined up with V14+ fields.
+		if self.old.is_some() != self.new.is_some() {
+			return None;
+		}
+		if old.len() != new.len() {
+			return Some(TypeDifference::new(format!("number of fields changed from {} to {}", old.len(), new.len())));
+		}
+		// Fields are encoded in order and without their names, so they are lined up by position, and a
+		// renamed field isn't a change.
+		let lone = old.len() == 1;
+		for (idx, (old, new)) in old.iter().zip(new).enumerate() {
+			match (self.compare(&old.ty, &new.ty), &old.name) {
+				// A lone unnamed field (as in a newtype) adds nothing useful to the path.
+				(Some(difference), None) if lone => return Some(difference),
+				(Some(difference), Some(name)) => return Some(difference.at(name)),
+				(Some(difference), None) => return Some(difference.at(idx)),
+				(None, _) => {}
+			}
+		}
+		None
+	}
+
+	fn compare_ids(&mut self, old: TypeId, new: TypeId) -> Option<TypeDifference> {
+		if let Some(result) = self.done.get(&(old, new)) {
+			return result.clone();
+		}
+
+		// Types can refer to themselves. If we're already comparing this pair, we assume they are the same;
+		// if they aren't, the comparison in progress will find out.
+		let depth = self.in_progress.len();
+		if let Some(&assumed_at) = self.in_progress.get(&(old, new)) {
+			self.assumed = self.assumed.min(assumed_at);
+			return None;
+		}
+
+		self.in_progress.insert((old, new), depth);
+		let outer_assumed = std::mem::replace(&mut self.assumed, usize::MAX);
+		let result = self.compare_defs(old, new);
+		self.in_progress.remove(&(old, new));
+
+		// A difference is always a difference, but a lack of one can depend on types further up being the
+		// same, which we don't know yet. We only remember those results once we've finished with those types.
+		if result.is_some() || self.assumed >= depth {
+			self.done.insert((old, new), result.clone());
+		}
+		let inner_assumed = if self.assumed >= depth { usize::MAX } else { self.assumed };
+		self.assumed = outer_assumed.min(inner_assumed);
+
+		result
+	}
+
+	fn compare_defs(&mut self, old: TypeId, new: TypeId) -> Option<TypeDifference> {
+		let old_ty = self.old.and_then(|metadata| metadata.resolve(old));
+		let new_ty = self.new.and_then(|metadata| metadata.resolve(new));
+		let (old_ty, new_ty) = match (old_ty, new_ty) {
+			(Some(old_ty), Some(new_ty)) => (old_ty, new_ty),
+			_ => return Some(TypeDifference::new("type not found in the registry")),
+		};
+
+		match (old_ty.type_def(), new_ty.type_def()) {
+			(TypeDef::Composite(old), TypeDef::Composite(new)) => {
+				self.fields(&current_fields(old.fields()), &current_fields(new.fields()))
+			}
+			(TypeDef::Variant(old), TypeDef::Variant(new)) => self.variants(old.variants(), new.variants()),
+			(TypeDef::Sequence(old), TypeDef::Sequence(new)) => {
+				self.compare_ids(old.type_param().into(), new.type_param().into())
+			}
+			(TypeDef::Array(old), TypeDef::Array(new)) if old.len() != new.len() => {
+				Some(TypeDifference::new(format!("array length changed from {} to {}", old.len(), new.len())))
+			}
+			(TypeDef::Array(old), TypeDef::Array(new)) => {
+				self.compare_ids(old.type_param().into(), new.type_param().into())
+			}
+			(TypeDef::Tuple(old), TypeDef::Tuple(new)) => self.fields(&tuple_fields(old), &tuple_fields(new)),
+			(TypeDef::Primitive(old), TypeDef::Primitive(new)) if old == new => None,
+			(TypeDef::Compact(old), TypeDef::Compact(new)) => {
+				self.compare_ids(old.type_param().into(), new.type_param().into())
+			}
+			(TypeDef::BitSequence(old), TypeDef::BitSequence(new)) => self
+				.compare_ids(old.bit_store_type().into(), new.bit_store_type().into())
+				.or_else(|| self.bit_orders(old.bit_order_type().into(), new.bit_order_type().into())),
+			(old, new) => {
+				Some(TypeDifference::new(format!("changed from {} to {}", describe_def(old), describe_def(new))))
+			}
+		}
+	}
+
+	// Bit order types (`Lsb0` and `Msb0`) have no fields, and so can only be told apart by name.
+	fn bit_orders(&self, old: TypeId, new: TypeId) -> Option<TypeDifference> {
+		let name = |metadata: Option<&'a DesubMetadata>, id: TypeId| {
+			metadata.and_then(|metadata| metadata.resolve(id)).and_then(|ty| ty.path().segments().last())
+		};
+		match (name(self.old, old), name(self.new, new)) {
+			(Some(old), Some(new)) if old == new => None,
+			(Some(old), Some(new)) => Some(TypeDifference::new(format!("bit order changed from {} to {}", old, new))),
+			_ => Some(TypeDifference::new("type not found in the registry")),
+		}
+	}
+
+	fn variants(&mut self, old: &[Variant<PortableForm>], new: &[Variant<PortableForm>]) -> Option<TypeDifference> {
+		for old_variant in old {
+			let name = old_variant.name();
+			let new_variant = match new.iter().find(|v| v.name() == name) {
+				Some(new_variant) => new_variant,
+				None => return Some(TypeDifference::new(format!("variant `{}` was removed", name))),
+			};
+			if old_variant.index() != new_variant.index() {
+				return Some(TypeDifference::new(format!(
+					"variant `{}` index changed from {} to {}",
+					name,
+					old_variant.index(),
+					new_variant.index()
+				)));
+			}
+			let (old_fields, new_fields) = (current_fields(old_variant.fields()), current_fields(new_variant.fields()));
+			if let Some(difference) = self.fields(&old_fields, &new_fields) {
+				return Some(difference.at(name));
+			}
+		}
+		new.iter()
+			.find(|new_variant| !old.iter().any(|v| v.name() == new_variant.name()))
+			.map(|new_variant| TypeDifference::new(format!("variant `{}` was added", new_variant.name())))
+	}
+}
+
+fn current_fields(fields: &[Field<PortableForm>]) -> Vec<FieldView> {
+	fields.iter().map(|f| FieldView { name: f.name().cloned(), ty: Ty::Current(f.ty().into()) }).collect()
+}
+
+fn tuple_fields(tuple: &TypeDefTuple<PortableForm>) -> Vec<FieldView> {
+	tuple.fields().iter().map(|ty| unnamed_current(ty.into())).collect()
+}
+
+fn describe_def(def: &TypeDef<PortableForm>) -> &'static str {
+	match def {
+		TypeDef::Composite(_) => "a composite",
+		TypeDef::Variant(_) => "a variant",
+		TypeDef::Sequence(_) => "a sequence",
+		TypeDef::Array(_) => "an array",
+		TypeDef::Tuple(_) => "a tuple",
+		TypeDef::Compact(_) => "a compact",
+		TypeDef::BitSequence(_) => "a bit sequence",
+		TypeDef::Primitive(primitive) => primitive_name(primitive),
+	}
+}
@@ -29,6 +29,10 @@ pub enum Error {
 		source: DecodeError,
 		ext: Vec<Extrinsic<'static>>,
 	},
+	#[error("Decoding extrinsics failed {0}")]
+	Extrinsics(#[source] DecodeError),
+	#[error("Decoding block failed {0}")]
+	Block(#[source] DecodeError),
 	#[error(transparent)]
 	Legacy(#[from] LegacyError),
 	#[error(transparent)]
@@ -42,3 +46,6 @@ pub enum Error {
 	#[error(transparent)]
 	Serialization(#[from] serde_json::Error),
 }
+
+/// An extrinsic which could not be decoded by [`crate::Decoder::decode_extrinsics_resilient`].
+pub type ExtrinsicDecodeFailure = desub_common::ExtrinsicDecodeFailure<Error>;
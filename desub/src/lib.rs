@@ -16,18 +16,21 @@
 //! Facade crate for decoding data that uses any version of metadata (V8+)
 
 #![forbid(unsafe_code)]
+mod diff;
 #[deny(unused)]
 mod error;
 
 use codec::Decode;
 use desub_current::{
-	decoder::{self, Extrinsic},
+	decoder::{self, AccountFormat, Extrinsic},
+	value::WithProfile,
 	Metadata as DesubMetadata,
 };
 use desub_legacy::{
 	decoder::{Decoder as LegacyDecoder, Metadata as LegacyDesubMetadata},
 	RustTypeMarker, TypeDetective,
 };
+use diff::MetadataRef;
 use frame_metadata::RuntimeMetadataPrefixed;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -35,8 +38,10 @@ use std::collections::HashMap;
 #[cfg(feature = "polkadot-js")]
 use desub_json_resolver::TypeResolver as PolkadotJsResolver;
 
-pub use self::error::Error;
+pub use self::diff::{ItemChange, MetadataChange, MetadataDiff, StorageChange, TypeDifference};
+pub use self::error::{Error, ExtrinsicDecodeFailure};
 pub use desub_common::SpecVersion;
+pub use desub_current::value::SerializeProfile;
 #[cfg(feature = "polkadot-js")]
 pub use desub_json_resolver::runtimes;
 pub use desub_legacy::decoder::Chain;
@@ -61,9 +66,22 @@ impl TypeDetective for NoLegacyTypes {
 	}
 }
 
+/// How account IDs are rendered in the JSON output for runtimes with V14+ metadata.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AccountIdFormat {
+	/// Render account IDs as they are decoded, which is usually as arrays of bytes.
+	#[default]
+	Bytes,
+	/// Render 32 byte account IDs as SS58 addresses, and 20 byte account IDs as EIP-55 checksummed
+	/// hex strings. If no SS58 prefix is given, the `System.SS58Prefix` constant is used.
+	Rendered { ss58_prefix: Option<u16> },
+}
+
 pub struct Decoder {
 	legacy_decoder: LegacyDecoder,
 	current_metadata: HashMap<SpecVersion, DesubMetadata>,
+	account_id_format: AccountIdFormat,
+	serialize_profile: SerializeProfile,
 }
 
 impl Decoder {
@@ -72,7 +90,12 @@ impl Decoder {
 		let legacy_decoder = LegacyDecoder::new(PolkadotJsResolver::default(), chain);
 		let current_metadata = HashMap::new();
 
-		Self { legacy_decoder, current_metadata }
+		Self {
+			legacy_decoder,
+			current_metadata,
+			account_id_format: AccountIdFormat::default(),
+			serialize_profile: SerializeProfile::default(),
+		}
 	}
 
 	#[cfg(not(feature = "polkadot-js"))]
@@ -80,33 +103,63 @@ impl Decoder {
 		let legacy_decoder = LegacyDecoder::new(NoLegacyTypes, Chain::Custom("none".to_string()));
 		let current_metadata = HashMap::new();
 
-		Self { legacy_decoder, current_metadata }
+		Self {
+			legacy_decoder,
+			current_metadata,
+			account_id_format: AccountIdFormat::default(),
+			serialize_profile: SerializeProfile::default(),
+		}
 	}
 
 	/// Create a new general Decoder
 	pub fn with_custom_types(types: impl TypeDetective + 'static, chain: Chain) -> Self {
 		let legacy_decoder = LegacyDecoder::new(types, chain);
 		let current_decoder = HashMap::new();
-		Self { legacy_decoder, current_metadata: current_decoder }
+		Self {
+			legacy_decoder,
+			current_metadata: current_decoder,
+			account_id_format: AccountIdFormat::default(),
+			serialize_profile: SerializeProfile::default(),
+		}
 	}
 
-	/// Register a runtime version with the decoder.
-	pub fn register_version(&mut self, version: SpecVersion, mut metadata: &[u8]) -> Result<(), Error> {
-		let metadata: RuntimeMetadataPrefixed = Decode::decode(&mut metadata)?;
-		if metadata.1.version() >= 14 {
-			let meta = DesubMetadata::from_runtime_metadata(metadata.1)?;
-			self.current_metadata.insert(version, meta);
-		} else {
-			self.legacy_decoder.register_version(version, LegacyDesubMetadata::from_runtime_metadata(metadata.1)?)?;
+	/// Register a runtime version with the decoder. V14 and V15 metadata is decoded using `desub-current`,
+	/// and older metadata versions (V8 to V13) using `desub-legacy`.
+	pub fn register_version(&mut self, version: SpecVersion, metadata: &[u8]) -> Result<(), Error> {
+		match decode_metadata(metadata)? {
+			VersionedMetadata::Current(meta) => {
+				self.current_metadata.insert(version, *meta);
+			}
+			VersionedMetadata::Legacy(meta) => self.legacy_decoder.register_version(version, meta)?,
 		}
 		Ok(())
 	}
 
+	/// Compare the metadata registered for two runtime versions, to find out what changed between them. See
+	/// [`diff_metadata`] for details.
+	pub fn diff_versions(&self, old: SpecVersion, new: SpecVersion) -> Result<MetadataDiff, Error> {
+		Ok(diff::diff(self.metadata_ref(old)?, self.metadata_ref(new)?))
+	}
+
+	/// Set how account IDs are rendered in the JSON output for runtimes with V14+ metadata.
+	pub fn set_account_id_format(&mut self, format: AccountIdFormat) {
+		self.account_id_format = format;
+	}
+
+	/// Set the profile used to serialize decoded values into JSON for runtimes with V14+ metadata. For
+	/// instance, [`SerializeProfile::human_readable()`] renders bytes as hex and large numbers as strings.
+	pub fn set_serialize_profile(&mut self, profile: SerializeProfile) {
+		self.serialize_profile = profile;
+	}
+
 	pub fn decode_extrinsics(&self, version: SpecVersion, mut data: &[u8]) -> Result<Value, Error> {
 		if self.current_metadata.contains_key(&version) {
 			let metadata = self.current_metadata.get(&version).expect("Checked if key is contained; qed");
 			match decoder::decode_extrinsics(metadata, &mut data) {
-				Ok(v) => Ok(serde_json::to_value(&v)?),
+				Ok(v) => {
+					let v: Vec<_> = v.into_iter().map(|ext| self.format_extrinsic(metadata, ext)).collect();
+					Ok(serde_json::to_value(self.with_profile(metadata, &v))?)
+				}
 				Err((ext, e)) => {
 					Err(Error::V14 { source: e, ext: ext.into_iter().map(Extrinsic::into_owned).collect() })
 				}
@@ -120,7 +173,125 @@ impl Decoder {
 		}
 	}
 
+	/// Decode a SCALE encoded vector of extrinsics into JSON, like [`Decoder::decode_extrinsics`], but carry on
+	/// decoding after an extrinsic fails, and return a result for each one.
+	pub fn decode_extrinsics_resilient(
+		&self,
+		version: SpecVersion,
+		mut data: &[u8],
+	) -> Result<Vec<Result<Value, ExtrinsicDecodeFailure>>, Error> {
+		if let Some(metadata) = self.current_metadata.get(&version) {
+			let extrinsics = decoder::decode_extrinsics_resilient(metadata, &mut data).map_err(Error::Extrinsics)?;
+			let extrinsics =
+				extrinsics.into_iter().map(|ext| ext.map(|ext| self.format_extrinsic(metadata, ext))).collect();
+			extrinsics_to_json(
+				extrinsics,
+				|ext| Ok(serde_json::to_value(self.with_profile(metadata, ext))?),
+				Error::Extrinsics,
+			)
+		} else {
+			if !self.legacy_decoder.has_version(&version) {
+				return Err(Error::SpecVersionNotFound(version));
+			}
+			let extrinsics = self.legacy_decoder.decode_extrinsics_resilient(version, data)?;
+			extrinsics_to_json(extrinsics, |ext| Ok(serde_json::to_value(ext)?), Error::Legacy)
+		}
+	}
+
+	/// Decode a SCALE encoded block (a header followed by a vector of extrinsics) into JSON, with
+	/// the shape `{ "header": .., "extrinsics": [..] }`.
+	pub fn decode_block(&self, version: SpecVersion, mut data: &[u8]) -> Result<Value, Error> {
+		if let Some(metadata) = self.current_metadata.get(&version) {
+			let mut block = decoder::decode_block(metadata, &mut data).map_err(Error::Block)?;
+			block.extrinsics = block.extrinsics.into_iter().map(|ext| self.format_extrinsic(metadata, ext)).collect();
+			Ok(serde_json::to_value(self.with_profile(metadata, &block))?)
+		} else {
+			if !self.legacy_decoder.has_version(&version) {
+				return Err(Error::SpecVersionNotFound(version));
+			}
+			let header = decoder::decode_header(&mut data).map_err(Error::Block)?;
+			let ext = self.legacy_decoder.decode_extrinsics(version, data)?;
+			Ok(serde_json::json!({ "header": header, "extrinsics": ext }))
+		}
+	}
+
 	pub fn has_version(&self, version: &SpecVersion) -> bool {
 		self.current_metadata.contains_key(version) || self.legacy_decoder.has_version(version)
 	}
+
+	fn metadata_ref(&self, version: SpecVersion) -> Result<MetadataRef<'_>, Error> {
+		if let Some(metadata) = self.current_metadata.get(&version) {
+			Ok(MetadataRef::Current(metadata))
+		} else {
+			let metadata = self.legacy_decoder.get_version_metadata(version);
+			metadata.map(MetadataRef::Legacy).ok_or(Error::SpecVersionNotFound(version))
+		}
+	}
+
+	/// Wrap something decoded using V14+ metadata, so that it's serialized with our profile.
+	fn with_profile<'a, T>(&self, metadata: &'a DesubMetadata, item: &'a T) -> WithProfile<'a, T> {
+		WithProfile::new(item, self.serialize_profile, metadata.types())
+	}
+
+	fn format_extrinsic<'a>(&self, metadata: &DesubMetadata, ext: Extrinsic<'a>) -> Extrinsic<'a> {
+		let format = match self.account_id_format {
+			AccountIdFormat::Bytes => return ext,
+			AccountIdFormat::Rendered { ss58_prefix: Some(prefix) } => AccountFormat::new(prefix),
+			AccountIdFormat::Rendered { ss58_prefix: None } => AccountFormat::from_metadata(metadata),
+		};
+		format.format_extrinsic(ext, metadata)
+	}
+}
+
+/// Compare two SCALE encoded runtime metadatas (V8 or later), to find out which pallets, calls, events, storage
+/// entries and signed extensions were added, removed or changed between them. Calls and events are reported if
+/// their index or the types of their arguments changed, and storage entries if their hashers or the types of
+/// their keys or values changed.
+///
+/// Types in V14+ metadata are compared structurally, since type IDs aren't stable between runtime versions. Legacy
+/// metadata only names its types, and so they are compared by name, and not at all between legacy and V14+ metadata.
+pub fn diff_metadata(old: &[u8], new: &[u8]) -> Result<MetadataDiff, Error> {
+	let (old, new) = (decode_metadata(old)?, decode_metadata(new)?);
+	Ok(diff::diff(old.as_ref(), new.as_ref()))
+}
+
+/// Metadata decoded by whichever of `desub-current` and `desub-legacy` can handle its version.
+enum VersionedMetadata {
+	Current(Box<DesubMetadata>),
+	Legacy(LegacyDesubMetadata),
+}
+
+impl VersionedMetadata {
+	fn as_ref(&self) -> MetadataRef<'_> {
+		match self {
+			VersionedMetadata::Current(metadata) => MetadataRef::Current(metadata),
+			VersionedMetadata::Legacy(metadata) => MetadataRef::Legacy(metadata),
+		}
+	}
+}
+
+/// V14 and V15 metadata is decoded using `desub-current`, and older metadata versions using `desub-legacy`.
+fn decode_metadata(mut metadata: &[u8]) -> Result<VersionedMetadata, Error> {
+	let metadata: RuntimeMetadataPrefixed = Decode::decode(&mut metadata)?;
+	if metadata.1.version() >= 14 {
+		Ok(VersionedMetadata::Current(Box::new(DesubMetadata::from_runtime_metadata(metadata.1)?)))
+	} else {
+		Ok(VersionedMetadata::Legacy(LegacyDesubMetadata::from_runtime_metadata(metadata.1)?))
+	}
+}
+
+/// Serialize each extrinsic that was decoded, and convert the error in each failure into our [`Error`].
+fn extrinsics_to_json<T, E>(
+	extrinsics: Vec<Result<T, desub_common::ExtrinsicDecodeFailure<E>>>,
+	to_json: impl Fn(&T) -> Result<Value, Error>,
+	to_error: impl Fn(E) -> Error,
+) -> Result<Vec<Result<Value, ExtrinsicDecodeFailure>>, Error> {
+	let mut out = Vec::with_capacity(extrinsics.len());
+	for ext in extrinsics {
+		out.push(match ext {
+			Ok(ext) => Ok(to_json(&ext)?),
+			Err(failure) => Err(failure.map_err(&to_error)),
+		});
+	}
+	Ok(out)
 }
@@ -0,0 +1,124 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::Encode;
+use desub::{Chain, Decoder, SerializeProfile};
+use desub_legacy::{RustTypeMarker, TypeDetective};
+use frame_metadata::{
+	decode_different::{DecodeDifferent, FnEncode},
+	v8::{FunctionArgumentMetadata, FunctionMetadata, ModuleMetadata, RuntimeMetadataV8},
+	RuntimeMetadata, RuntimeMetadataPrefixed, META_RESERVED,
+};
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] =
+	include_bytes!("../../desub-current/tests/data/v14_metadata_polkadot.scale");
+
+/// V14 metadata doesn't need any legacy type definitions.
+#[derive(Debug, Clone)]
+struct NoTypes;
+
+impl TypeDetective for NoTypes {
+	fn get(&self, _: &str, _: u32, _: &str, _: &str) -> Option<&RustTypeMarker> {
+		None
+	}
+
+	fn try_fallback(&self, _: &str, _: &str) -> Option<&RustTypeMarker> {
+		None
+	}
+
+	fn get_extrinsic_ty(&self, _: &str, _: u32, _: &str) -> Option<&RustTypeMarker> {
+		None
+	}
+}
+
+/// A length prefixed, unsigned Auctions.bid extrinsic.
+const AUCTIONS_BID: &[u8] = &[0x20, 0x04, 0x48, 0x01, 0x04, 0x08, 0x0c, 0x10, 0x14];
+
+fn decoder() -> Decoder {
+	let mut decoder = Decoder::with_custom_types(NoTypes, Chain::Custom("polkadot".into()));
+	decoder.register_version(9110, V14_METADATA_POLKADOT_SCALE).expect("can register metadata");
+	decoder
+}
+
+#[test]
+fn decode_extrinsics_resilient_carries_on_after_failures() {
+	let decoder = decoder();
+
+	// Auctions.bid, then an unknown call, then Auctions.bid again.
+	let ext_bytes = [[0x0c].as_slice(), AUCTIONS_BID, &[0x0c, 0x04, 0xff, 0x00], AUCTIONS_BID].concat();
+	let extrinsics = decoder.decode_extrinsics_resilient(9110, &ext_bytes).expect("can decode extrinsics");
+
+	assert_eq!(extrinsics.len(), 3);
+	assert_eq!(extrinsics[0].as_ref().unwrap()["call_data"]["ty"]["name"], "bid");
+	assert_eq!(extrinsics[2].as_ref().unwrap()["call_data"]["ty"]["name"], "bid");
+	let failure = extrinsics[1].as_ref().unwrap_err();
+	assert_eq!((failure.index, &*failure.bytes), (1, &[0x04, 0xff, 0x00][..]));
+}
+
+// The count of extrinsics is read from the input, and shouldn't be trusted.
+#[test]
+fn decode_extrinsics_resilient_with_huge_count() {
+	let decoder = decoder();
+
+	// A count of u32::MAX, but only one Auctions.bid extrinsic.
+	let ext_bytes = [[0x03, 0xff, 0xff, 0xff, 0xff].as_slice(), AUCTIONS_BID].concat();
+	let extrinsics = decoder.decode_extrinsics_resilient(9110, &ext_bytes).expect("can decode extrinsics");
+
+	assert_eq!(extrinsics.len(), 1);
+	assert!(extrinsics[0].is_ok());
+}
+
+#[test]
+fn decode_extrinsics_with_a_serialize_profile() {
+	let mut decoder = decoder();
+	let ext_bytes = [[0x04].as_slice(), AUCTIONS_BID].concat();
+
+	let extrinsics = decoder.decode_extrinsics(9110, &ext_bytes).expect("can decode extrinsics");
+	assert_eq!(extrinsics[0]["call_data"]["arguments"][4], 5);
+
+	decoder.set_serialize_profile(SerializeProfile::human_readable());
+	let extrinsics = decoder.decode_extrinsics(9110, &ext_bytes).expect("can decode extrinsics");
+	// The amount bid is a u128:
+	assert_eq!(extrinsics[0]["call_data"]["arguments"][4], "5");
+	assert_eq!(extrinsics[0]["signature"], serde_json::Value::Null);
+}
+
+#[test]
+fn v8_metadata_can_be_registered() {
+	let module = ModuleMetadata {
+		name: DecodeDifferent::Encode("System"),
+		storage: None,
+		calls: Some(DecodeDifferent::Encode(FnEncode(|| {
+			&[FunctionMetadata {
+				name: DecodeDifferent::Encode("remark"),
+				arguments: DecodeDifferent::Encode(&[FunctionArgumentMetadata {
+					name: DecodeDifferent::Encode("remark"),
+					ty: DecodeDifferent::Encode("Vec<u8>"),
+				}]),
+				documentation: DecodeDifferent::Encode(&[]),
+			}]
+		}))),
+		event: None,
+		constants: DecodeDifferent::Encode(FnEncode(|| &[])),
+		errors: DecodeDifferent::Encode(FnEncode(|| &[])),
+	};
+	let modules = DecodeDifferent::Encode(Vec::leak(vec![module]) as &[_]);
+	let metadata = RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V8(RuntimeMetadataV8 { modules })).encode();
+
+	let mut decoder = Decoder::with_custom_types(NoTypes, Chain::Custom("kusama".into()));
+	decoder.register_version(1020, &metadata).expect("can register V8 metadata");
+	assert!(decoder.has_version(&1020));
+	assert!(decoder.diff_versions(1020, 1020).expect("can diff V8 metadata").is_empty());
+}
@@ -0,0 +1,254 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+// This file is part of substrate-desub.
+//
+// substrate-desub is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+// substrate-desub is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-desub.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::Encode;
+use desub::{diff_metadata, ItemChange, MetadataChange, StorageChange};
+use frame_metadata::{
+	v14::{
+		ExtrinsicMetadata, PalletCallMetadata, PalletMetadata, PalletStorageMetadata, RuntimeMetadataV14,
+		SignedExtensionMetadata, StorageEntryMetadata, StorageEntryModifier, StorageEntryType, StorageHasher,
+	},
+	RuntimeMetadataPrefixed,
+};
+use scale_info::{meta_type, MetaType, TypeInfo};
+use std::marker::PhantomData;
+
+static V14_METADATA_POLKADOT_SCALE: &[u8] =
+	include_bytes!("../../desub-current/tests/data/v14_metadata_polkadot.scale");
+static V10_METADATA_KUSAMA: &[u8] = include_bytes!("../../integration-tests/data/metadata_v10.bin");
+static V11_METADATA_KUSAMA: &[u8] = include_bytes!("../../integration-tests/data/metadata_v11.bin");
+
+#[test]
+fn identical_metadata_has_no_changes() {
+	assert!(diff_metadata(V14_METADATA_POLKADOT_SCALE, V14_METADATA_POLKADOT_SCALE).unwrap().is_empty());
+	assert!(diff_metadata(V11_METADATA_KUSAMA, V11_METADATA_KUSAMA).unwrap().is_empty());
+}
+
+#[test]
+fn legacy_metadata_changes() {
+	let diff = diff_metadata(V10_METADATA_KUSAMA, V11_METADATA_KUSAMA).unwrap();
+	let has = |change: MetadataChange| diff.changes.contains(&change);
+
+	assert!(has(MetadataChange::PalletRemoved { pallet: "Attestations".into() }));
+	assert!(has(MetadataChange::PalletIndexChanged { pallet: "Claims".into(), old: 21, new: 22 }));
+	assert!(has(MetadataChange::Call {
+		pallet: "Democracy".into(),
+		call: "delegate".into(),
+		change: ItemChange::IndexChanged { old: 15, new: 11 }
+	}));
+	assert!(has(MetadataChange::Event {
+		pallet: "Balances".into(),
+		event: "DustLost".into(),
+		change: ItemChange::Added
+	}));
+	assert!(has(MetadataChange::Storage {
+		pallet: "ImOnline".into(),
+		entry: "AuthoredBlocks".into(),
+		change: StorageChange::HashersChanged {
+			old: vec!["Blake2_256".into(), "Blake2_256".into()],
+			new: vec!["Twox64Concat".into(), "Twox64Concat".into()]
+		}
+	}));
+
+	let lines: Vec<String> = diff.changes.iter().map(|c| c.to_string()).collect();
+	assert!(lines.contains(
+		&"call `Democracy.vote` types changed: `vote` changed from `Vote` to `AccountVote<BalanceOf<T>>`".into()
+	));
+	assert!(
+		lines.contains(&"event `Council.Executed` types changed: `[0]` changed from `Hash` to `DispatchResult`".into())
+	);
+}
+
+// Legacy types are only known by name, and V14 types by their shape, so the two can't be compared.
+#[test]
+fn legacy_and_current_metadata_types_are_not_compared() {
+	let diff = diff_metadata(V11_METADATA_KUSAMA, V14_METADATA_POLKADOT_SCALE).unwrap();
+	assert!(!diff.is_empty());
+	for change in &diff.changes {
+		match change {
+			MetadataChange::Call { change: ItemChange::TypesChanged(_), .. }
+			| MetadataChange::Event { change: ItemChange::TypesChanged(_), .. }
+			| MetadataChange::SignedExtension { change: ItemChange::TypesChanged(_), .. }
+			| MetadataChange::Storage {
+				change: StorageChange::KeysChanged(_) | StorageChange::ValueChanged(_), ..
+			} => {
+				panic!("unexpected type change: {}", change)
+			}
+			_ => {}
+		}
+	}
+}
+
+mod old {
+	use scale_info::TypeInfo;
+
+	#[derive(TypeInfo)]
+	#[allow(dead_code, non_camel_case_types)]
+	pub enum SystemCall {
+		remark { remark: Vec<u8> },
+	}
+	#[derive(TypeInfo)]
+	#[allow(dead_code, non_camel_case_types)]
+	pub enum UtilityCall {
+		batch { calls: Vec<RuntimeCall> },
+	}
+	#[derive(TypeInfo)]
+	#[allow(dead_code)]
+	pub enum RuntimeCall {
+		System(SystemCall),
+		Utility(UtilityCall),
+	}
+	#[derive(TypeInfo)]
+	#[allow(dead_code)]
+	pub struct AccountInfo {
+		pub nonce: u32,
+		pub free: u64,
+	}
+}
+
+mod new {
+	use scale_info::TypeInfo;
+
+	#[derive(TypeInfo)]
+	#[allow(dead_code, non_camel_case_types)]
+	pub enum SystemCall {
+		remark { remark: Vec<u16> },
+		kill { key: u32 },
+	}
+	#[derive(TypeInfo)]
+	#[allow(dead_code, non_camel_case_types)]
+	pub enum UtilityCall {
+		batch { calls: Vec<RuntimeCall> },
+	}
+	#[derive(TypeInfo)]
+	#[allow(dead_code)]
+	pub enum RuntimeCall {
+		System(SystemCall),
+		Utility(UtilityCall),
+	}
+	#[derive(TypeInfo)]
+	#[allow(dead_code)]
+	pub struct AccountInfo {
+		pub account_nonce: u32,
+		pub free: u128,
+	}
+}
+
+#[derive(TypeInfo)]
+struct UncheckedExtrinsic<Call>(PhantomData<Call>);
+#[derive(TypeInfo)]
+struct Runtime;
+
+fn pallet(name: &'static str, index: u8, calls: Option<MetaType>) -> PalletMetadata {
+	PalletMetadata {
+		name,
+		storage: None,
+		calls: calls.map(|ty| PalletCallMetadata { ty }),
+		event: None,
+		constants: vec![],
+		error: None,
+		index,
+	}
+}
+
+fn account_storage(hasher: StorageHasher, value: MetaType) -> Option<PalletStorageMetadata> {
+	Some(PalletStorageMetadata {
+		prefix: "System",
+		entries: vec![StorageEntryMetadata {
+			name: "Account",
+			modifier: StorageEntryModifier::Default,
+			ty: StorageEntryType::Map { hashers: vec![hasher], key: meta_type::<[u8; 32]>(), value },
+			default: vec![],
+			docs: vec![],
+		}],
+	})
+}
+
+fn signed_extension(identifier: &'static str, ty: MetaType) -> SignedExtensionMetadata {
+	SignedExtensionMetadata { identifier, ty, additional_signed: meta_type::<()>() }
+}
+
+fn encode(pallets: Vec<PalletMetadata>, call: MetaType, signed_extensions: Vec<SignedExtensionMetadata>) -> Vec<u8> {
+	let extrinsic = ExtrinsicMetadata { ty: call, version: 4, signed_extensions };
+	let metadata = RuntimeMetadataV14::new(pallets, extrinsic, meta_type::<Runtime>());
+	RuntimeMetadataPrefixed::from(metadata).encode()
+}
+
+#[test]
+fn current_metadata_changes() {
+	let old = encode(
+		vec![
+			PalletMetadata {
+				storage: account_storage(StorageHasher::Blake2_128Concat, meta_type::<old::AccountInfo>()),
+				..pallet("System", 0, Some(meta_type::<old::SystemCall>()))
+			},
+			pallet("Utility", 1, Some(meta_type::<old::UtilityCall>())),
+			pallet("Claims", 2, None),
+		],
+		meta_type::<UncheckedExtrinsic<old::RuntimeCall>>(),
+		vec![signed_extension("CheckNonce", meta_type::<codec::Compact<u32>>())],
+	);
+	let new = encode(
+		vec![
+			PalletMetadata {
+				storage: account_storage(StorageHasher::Twox64Concat, meta_type::<new::AccountInfo>()),
+				..pallet("System", 0, Some(meta_type::<new::SystemCall>()))
+			},
+			pallet("Utility", 3, Some(meta_type::<new::UtilityCall>())),
+			pallet("Sudo", 2, None),
+		],
+		meta_type::<UncheckedExtrinsic<new::RuntimeCall>>(),
+		vec![
+			signed_extension("CheckMortality", meta_type::<u64>()),
+			signed_extension("CheckNonce", meta_type::<codec::Compact<u64>>()),
+		],
+	);
+
+	let diff = diff_metadata(&old, &new).unwrap();
+	let expected = [
+		"pallet `Claims` was removed",
+		"call `System.remark` types changed: `remark` changed from u8 to u16",
+		"call `System.kill` was added",
+		"storage entry `System.Account` hashers changed from [Blake2_128Concat] to [Twox64Concat]",
+		// `nonce` was also renamed, but that doesn't change how it's encoded.
+		"storage entry `System.Account` value type changed: `free` changed from u64 to u128",
+		"pallet `Utility` index changed from 1 to 3",
+		// The call type is recursive, and the change to `System.remark` is found within it.
+		"call `Utility.batch` types changed: `calls.System.remark.remark` changed from u8 to u16",
+		"pallet `Sudo` was added",
+		"signed extension `CheckNonce` index changed from 0 to 1",
+		"signed extension `CheckNonce` types changed: `ty` changed from u32 to u64",
+		"signed extension `CheckMortality` was added",
+	];
+	assert_eq!(diff.to_string().lines().collect::<Vec<_>>(), expected);
+}
+
+// Bit order types have no fields, and so the diff has to look at their names.
+#[test]
+fn bit_order_changes() {
+	use bitvec::{order, vec::BitVec};
+
+	let old =
+		encode(vec![], meta_type::<()>(), vec![signed_extension("CheckBits", meta_type::<BitVec<u8, order::Lsb0>>())]);
+	let new =
+		encode(vec![], meta_type::<()>(), vec![signed_extension("CheckBits", meta_type::<BitVec<u8, order::Msb0>>())]);
+
+	let diff = diff_metadata(&old, &new).unwrap();
+	assert_eq!(
+		diff.to_string(),
+		"signed extension `CheckBits` types changed: `ty` bit order changed from Lsb0 to Msb0\n"
+	);
+	assert!(diff_metadata(&old, &old).unwrap().is_empty());
+}